@@ -1,11 +1,18 @@
 use anyhow::Result;
 use ark_starknet::client::{StarknetClient, StarknetClientHttp};
-use tiny_stark::{
-    event_handler::EventHandler, storage::types::*, storage::Storage, Pontos, PontosConfig,
-};
 use async_trait::async_trait;
 use starknet::core::types::BlockId;
 use std::sync::Arc;
+use tiny_stark::{
+    analysis::{PricingConfig, SniperConfig, TrendingConfig},
+    event_handler::EventHandler,
+    health::HealthConfig,
+    managers::swap_manager::EKUBO_CORE_ADDRESS,
+    managers::{ContractCacheConfig, DEFAULT_MAX_REPLAY_ATTEMPTS},
+    storage::types::*,
+    storage::Storage,
+    Pontos, PontosConfig,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -19,6 +26,15 @@ async fn main() -> Result<()> {
     let config = PontosConfig {
         indexer_version: String::from("0.0.1"),
         indexer_identifier: "task_1234".to_string(),
+        contract_cache: ContractCacheConfig::default(),
+        memecoin_factories: vec![],
+        lock_managers: vec![],
+        ekubo_core: EKUBO_CORE_ADDRESS,
+        trending: TrendingConfig::default(),
+        sniper: SniperConfig::default(),
+        pricing: PricingConfig::default(),
+        max_replay_attempts: DEFAULT_MAX_REPLAY_ATTEMPTS,
+        health: HealthConfig::default(),
     };
 
     let pontos = Arc::new(Pontos::new(
@@ -186,6 +202,24 @@ impl Storage for DefaultStorage {
         })
     }
 
+    async fn set_failed_event(&self, event: &FailedEvent) -> Result<(), StorageError> {
+        log::trace!("Setting failed event {:?}", event);
+        Ok(())
+    }
+
+    async fn get_failed_event(&self, failure_id: &str) -> Result<FailedEvent, StorageError> {
+        log::trace!("Getting failed event {}", failure_id);
+        Err(StorageError::NotFound(failure_id.to_string()))
+    }
+
+    async fn get_failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> Result<Vec<FailedEvent>, StorageError> {
+        log::trace!("Getting failed events with status {:?}", status);
+        Ok(vec![])
+    }
+
     async fn clean_block(
         &self,
         _block_timestamp: u64,
@@ -200,7 +234,11 @@ impl Storage for DefaultStorage {
         event: &MemecoinCreatedEvent,
         block_timestamp: u64,
     ) -> Result<(), StorageError> {
-        log::trace!("Registering memecoin created event {:?} at {}", event, block_timestamp);
+        log::trace!(
+            "Registering memecoin created event {:?} at {}",
+            event,
+            block_timestamp
+        );
         Ok(())
-    } 
+    }
 }
@@ -0,0 +1,61 @@
+//! Records the requests of Pontos indexing a block range from a node
+//! into a fixture, replayed offline by the recorded blocks regression test.
+//!
+//! cargo run --example record_fixture --features testing -- \
+//!     <rpc_url> <from_block> <to_block> [fixture_path]
+use anyhow::{anyhow, Result};
+use ark_starknet::client::{StarknetClient, StarknetClientHttp};
+use starknet::core::types::BlockId;
+use std::sync::Arc;
+use tiny_stark::{
+    client::RecordingClient,
+    testing::{recorded, MemoryStorage, RecordingEventHandler},
+    Pontos,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        return Err(anyhow!(
+            "Usage: record_fixture <rpc_url> <from_block> <to_block> [fixture_path]"
+        ));
+    }
+
+    let from: u64 = args[2].parse()?;
+    let to: u64 = args[3].parse()?;
+    let path = args
+        .get(4)
+        .cloned()
+        .unwrap_or_else(|| recorded::MAINNET_FIXTURE.to_string());
+
+    let node = Arc::new(StarknetClientHttp::new(&args[1]).map_err(|e| anyhow!("{:?}", e))?);
+    let client = Arc::new(RecordingClient::wrap(node));
+    let storage = Arc::new(MemoryStorage::new());
+
+    let pontos = Pontos::new(
+        Arc::clone(&client),
+        Arc::clone(&storage),
+        Arc::new(RecordingEventHandler::new()),
+        recorded::config(),
+    );
+    pontos
+        .index_block_range(BlockId::Number(from), BlockId::Number(to), false)
+        .await
+        .map_err(|e| anyhow!("{}", e))?;
+
+    let data = storage.data().await;
+    println!(
+        "Recorded blocks {} to {}: {} memecoins, {} swaps, {} transfers",
+        from,
+        to,
+        data.memecoins.len(),
+        data.swaps.len(),
+        data.transfers.len()
+    );
+
+    client.save(&path).await.map_err(|e| anyhow!("{:?}", e))?;
+    println!("Fixture saved to {}", path);
+
+    Ok(())
+}
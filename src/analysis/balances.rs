@@ -0,0 +1,68 @@
+//! Fungible token balances computed from the indexed transfers.
+use crate::storage::types::TokenTransfer;
+use ark_starknet::format::to_hex_str;
+use num_bigint::BigInt;
+use starknet::core::types::FieldElement;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Returns the balance of each address having received or sent the token.
+/// The zero address is excluded, being the source of mints and the target
+/// of burns.
+pub fn balances_from_transfers(transfers: &[TokenTransfer]) -> HashMap<String, BigInt> {
+    let mut balances: HashMap<String, BigInt> = HashMap::new();
+    for t in transfers {
+        apply_transfer(&mut balances, t);
+    }
+    balances
+}
+
+/// Updates the balances with the transfer, as `balances_from_transfers`.
+pub fn apply_transfer(balances: &mut HashMap<String, BigInt>, transfer: &TokenTransfer) {
+    let zero = zero_address();
+    let amount = match BigInt::from_str(&transfer.amount) {
+        Ok(a) => a,
+        Err(_) => return,
+    };
+
+    if transfer.from_address != zero {
+        *balances.entry(transfer.from_address.clone()).or_default() -= &amount;
+    }
+
+    if transfer.to_address != zero {
+        *balances.entry(transfer.to_address.clone()).or_default() += &amount;
+    }
+}
+
+/// Returns the zero address, formatted as the indexed addresses are.
+pub fn zero_address() -> String {
+    to_hex_str(&FieldElement::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: &str) -> TokenTransfer {
+        TokenTransfer {
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: amount.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_balances_from_transfers() {
+        let zero = zero_address();
+        let balances = balances_from_transfers(&[
+            transfer(&zero, "0xa", "1000"),
+            transfer("0xa", "0xb", "300"),
+            transfer("0xb", &zero, "100"),
+        ]);
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances["0xa"], BigInt::from(700));
+        assert_eq!(balances["0xb"], BigInt::from(200));
+    }
+}
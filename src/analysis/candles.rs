@@ -0,0 +1,412 @@
+//! OHLCV candles of the memecoins, derived from the indexed swaps.
+//!
+//! Candles are updated incrementally for each new swap. When a block is
+//! cleaned, the candles containing it are rebuilt from the remaining swaps.
+//! Trades are also valued in USD, from the quote token price at their block.
+use crate::analysis::pricing::PriceOracle;
+use crate::storage::types::{Candle, CandleInterval, Pool, StorageError, SwapEvent};
+use crate::storage::Storage;
+use ark_starknet::client::StarknetClient;
+use num_bigint::BigInt;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// A swap seen from the memecoin: its price in the quote token,
+/// and the traded amounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub price: f64,
+    pub token_amount: BigInt,
+    pub quote_amount: BigInt,
+    /// Price of one memecoin in USD, 0 if unknown.
+    pub price_usd: f64,
+    /// Quote amount in USD, 0 if unknown.
+    pub volume_usd: f64,
+    pub block_timestamp: u64,
+}
+
+impl Trade {
+    /// Returns the trade of the swap, `None` if the swap doesn't
+    /// exchange the memecoin against the quote token of the pool.
+    pub fn from_swap(pool: &Pool, swap: &SwapEvent) -> Option<Self> {
+        let (token_amount, quote_amount) =
+            if swap.token_out == pool.memecoin_address && swap.token_in == pool.quote_token {
+                (&swap.amount_out, &swap.amount_in)
+            } else if swap.token_in == pool.memecoin_address && swap.token_out == pool.quote_token {
+                (&swap.amount_in, &swap.amount_out)
+            } else {
+                return None;
+            };
+
+        let token_amount = BigInt::from_str(token_amount).ok()?;
+        let quote_amount = BigInt::from_str(quote_amount).ok()?;
+
+        if token_amount == BigInt::from(0) {
+            return None;
+        }
+
+        Some(Trade {
+            price: to_f64(&quote_amount) / to_f64(&token_amount),
+            token_amount,
+            quote_amount,
+            price_usd: 0.0,
+            volume_usd: 0.0,
+            block_timestamp: swap.block_timestamp,
+        })
+    }
+
+    /// Values the trade in USD, the memecoin having 18 decimals.
+    pub fn with_usd(self, quote_price_usd: f64, quote_decimals: u32) -> Self {
+        let quote_unit = 10f64.powi(quote_decimals as i32);
+
+        Trade {
+            price_usd: self.price * 1e18 / quote_unit * quote_price_usd,
+            volume_usd: to_f64(&self.quote_amount) / quote_unit * quote_price_usd,
+            ..self
+        }
+    }
+}
+
+pub struct CandleAggregator<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    oracle: Arc<PriceOracle<S, C>>,
+}
+
+impl<S: Storage, C: StarknetClient> CandleAggregator<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, oracle: Arc<PriceOracle<S, C>>) -> Self {
+        Self { storage, oracle }
+    }
+
+    /// Adds the swap to the candles of every interval.
+    pub async fn add_swap(&self, pool: &Pool, swap: &SwapEvent) -> Result<(), StorageError> {
+        let trade = match self.oracle.trade(pool, swap).await? {
+            Some(t) => t,
+            None => {
+                warn!("Swap {} is not a memecoin trade", swap.event_id);
+                return Ok(());
+            }
+        };
+
+        for interval in CandleInterval::ALL {
+            let open_time = interval.open_time(swap.block_timestamp);
+
+            let candle = match self
+                .storage
+                .get_candle(&pool.memecoin_address, interval, open_time)
+                .await
+            {
+                Ok(c) => merge(c, &trade),
+                Err(StorageError::NotFound(_)) => {
+                    new_candle(&pool.memecoin_address, interval, open_time, &trade)
+                }
+                Err(e) => return Err(e),
+            };
+
+            self.storage.set_candle(&candle).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the swaps of the block, to be given to `rebuild` once
+    /// the block is cleaned.
+    pub async fn block_swaps(&self, block_timestamp: u64) -> Result<Vec<SwapEvent>, StorageError> {
+        self.storage.get_block_swaps(block_timestamp).await
+    }
+
+    /// Rebuilds the candles containing the block, for the memecoins traded
+    /// in the given swaps, from the swaps still in the storage on all the
+    /// pools of each memecoin.
+    pub async fn rebuild(
+        &self,
+        swaps: &[SwapEvent],
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let pool_addresses: HashSet<&String> = swaps.iter().map(|s| &s.pool_address).collect();
+
+        let all_pools = self.storage.get_pools().await?;
+        let memecoins: HashSet<&String> = all_pools
+            .iter()
+            .filter(|p| pool_addresses.contains(&p.pool_address))
+            .map(|p| &p.memecoin_address)
+            .collect();
+
+        for memecoin_address in memecoins {
+            let pools: Vec<&Pool> = all_pools
+                .iter()
+                .filter(|p| &p.memecoin_address == memecoin_address)
+                .collect();
+
+            for interval in CandleInterval::ALL {
+                let open_time = interval.open_time(block_timestamp);
+
+                let mut candle: Option<Candle> = None;
+                for pool in &pools {
+                    let swaps = self
+                        .storage
+                        .get_swaps(
+                            &pool.pool_address,
+                            open_time,
+                            open_time + interval.seconds() - 1,
+                        )
+                        .await?;
+
+                    for s in &swaps {
+                        if let Some(t) = self.oracle.trade(pool, s).await? {
+                            candle = Some(match candle {
+                                Some(c) => merge(c, &t),
+                                None => new_candle(memecoin_address, interval, open_time, &t),
+                            });
+                        }
+                    }
+                }
+
+                debug!(
+                    "Rebuilding {} candle of {} at {}: {:?}",
+                    interval, memecoin_address, open_time, candle
+                );
+
+                match candle {
+                    Some(c) => self.storage.set_candle(&c).await?,
+                    None => {
+                        self.storage
+                            .delete_candle(memecoin_address, interval, open_time)
+                            .await?
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn new_candle(
+    memecoin_address: &str,
+    interval: CandleInterval,
+    open_time: u64,
+    trade: &Trade,
+) -> Candle {
+    Candle {
+        memecoin_address: memecoin_address.to_string(),
+        interval,
+        open_time,
+        open: trade.price,
+        high: trade.price,
+        low: trade.price,
+        close: trade.price,
+        volume_token: trade.token_amount.to_string(),
+        volume_quote: trade.quote_amount.to_string(),
+        trades: 1,
+        close_usd: trade.price_usd,
+        volume_usd: trade.volume_usd,
+        first_trade_at: trade.block_timestamp,
+        last_trade_at: trade.block_timestamp,
+    }
+}
+
+/// Adds the trade to the candle. Trades may be merged out of order, the
+/// open and close being only replaced by an earlier or a later trade.
+fn merge(candle: Candle, trade: &Trade) -> Candle {
+    let add = |volume: &str, amount: &BigInt| {
+        (BigInt::from_str(volume).unwrap_or_default() + amount).to_string()
+    };
+
+    let is_first = trade.block_timestamp < candle.first_trade_at;
+    let is_last = trade.block_timestamp >= candle.last_trade_at;
+
+    Candle {
+        open: if is_first { trade.price } else { candle.open },
+        high: candle.high.max(trade.price),
+        low: candle.low.min(trade.price),
+        close: if is_last { trade.price } else { candle.close },
+        volume_token: add(&candle.volume_token, &trade.token_amount),
+        volume_quote: add(&candle.volume_quote, &trade.quote_amount),
+        trades: candle.trades + 1,
+        // Keeps the last known USD price.
+        close_usd: if is_last && trade.price_usd > 0.0 {
+            trade.price_usd
+        } else {
+            candle.close_usd
+        },
+        first_trade_at: candle.first_trade_at.min(trade.block_timestamp),
+        last_trade_at: candle.last_trade_at.max(trade.block_timestamp),
+        volume_usd: candle.volume_usd + trade.volume_usd,
+        ..candle
+    }
+}
+
+fn to_f64(v: &BigInt) -> f64 {
+    v.to_string().parse::<f64>().unwrap_or(f64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pricing::PricingConfig;
+    use crate::storage::types::LiquidityType;
+    use crate::testing::MemoryStorage;
+    use ark_starknet::client::MockStarknetClient;
+
+    fn pool() -> Pool {
+        Pool {
+            pool_address: "0x9a1".to_string(),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: "0x10".to_string(),
+            quote_token: "0x20".to_string(),
+            token0: "0x10".to_string(),
+            token1: "0x20".to_string(),
+            block_timestamp: 0,
+        }
+    }
+
+    fn swap(token_in: &str, token_out: &str, amount_in: &str, amount_out: &str) -> SwapEvent {
+        SwapEvent {
+            token_in: token_in.to_string(),
+            token_out: token_out.to_string(),
+            amount_in: amount_in.to_string(),
+            amount_out: amount_out.to_string(),
+            block_timestamp: 60,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trade_from_swap() {
+        let buy = Trade::from_swap(&pool(), &swap("0x20", "0x10", "10", "1000")).unwrap();
+        assert_eq!(buy.price, 0.01);
+        assert_eq!(buy.token_amount, BigInt::from(1000));
+
+        let sell = Trade::from_swap(&pool(), &swap("0x10", "0x20", "500", "10")).unwrap();
+        assert_eq!(sell.price, 0.02);
+        assert_eq!(sell.quote_amount, BigInt::from(10));
+
+        assert!(Trade::from_swap(&pool(), &swap("0x30", "0x10", "1", "1")).is_none());
+    }
+
+    #[test]
+    fn test_merge() {
+        let first = Trade::from_swap(&pool(), &swap("0x20", "0x10", "10", "1000")).unwrap();
+        let second = Trade::from_swap(&pool(), &swap("0x10", "0x20", "500", "20")).unwrap();
+
+        let candle = merge(
+            new_candle("0x10", CandleInterval::OneMinute, 60, &first),
+            &second,
+        );
+
+        assert_eq!(candle.open, 0.01);
+        assert_eq!(candle.high, 0.04);
+        assert_eq!(candle.low, 0.01);
+        assert_eq!(candle.close, 0.04);
+        assert_eq!(candle.volume_token, "1500");
+        assert_eq!(candle.volume_quote, "30");
+        assert_eq!(candle.trades, 2);
+        assert_eq!(candle.close_usd, 0.0);
+    }
+
+    #[test]
+    fn test_merge_out_of_order() {
+        let mut early = swap("0x20", "0x10", "10", "1000");
+        early.block_timestamp = 61;
+        let mut late = swap("0x10", "0x20", "500", "20");
+        late.block_timestamp = 65;
+        let mut middle = swap("0x20", "0x10", "30", "1000");
+        middle.block_timestamp = 63;
+
+        let trade = |s: &SwapEvent| Trade::from_swap(&pool(), s).unwrap();
+
+        // The latest trade is merged first.
+        let candle = [&middle, &early].iter().fold(
+            new_candle("0x10", CandleInterval::OneMinute, 60, &trade(&late)),
+            |c, s| merge(c, &trade(s)),
+        );
+
+        assert_eq!(candle.open, 0.01);
+        assert_eq!(candle.close, 0.04);
+        assert_eq!(candle.high, 0.04);
+        assert_eq!(candle.low, 0.01);
+        assert_eq!(candle.trades, 3);
+        assert_eq!((candle.first_trade_at, candle.last_trade_at), (61, 65));
+    }
+
+    #[test]
+    fn test_trade_with_usd() {
+        // 1000 memecoins bought for 10 USDC, with 6 decimals.
+        let trade = Trade::from_swap(
+            &pool(),
+            &swap("0x20", "0x10", "10000000", "1000000000000000000000"),
+        )
+        .unwrap()
+        .with_usd(1.0, 6);
+
+        assert!((trade.price_usd - 0.01).abs() < 1e-12);
+        assert!((trade.volume_usd - 10.0).abs() < 1e-9);
+
+        let candle = merge(
+            new_candle("0x10", CandleInterval::OneMinute, 60, &trade),
+            &Trade::from_swap(&pool(), &swap("0x10", "0x20", "500", "20")).unwrap(),
+        );
+        assert_eq!(candle.close_usd, trade.price_usd);
+        assert_eq!(candle.volume_usd, trade.volume_usd);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_memecoin_traded_in_two_pools() {
+        let storage = Arc::new(MemoryStorage::new());
+        let config = PricingConfig {
+            stablecoins: vec![],
+            quote_pools: vec![],
+            pragma: None,
+        };
+        let oracle = Arc::new(PriceOracle::new(
+            Arc::clone(&storage),
+            Arc::new(MockStarknetClient::default()),
+            &config,
+        ));
+        let aggregator = CandleAggregator::new(Arc::clone(&storage), oracle);
+
+        let jediswap = pool();
+        let ekubo = Pool {
+            pool_address: "0x9a2".to_string(),
+            exchange: LiquidityType::Ekubo,
+            ..pool()
+        };
+
+        let mut swaps = vec![];
+        for (i, (pool, amount_in)) in [(&jediswap, "10"), (&ekubo, "20"), (&ekubo, "30")]
+            .into_iter()
+            .enumerate()
+        {
+            storage.register_pool(pool, 0).await.unwrap();
+
+            let swap = SwapEvent {
+                event_id: format!("0x{}", i + 1),
+                pool_address: pool.pool_address.clone(),
+                block_number: i as u64 + 1,
+                block_timestamp: 60 + i as u64,
+                ..swap("0x20", "0x10", amount_in, "1000")
+            };
+            storage
+                .register_swap(&swap, swap.block_timestamp)
+                .await
+                .unwrap();
+            aggregator.add_swap(pool, &swap).await.unwrap();
+            swaps.push(swap);
+        }
+
+        // Only the JediSwap pool is traded in the rebuilt block, the
+        // candle keeping the trades of the Ekubo pool.
+        aggregator.rebuild(&swaps[..1], 60).await.unwrap();
+
+        let candle = storage
+            .get_candle("0x10", CandleInterval::OneMinute, 60)
+            .await
+            .unwrap();
+        assert_eq!(candle.trades, 3);
+        assert_eq!(candle.volume_quote, "60");
+        assert_eq!((candle.open, candle.close), (0.01, 0.03));
+    }
+}
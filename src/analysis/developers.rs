@@ -0,0 +1,333 @@
+//! Tracking of the wallets of the memecoins teams.
+//!
+//! The deployer is the owner given at the creation, and the team allocation
+//! recipients are the wallets minted a part of the initial supply. Any
+//! transfer out of those wallets is an activity, a sell if the memecoins
+//! are sent to one of its pools or to Ekubo core.
+use crate::analysis::balances::zero_address;
+use crate::storage::types::{
+    DeveloperActivity, DeveloperActivityKind, DeveloperRole, DeveloperWallet, MemecoinCreatedEvent,
+    Pool, StorageError, TokenTransfer,
+};
+use crate::storage::Storage;
+use ark_starknet::format::to_hex_str;
+use starknet::core::types::FieldElement;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+pub struct DeveloperTracker<S: Storage> {
+    storage: Arc<S>,
+    ekubo_core: String,
+    /// Developer wallets, keyed by memecoin, loaded from the storage
+    /// on the first transfer of the memecoin.
+    wallets: RwLock<HashMap<String, Vec<DeveloperWallet>>>,
+    /// Pool addresses, keyed by memecoin, `None` until loaded from the storage.
+    pools: RwLock<Option<HashMap<String, HashSet<String>>>>,
+}
+
+impl<S: Storage> DeveloperTracker<S> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, ekubo_core: FieldElement) -> Self {
+        Self {
+            storage,
+            ekubo_core: to_hex_str(&ekubo_core),
+            wallets: RwLock::new(HashMap::new()),
+            pools: RwLock::new(None),
+        }
+    }
+
+    /// Adds a pool registered after the pools were loaded, for the
+    /// transfers to it to be sells.
+    pub fn add_pool(&self, pool: &Pool) {
+        if let Some(pools) = self.pools.write().unwrap().as_mut() {
+            pools
+                .entry(pool.memecoin_address.clone())
+                .or_default()
+                .insert(pool.pool_address.clone());
+        }
+    }
+
+    /// Marks the owner of the created memecoin as its deployer.
+    pub async fn register_deployer(
+        &self,
+        created: &MemecoinCreatedEvent,
+        block_number: u64,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        self.register_wallet(DeveloperWallet {
+            memecoin_address: created.memecoin_address.clone(),
+            wallet_address: created.owner.clone(),
+            role: DeveloperRole::Deployer,
+            block_number,
+            block_timestamp,
+        })
+        .await
+    }
+
+    /// Marks the recipient of a mint as a team allocation recipient, or
+    /// registers the activity if the transfer is out of a developer wallet.
+    pub async fn on_transfer(
+        &self,
+        transfer: &TokenTransfer,
+    ) -> Result<Option<DeveloperActivity>, StorageError> {
+        let memecoin = &transfer.token_address;
+
+        if transfer.from_address == zero_address() {
+            // The supply not allocated to the team is minted to the memecoin.
+            if transfer.to_address != *memecoin {
+                self.register_wallet(DeveloperWallet {
+                    memecoin_address: memecoin.clone(),
+                    wallet_address: transfer.to_address.clone(),
+                    role: DeveloperRole::TeamAllocation,
+                    block_number: transfer.block_number,
+                    block_timestamp: transfer.block_timestamp,
+                })
+                .await?;
+            }
+
+            return Ok(None);
+        }
+
+        let roles = self.roles(memecoin, &transfer.from_address).await?;
+
+        let role = if roles.contains(&DeveloperRole::Deployer) {
+            DeveloperRole::Deployer
+        } else if roles.contains(&DeveloperRole::TeamAllocation) {
+            DeveloperRole::TeamAllocation
+        } else {
+            return Ok(None);
+        };
+
+        let is_sell = self.is_pool(memecoin, &transfer.to_address).await?;
+
+        let activity = DeveloperActivity {
+            memecoin_address: memecoin.clone(),
+            wallet_address: transfer.from_address.clone(),
+            role,
+            kind: if is_sell {
+                DeveloperActivityKind::Sell
+            } else {
+                DeveloperActivityKind::Transfer
+            },
+            to_address: transfer.to_address.clone(),
+            amount: transfer.amount.clone(),
+            transaction_hash: transfer.transaction_hash.clone(),
+            event_id: transfer.event_id.clone(),
+            block_number: transfer.block_number,
+            block_timestamp: transfer.block_timestamp,
+        };
+
+        debug!("Developer activity: {:?}", activity);
+
+        self.storage.register_developer_activity(&activity).await?;
+        Ok(Some(activity))
+    }
+
+    pub async fn wallets(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperWallet>, StorageError> {
+        self.storage.get_developer_wallets(memecoin_address).await
+    }
+
+    pub async fn history(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperActivity>, StorageError> {
+        self.storage
+            .get_developer_activities(memecoin_address)
+            .await
+    }
+
+    /// Returns the roles of the wallet for the memecoin, from the cache.
+    async fn roles(
+        &self,
+        memecoin_address: &str,
+        wallet_address: &str,
+    ) -> Result<Vec<DeveloperRole>, StorageError> {
+        if !self.wallets.read().unwrap().contains_key(memecoin_address) {
+            let wallets = self.storage.get_developer_wallets(memecoin_address).await?;
+            self.wallets
+                .write()
+                .unwrap()
+                .insert(memecoin_address.to_string(), wallets);
+        }
+
+        Ok(self.wallets.read().unwrap()[memecoin_address]
+            .iter()
+            .filter(|w| w.wallet_address == wallet_address)
+            .map(|w| w.role)
+            .collect())
+    }
+
+    /// Returns true if the address receives the memecoins sold: Ekubo core
+    /// or a pool of the memecoin.
+    async fn is_pool(&self, memecoin_address: &str, address: &str) -> Result<bool, StorageError> {
+        if address == self.ekubo_core {
+            return Ok(true);
+        }
+
+        if self.pools.read().unwrap().is_none() {
+            let mut pools: HashMap<String, HashSet<String>> = HashMap::new();
+            for p in self.storage.get_pools().await? {
+                pools
+                    .entry(p.memecoin_address)
+                    .or_default()
+                    .insert(p.pool_address);
+            }
+            *self.pools.write().unwrap() = Some(pools);
+        }
+
+        Ok(self
+            .pools
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|pools| pools.get(memecoin_address))
+            .map_or(false, |p| p.contains(address)))
+    }
+
+    /// Registers the wallet, a wallet already having the role being ignored.
+    async fn register_wallet(&self, wallet: DeveloperWallet) -> Result<(), StorageError> {
+        match self.storage.register_developer_wallet(&wallet).await {
+            Ok(()) | Err(StorageError::AlreadyExists(_)) => (),
+            Err(e) => return Err(e),
+        }
+
+        // Wallets not loaded yet are read from the storage with the new one.
+        if let Some(wallets) = self
+            .wallets
+            .write()
+            .unwrap()
+            .get_mut(&wallet.memecoin_address)
+        {
+            if !wallets
+                .iter()
+                .any(|w| w.wallet_address == wallet.wallet_address && w.role == wallet.role)
+            {
+                wallets.push(wallet);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::LiquidityType;
+    use crate::storage::MockStorage;
+
+    fn transfer(from: &str, to: &str) -> TokenTransfer {
+        TokenTransfer {
+            token_address: "0x10".to_string(),
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: "100".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn wallet(address: &str, role: DeveloperRole) -> DeveloperWallet {
+        DeveloperWallet {
+            memecoin_address: "0x10".to_string(),
+            wallet_address: address.to_string(),
+            role,
+            block_number: 1,
+            block_timestamp: 1000,
+        }
+    }
+
+    fn pool(address: &str) -> Pool {
+        Pool {
+            pool_address: address.to_string(),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: "0x10".to_string(),
+            quote_token: "0x20".to_string(),
+            token0: "0x10".to_string(),
+            token1: "0x20".to_string(),
+            block_timestamp: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mint_marks_team_allocation() {
+        let mut storage = MockStorage::default();
+        storage
+            .expect_register_developer_wallet()
+            .withf(|w| w.wallet_address == "0xa" && w.role == DeveloperRole::TeamAllocation)
+            .times(1)
+            .returning(|_| Box::pin(futures::future::ready(Ok(()))));
+
+        let tracker = DeveloperTracker::new(Arc::new(storage), FieldElement::ONE);
+
+        let zero = zero_address();
+        assert!(tracker
+            .on_transfer(&transfer(&zero, "0xa"))
+            .await
+            .unwrap()
+            .is_none());
+        // The supply minted to the memecoin itself.
+        assert!(tracker
+            .on_transfer(&transfer(&zero, "0x10"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_to_pool_is_a_sell() {
+        let mut storage = MockStorage::default();
+        // The wallets and the pools are loaded once.
+        storage
+            .expect_get_developer_wallets()
+            .times(1)
+            .returning(|_| {
+                Box::pin(futures::future::ready(Ok(vec![
+                    wallet("0xa", DeveloperRole::TeamAllocation),
+                    wallet("0xa", DeveloperRole::Deployer),
+                ])))
+            });
+        storage
+            .expect_get_pools()
+            .times(1)
+            .returning(|| Box::pin(futures::future::ready(Ok(vec![pool("0x9a1")]))));
+        storage
+            .expect_register_developer_activity()
+            .times(3)
+            .returning(|_| Box::pin(futures::future::ready(Ok(()))));
+
+        let tracker = DeveloperTracker::new(Arc::new(storage), FieldElement::ONE);
+
+        let sell = tracker
+            .on_transfer(&transfer("0xa", "0x9a1"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sell.kind, DeveloperActivityKind::Sell);
+        assert_eq!(sell.role, DeveloperRole::Deployer);
+
+        let sent = tracker
+            .on_transfer(&transfer("0xa", "0xb"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sent.kind, DeveloperActivityKind::Transfer);
+
+        // A pool registered once the pools are loaded.
+        tracker.add_pool(&pool("0x9a2"));
+        let sell = tracker
+            .on_transfer(&transfer("0xa", "0x9a2"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sell.kind, DeveloperActivityKind::Sell);
+
+        assert!(tracker
+            .on_transfer(&transfer("0xb", "0xc"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
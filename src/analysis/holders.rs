@@ -0,0 +1,283 @@
+//! Holders of a memecoin at a given block, computed from the indexed
+//! transfers, to be exported for airdrops and rewards.
+//!
+//! A sample of the balances can be verified against `balance_of` calls
+//! pinned at the same block.
+//!
+//! Only the transfers of the memecoins are indexed, so the holders of
+//! any other token are not available.
+use crate::analysis::balances::balances_from_transfers;
+use crate::storage::types::{BlockIndexingStatus, StorageError, TokenTransfer};
+use crate::storage::Storage;
+use ark_starknet::client::{StarknetClient, StarknetClientError};
+use ark_starknet::CairoU256;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use starknet::core::types::{BlockId, FieldElement};
+use starknet::macros::selector;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct Holder {
+    pub address: String,
+    /// Balance, as a decimal string.
+    pub balance: String,
+}
+
+/// Holders of a token at a block, ordered by decreasing balance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderSnapshot {
+    pub token_address: String,
+    pub block_number: u64,
+    pub holders: Vec<Holder>,
+}
+
+impl HolderSnapshot {
+    /// Returns the holders as CSV, with an `address,balance` header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("address,balance\n");
+        for h in &self.holders {
+            csv.push_str(&format!("{},{}\n", h.address, h.balance));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// A balance of the snapshot which doesn't match the on-chain one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceMismatch {
+    pub address: String,
+    pub indexed_balance: String,
+    /// `None` if the balance couldn't be fetched.
+    pub onchain_balance: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderVerification {
+    /// Number of holders checked.
+    pub checked: usize,
+    pub mismatches: Vec<BalanceMismatch>,
+}
+
+impl HolderVerification {
+    pub fn is_valid(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+pub struct HolderSnapshotter<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    client: Arc<C>,
+}
+
+impl<S: Storage, C: StarknetClient> HolderSnapshotter<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, client: Arc<C>) -> Self {
+        Self { storage, client }
+    }
+
+    /// Returns the holders of the memecoin at the given block included.
+    /// `NotFound` for a token which is not a memecoin, whose transfers are
+    /// not indexed, or for a block which is not indexed yet.
+    pub async fn snapshot(
+        &self,
+        token_address: &str,
+        block_number: u64,
+    ) -> Result<HolderSnapshot, StorageError> {
+        self.storage.get_memecoin(token_address).await?;
+
+        let indexed = self.storage.get_block_info(block_number).await?.status
+            == BlockIndexingStatus::Terminated;
+        if !indexed {
+            return Err(StorageError::NotFound(format!(
+                "block number {block_number} not indexed"
+            )));
+        }
+
+        let transfers = self
+            .storage
+            .get_token_transfers(token_address, Some(block_number))
+            .await?;
+
+        let holders = holders_from_transfers(&transfers);
+
+        debug!(
+            "{} holders of {} at block {}",
+            holders.len(),
+            token_address,
+            block_number
+        );
+
+        Ok(HolderSnapshot {
+            token_address: token_address.to_string(),
+            block_number,
+            holders,
+        })
+    }
+
+    /// Compares up to `sample_size` balances of the snapshot, spread over
+    /// the holders, with the balances returned by the token at the block.
+    pub async fn verify(
+        &self,
+        snapshot: &HolderSnapshot,
+        sample_size: usize,
+    ) -> Result<HolderVerification, StarknetClientError> {
+        let token = FieldElement::from_hex_be(&snapshot.token_address)
+            .map_err(|_| StarknetClientError::Other("Invalid token address".to_string()))?;
+
+        let holders = sample(&snapshot.holders, sample_size);
+        let mut mismatches = vec![];
+
+        for h in &holders {
+            let onchain_balance = match self.balance_of(token, h, snapshot.block_number).await {
+                Ok(b) => Some(b),
+                Err(e) => {
+                    warn!("Can't get balance of {}: {:?}", h.address, e);
+                    None
+                }
+            };
+
+            if onchain_balance.as_ref() != Some(&h.balance) {
+                mismatches.push(BalanceMismatch {
+                    address: h.address.clone(),
+                    indexed_balance: h.balance.clone(),
+                    onchain_balance,
+                });
+            }
+        }
+
+        Ok(HolderVerification {
+            checked: holders.len(),
+            mismatches,
+        })
+    }
+
+    async fn balance_of(
+        &self,
+        token: FieldElement,
+        holder: &Holder,
+        block_number: u64,
+    ) -> Result<String, StarknetClientError> {
+        let address = FieldElement::from_hex_be(&holder.address)
+            .map_err(|_| StarknetClientError::Other("Invalid holder address".to_string()))?;
+        let block = BlockId::Number(block_number);
+
+        let response = match self
+            .client
+            .call_contract(token, selector!("balance_of"), vec![address], block)
+            .await
+        {
+            Err(StarknetClientError::EntrypointNotFound(_)) => {
+                self.client
+                    .call_contract(token, selector!("balanceOf"), vec![address], block)
+                    .await?
+            }
+            r => r?,
+        };
+
+        if response.len() < 2 {
+            return Err(StarknetClientError::Other(
+                "Invalid balance response".to_string(),
+            ));
+        }
+
+        let balance = CairoU256 {
+            low: response[0]
+                .try_into()
+                .map_err(|_| StarknetClientError::Other("Invalid u256 low".to_string()))?,
+            high: response[1]
+                .try_into()
+                .map_err(|_| StarknetClientError::Other("Invalid u256 high".to_string()))?,
+        };
+
+        Ok(balance.to_decimal(false))
+    }
+}
+
+/// Returns the holders with a positive balance after the transfers,
+/// ordered by decreasing balance.
+pub fn holders_from_transfers(transfers: &[TokenTransfer]) -> Vec<Holder> {
+    let mut balances: Vec<(String, BigInt)> = balances_from_transfers(transfers)
+        .into_iter()
+        .filter(|(_, b)| *b > BigInt::from(0))
+        .collect();
+
+    // Ties are ordered by address, for the export to be reproducible.
+    balances.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    balances
+        .into_iter()
+        .map(|(address, balance)| Holder {
+            address,
+            balance: balance.to_string(),
+        })
+        .collect()
+}
+
+/// Returns up to `size` holders evenly spread over the list, so that
+/// both the largest and the smallest holders are checked.
+fn sample(holders: &[Holder], size: usize) -> Vec<&Holder> {
+    if size >= holders.len() {
+        return holders.iter().collect();
+    }
+    if size == 1 {
+        return vec![&holders[0]];
+    }
+
+    let last = holders.len() - 1;
+    (0..size).map(|i| &holders[i * last / (size - 1)]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holders(count: usize) -> Vec<Holder> {
+        (0..count)
+            .map(|i| Holder {
+                address: format!("0x{:x}", i + 1),
+                balance: ((count - i) * 100).to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sample() {
+        let holders = holders(10);
+
+        let sampled: Vec<&str> = sample(&holders, 3)
+            .iter()
+            .map(|h| h.address.as_str())
+            .collect();
+        assert_eq!(sampled, vec!["0x1", "0x5", "0xa"]);
+
+        let sampled: Vec<&str> = sample(&holders, 1)
+            .iter()
+            .map(|h| h.address.as_str())
+            .collect();
+        assert_eq!(sampled, vec!["0x1"]);
+
+        assert_eq!(sample(&holders, 20).len(), 10);
+        assert!(sample(&holders, 0).is_empty());
+    }
+
+    #[test]
+    fn test_export() {
+        let snapshot = HolderSnapshot {
+            token_address: "0x10".to_string(),
+            block_number: 5,
+            holders: holders(2),
+        };
+
+        assert_eq!(snapshot.to_csv(), "address,balance\n0x1,200\n0x2,100\n");
+
+        let json: HolderSnapshot = serde_json::from_str(&snapshot.to_json().unwrap()).unwrap();
+        assert_eq!(json, snapshot);
+    }
+}
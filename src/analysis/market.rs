@@ -0,0 +1,288 @@
+//! Market metrics of the memecoins: supplies, market cap, fully diluted
+//! valuation and pool liquidity in USD.
+//!
+//! The circulating supply excludes the memecoins not tradable yet: held by
+//! the memecoin itself before the launch, locked, or held by the team. The
+//! liquidity is the value of the memecoins in the pools, counted twice as
+//! the pools hold the same value of the quote token.
+//!
+//! The balances are loaded from the transfers once per memecoin, and then
+//! kept up to date with the transfers being indexed.
+use crate::analysis::balances::{apply_transfer, zero_address};
+use crate::analysis::pricing::PriceOracle;
+use crate::storage::types::{LiquidityType, MarketMetrics, StorageError, TokenTransfer};
+use crate::storage::Storage;
+use ark_starknet::client::StarknetClient;
+use ark_starknet::format::to_hex_str;
+use num_bigint::BigInt;
+use starknet::core::types::FieldElement;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Unit of the memecoins amounts, having 18 decimals.
+const MEMECOIN_UNIT: f64 = 1e18;
+
+/// Supplies of a memecoin, computed from its transfers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Supplies {
+    pub total: BigInt,
+    pub circulating: BigInt,
+}
+
+/// Balances of a memecoin, with the burned amount.
+#[derive(Debug, Default)]
+struct RunningBalances {
+    balances: HashMap<String, BigInt>,
+    burned: BigInt,
+}
+
+impl RunningBalances {
+    fn from_transfers(transfers: &[TokenTransfer]) -> Self {
+        let mut running = RunningBalances::default();
+        for t in transfers {
+            running.apply(t);
+        }
+        running
+    }
+
+    fn apply(&mut self, transfer: &TokenTransfer) {
+        if transfer.to_address == zero_address() {
+            self.burned += BigInt::from_str(&transfer.amount).unwrap_or_default();
+        }
+        apply_transfer(&mut self.balances, transfer);
+    }
+
+    fn supplies(&self, initial_supply: &BigInt, excluded: &HashSet<String>) -> Supplies {
+        let total = (initial_supply - &self.burned).max(BigInt::from(0));
+
+        let held: BigInt = self
+            .balances
+            .iter()
+            .filter(|(a, b)| excluded.contains(*a) && **b > BigInt::from(0))
+            .map(|(_, b)| b)
+            .sum();
+
+        Supplies {
+            circulating: (&total - held).max(BigInt::from(0)),
+            total,
+        }
+    }
+}
+
+pub struct MarketAnalyzer<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    oracle: Arc<PriceOracle<S, C>>,
+    ekubo_core: String,
+    /// Balances of the memecoins, keyed by memecoin, loaded by `update`.
+    balances: Mutex<HashMap<String, RunningBalances>>,
+}
+
+impl<S: Storage, C: StarknetClient> MarketAnalyzer<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, oracle: Arc<PriceOracle<S, C>>, ekubo_core: FieldElement) -> Self {
+        Self {
+            storage,
+            oracle,
+            ekubo_core: to_hex_str(&ekubo_core),
+            balances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Updates the balances of the memecoin with the indexed transfer.
+    /// Ignored until the balances are loaded by `update`.
+    pub fn on_transfer(&self, transfer: &TokenTransfer) {
+        if let Some(b) = self
+            .balances
+            .lock()
+            .unwrap()
+            .get_mut(&transfer.token_address)
+        {
+            b.apply(transfer);
+        }
+    }
+
+    /// Drops the balances, to be loaded again from the transfers once
+    /// some were removed from the storage, as when a block is reindexed.
+    pub fn reset(&self) {
+        self.balances.lock().unwrap().clear();
+    }
+
+    /// Returns the memecoins having a pool quoted in one of the tokens,
+    /// whose metrics are to be updated when the token price changes.
+    pub async fn memecoins_quoted_in(
+        &self,
+        quote_tokens: &HashSet<String>,
+    ) -> Result<HashSet<String>, StorageError> {
+        if quote_tokens.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        Ok(self
+            .storage
+            .get_pools()
+            .await?
+            .into_iter()
+            .filter(|p| quote_tokens.contains(&p.quote_token))
+            .map(|p| p.memecoin_address)
+            .collect())
+    }
+
+    /// Computes the metrics of the memecoin at the given block, which must
+    /// be the last processed one, and persists them. Returns `None` if the
+    /// address is not a known memecoin.
+    pub async fn update(
+        &self,
+        memecoin_address: &str,
+        block_number: u64,
+        block_timestamp: u64,
+    ) -> Result<Option<MarketMetrics>, StorageError> {
+        let memecoin = match self.storage.get_memecoin(memecoin_address).await {
+            Ok(m) => m,
+            Err(StorageError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let loaded = self.balances.lock().unwrap().contains_key(memecoin_address);
+        if !loaded {
+            let transfers = self
+                .storage
+                .get_token_transfers(memecoin_address, Some(block_number))
+                .await?;
+            self.balances.lock().unwrap().insert(
+                memecoin_address.to_string(),
+                RunningBalances::from_transfers(&transfers),
+            );
+        }
+
+        let mut excluded: HashSet<String> = self
+            .storage
+            .get_liquidity_locks(memecoin_address)
+            .await?
+            .into_iter()
+            .map(|l| l.lock_address)
+            .collect();
+        excluded.extend(
+            self.storage
+                .get_developer_wallets(memecoin_address)
+                .await?
+                .into_iter()
+                .map(|w| w.wallet_address),
+        );
+        excluded.insert(memecoin.memecoin_address.clone());
+
+        // Ekubo pools hold their tokens in the core contract.
+        let holders: HashSet<String> = self
+            .storage
+            .get_pools()
+            .await?
+            .into_iter()
+            .filter(|p| p.memecoin_address == memecoin_address)
+            .map(|p| match p.exchange {
+                LiquidityType::Ekubo => self.ekubo_core.clone(),
+                _ => p.pool_address,
+            })
+            .collect();
+
+        let initial_supply = BigInt::from_str(&memecoin.initial_supply).unwrap_or_default();
+        let (supplies, reserve) = {
+            let empty = RunningBalances::default();
+            let balances = self.balances.lock().unwrap();
+            let running = balances.get(memecoin_address).unwrap_or(&empty);
+
+            let reserve: BigInt = holders
+                .iter()
+                .filter_map(|h| running.balances.get(h))
+                .filter(|b| **b > BigInt::from(0))
+                .sum();
+            (running.supplies(&initial_supply, &excluded), reserve)
+        };
+
+        let price_usd = self
+            .oracle
+            .price_usd(memecoin_address, block_number)
+            .await?
+            .unwrap_or(0.0);
+        let value_usd = |amount: &BigInt| to_f64(amount) / MEMECOIN_UNIT * price_usd;
+
+        let metrics = MarketMetrics {
+            memecoin_address: memecoin_address.to_string(),
+            total_supply: supplies.total.to_string(),
+            circulating_supply: supplies.circulating.to_string(),
+            price_usd,
+            market_cap_usd: value_usd(&supplies.circulating),
+            fdv_usd: value_usd(&supplies.total),
+            liquidity_usd: 2.0 * value_usd(&reserve),
+            block_number,
+            block_timestamp,
+        };
+
+        debug!("Market metrics: {:?}", metrics);
+
+        self.storage.set_market_metrics(&metrics).await?;
+        Ok(Some(metrics))
+    }
+
+    pub async fn metrics(&self, memecoin_address: &str) -> Result<MarketMetrics, StorageError> {
+        self.storage.get_market_metrics(memecoin_address).await
+    }
+
+    /// Returns the metrics of the memecoins, the highest market cap first.
+    pub async fn top(&self, limit: u64) -> Result<Vec<MarketMetrics>, StorageError> {
+        self.storage.get_top_market_metrics(limit).await
+    }
+}
+
+/// Returns the supplies of the memecoin, the circulating one excluding
+/// the balances of the given addresses.
+pub fn supplies(
+    initial_supply: &BigInt,
+    transfers: &[TokenTransfer],
+    excluded: &HashSet<String>,
+) -> Supplies {
+    RunningBalances::from_transfers(transfers).supplies(initial_supply, excluded)
+}
+
+fn to_f64(v: &BigInt) -> f64 {
+    v.to_string().parse::<f64>().unwrap_or(f64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: &str) -> TokenTransfer {
+        TokenTransfer {
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: amount.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_supplies() {
+        let zero = zero_address();
+        let transfers = [
+            transfer(&zero, "0x10", "900"),
+            transfer(&zero, "0xdev", "100"),
+            // Launch, a part of the supply being locked.
+            transfer("0x10", "0x9a1", "800"),
+            transfer("0x10", "0x10c", "100"),
+            transfer("0x9a1", "0xa", "200"),
+            transfer("0xa", &zero, "50"),
+        ];
+
+        let excluded: HashSet<String> = ["0x10", "0x10c", "0xdev"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+
+        let supplies = supplies(&BigInt::from(1000), &transfers, &excluded);
+
+        assert_eq!(supplies.total, BigInt::from(950));
+        // The pool and 0xa.
+        assert_eq!(supplies.circulating, BigInt::from(750));
+    }
+}
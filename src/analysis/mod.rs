@@ -0,0 +1,21 @@
+//! Analysis of the indexed data.
+pub mod balances;
+pub mod candles;
+pub mod developers;
+pub mod holders;
+pub mod market;
+pub mod portfolio;
+pub mod pricing;
+pub mod safety;
+pub mod snipers;
+pub mod trending;
+
+pub use candles::CandleAggregator;
+pub use developers::DeveloperTracker;
+pub use holders::HolderSnapshotter;
+pub use market::MarketAnalyzer;
+pub use portfolio::PortfolioTracker;
+pub use pricing::{PriceOracle, PricingConfig};
+pub use safety::SafetyAnalyzer;
+pub use snipers::{SniperConfig, SniperDetector};
+pub use trending::{TrendingAnalyzer, TrendingConfig};
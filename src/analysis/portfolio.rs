@@ -0,0 +1,347 @@
+//! Positions and PnL of the wallets on the memecoins.
+//!
+//! Balances come from the memecoin transfers, and the cost basis from
+//! the wallet swaps, using the average cost method: buys increase the
+//! cost basis, and sells realize the difference with the average cost.
+//! Ekubo swaps are attributed to the recipient of the token bought: the
+//! hops of a multi-hop route, without a trader, are not in the cost basis.
+//! Positions are valued at the close of the latest daily candle, in the
+//! quote token and in USD.
+use crate::analysis::balances::zero_address;
+use crate::analysis::candles::Trade;
+use crate::storage::types::{
+    CandleInterval, Pool, PortfolioSnapshot, Position, StorageError, SwapEvent, TokenTransfer,
+};
+use crate::storage::Storage;
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+const DAY: u64 = 86_400;
+
+/// Unit of the memecoins balances, having 18 decimals.
+const MEMECOIN_UNIT: f64 = 1e18;
+
+/// Cost basis of a wallet on a memecoin.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostBasis {
+    /// Memecoins bought and not sold yet.
+    pub quantity: f64,
+    /// Quote paid for the memecoins not sold yet.
+    pub cost: f64,
+    pub realized_pnl: f64,
+}
+
+impl CostBasis {
+    pub fn buy(&mut self, trade: &Trade) {
+        self.quantity += to_f64(&trade.token_amount);
+        self.cost += to_f64(&trade.quote_amount);
+    }
+
+    /// Realizes the PnL of the sold memecoins. Memecoins received without
+    /// being bought are sold at no cost.
+    pub fn sell(&mut self, trade: &Trade) {
+        let amount = to_f64(&trade.token_amount);
+        let sold = amount.min(self.quantity);
+        let cost = self.average_cost() * sold;
+
+        self.realized_pnl += to_f64(&trade.quote_amount) - cost;
+        self.cost -= cost;
+        self.quantity -= sold;
+    }
+
+    pub fn average_cost(&self) -> f64 {
+        if self.quantity > 0.0 {
+            self.cost / self.quantity
+        } else {
+            0.0
+        }
+    }
+}
+
+pub struct PortfolioTracker<S: Storage> {
+    storage: Arc<S>,
+    /// Start of the day of the last processed block.
+    last_day: Mutex<Option<u64>>,
+}
+
+impl<S: Storage> PortfolioTracker<S> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>) -> Self {
+        Self {
+            storage,
+            last_day: Mutex::new(None),
+        }
+    }
+
+    /// Returns the positions of the wallet at the given timestamp.
+    pub async fn positions(
+        &self,
+        wallet_address: &str,
+        timestamp: u64,
+    ) -> Result<Vec<Position>, StorageError> {
+        let transfers = self
+            .storage
+            .get_wallet_transfers(wallet_address, timestamp)
+            .await?;
+        let swaps = self
+            .storage
+            .get_wallet_swaps(wallet_address, timestamp)
+            .await?;
+        let pools = self.storage.get_pools().await?;
+
+        let mut positions = compute_positions(wallet_address, &pools, &transfers, &swaps);
+
+        for p in positions.iter_mut() {
+            let (close, close_usd) = self
+                .storage
+                .get_candles(&p.memecoin_address, CandleInterval::OneDay, 0, timestamp)
+                .await?
+                .last()
+                .map(|c| (c.close, c.close_usd))
+                .unwrap_or((0.0, 0.0));
+
+            let balance = p.balance.parse::<f64>().unwrap_or(0.0);
+            p.last_price = close;
+            p.unrealized_pnl = (p.last_price - p.average_cost) * balance;
+            p.value_usd = balance / MEMECOIN_UNIT * close_usd;
+        }
+
+        Ok(positions)
+    }
+
+    /// Returns the snapshots of the wallet between the two days included.
+    pub async fn history(
+        &self,
+        wallet_address: &str,
+        from_day: u64,
+        to_day: u64,
+    ) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        self.storage
+            .get_portfolio_snapshots(wallet_address, from_day, to_day)
+            .await
+    }
+
+    /// To be called for each processed block: once a day is over, returns
+    /// that day, whose portfolios are to be snapshotted with `snapshot_day`.
+    /// Until a block is processed, the last day is the one of the previous
+    /// block in the storage, so that a restart doesn't miss a day.
+    pub async fn on_block(&self, block_number: u64, block_timestamp: u64) -> Option<u64> {
+        let day = block_timestamp - block_timestamp % DAY;
+        let mut last_day = self.last_day.lock().await;
+
+        let previous = match *last_day {
+            Some(d) => Some(d),
+            None => self.previous_block_day(block_number).await,
+        };
+
+        *last_day = Some(previous.map_or(day, |d| d.max(day)));
+        previous.filter(|d| day > *d)
+    }
+
+    async fn previous_block_day(&self, block_number: u64) -> Option<u64> {
+        if block_number == 0 {
+            return None;
+        }
+
+        match self.storage.get_block(block_number - 1).await {
+            Ok((timestamp, _)) => Some(timestamp - timestamp % DAY),
+            Err(StorageError::NotFound(_)) => None,
+            Err(e) => {
+                warn!("Can't get block {}: {:?}", block_number - 1, e);
+                None
+            }
+        }
+    }
+
+    /// Snapshots the portfolio, at the end of the day, of every wallet
+    /// active during the day.
+    pub async fn snapshot_day(&self, day: u64) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        let end = day + DAY - 1;
+        let zero = zero_address();
+        let mut snapshots = vec![];
+
+        for wallet in self.storage.get_active_wallets(day, end).await? {
+            if wallet == zero {
+                continue;
+            }
+
+            let snapshot = PortfolioSnapshot {
+                positions: self.positions(&wallet, end).await?,
+                wallet_address: wallet,
+                day,
+            };
+
+            self.storage.set_portfolio_snapshot(&snapshot).await?;
+            snapshots.push(snapshot);
+        }
+
+        debug!("{} portfolios snapshotted for day {}", snapshots.len(), day);
+
+        Ok(snapshots)
+    }
+}
+
+/// Computes the positions of the wallet, without valuation, ordered by memecoin.
+pub fn compute_positions(
+    wallet_address: &str,
+    pools: &[Pool],
+    transfers: &[TokenTransfer],
+    swaps: &[SwapEvent],
+) -> Vec<Position> {
+    let mut balances: BTreeMap<&str, BigInt> = BTreeMap::new();
+    for t in transfers {
+        let amount = BigInt::from_str(&t.amount).unwrap_or_default();
+        let balance = balances.entry(&t.token_address).or_default();
+
+        if t.to_address == wallet_address {
+            *balance += &amount;
+        }
+        if t.from_address == wallet_address {
+            *balance -= &amount;
+        }
+    }
+
+    let mut bases: BTreeMap<&str, CostBasis> = BTreeMap::new();
+    for s in swaps {
+        let pool = match pools.iter().find(|p| p.pool_address == s.pool_address) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        if let Some(t) = Trade::from_swap(pool, s) {
+            let basis = bases.entry(&pool.memecoin_address).or_default();
+            if s.token_out == pool.memecoin_address {
+                basis.buy(&t);
+            } else {
+                basis.sell(&t);
+            }
+
+            balances.entry(&pool.memecoin_address).or_default();
+        }
+    }
+
+    balances
+        .into_iter()
+        .map(|(memecoin, balance)| {
+            let basis = bases.remove(memecoin).unwrap_or_default();
+            Position {
+                wallet_address: wallet_address.to_string(),
+                memecoin_address: memecoin.to_string(),
+                balance: balance.to_string(),
+                average_cost: basis.average_cost(),
+                last_price: 0.0,
+                realized_pnl: basis.realized_pnl,
+                unrealized_pnl: 0.0,
+                value_usd: 0.0,
+            }
+        })
+        .collect()
+}
+
+fn to_f64(v: &BigInt) -> f64 {
+    v.to_string().parse::<f64>().unwrap_or(f64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::{BlockIndexingStatus, BlockInfo, LiquidityType};
+    use crate::testing::MemoryStorage;
+
+    fn pool() -> Pool {
+        Pool {
+            pool_address: "0x9a1".to_string(),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: "0x10".to_string(),
+            quote_token: "0x20".to_string(),
+            token0: "0x10".to_string(),
+            token1: "0x20".to_string(),
+            block_timestamp: 0,
+        }
+    }
+
+    fn swap(token_in: &str, token_out: &str, amount_in: &str, amount_out: &str) -> SwapEvent {
+        SwapEvent {
+            pool_address: "0x9a1".to_string(),
+            token_in: token_in.to_string(),
+            token_out: token_out.to_string(),
+            amount_in: amount_in.to_string(),
+            amount_out: amount_out.to_string(),
+            trader: "0xb".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn transfer(from: &str, to: &str, amount: &str) -> TokenTransfer {
+        TokenTransfer {
+            token_address: "0x10".to_string(),
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: amount.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_positions() {
+        let positions = compute_positions(
+            "0xb",
+            &[pool()],
+            &[
+                transfer("0x9a1", "0xb", "1000"),
+                transfer("0x9a1", "0xb", "1000"),
+                transfer("0xb", "0x9a1", "1000"),
+            ],
+            &[
+                // Buys 1000 at 0.01, then 1000 at 0.03.
+                swap("0x20", "0x10", "10", "1000"),
+                swap("0x20", "0x10", "30", "1000"),
+                // Sells 1000 at 0.05.
+                swap("0x10", "0x20", "1000", "50"),
+            ],
+        );
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].balance, "1000");
+        assert_eq!(positions[0].average_cost, 0.02);
+        assert_eq!(positions[0].realized_pnl, 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_last_day_from_previous_block() {
+        let storage = Arc::new(MemoryStorage::new());
+        storage
+            .set_block_info(
+                9,
+                DAY - 10,
+                BlockInfo {
+                    indexer_version: "0.0.1".to_string(),
+                    indexer_identifier: "test".to_string(),
+                    status: BlockIndexingStatus::Terminated,
+                    block_number: 9,
+                },
+            )
+            .await
+            .unwrap();
+
+        // Restarted on the next day, the previous day is over.
+        let tracker = PortfolioTracker::new(storage);
+        assert_eq!(tracker.on_block(10, DAY + 5).await, Some(0));
+        assert_eq!(tracker.on_block(11, DAY + 15).await, None);
+        assert_eq!(tracker.on_block(12, 2 * DAY).await, Some(DAY));
+    }
+
+    #[test]
+    fn test_sell_without_buy() {
+        let mut basis = CostBasis::default();
+        basis.sell(&Trade::from_swap(&pool(), &swap("0x10", "0x20", "1000", "50")).unwrap());
+
+        assert_eq!(basis.quantity, 0.0);
+        assert_eq!(basis.average_cost(), 0.0);
+        assert_eq!(basis.realized_pnl, 50.0);
+    }
+}
@@ -0,0 +1,626 @@
+//! USD prices of the tokens at a given block.
+//!
+//! Stablecoins are worth 1 USD. Other tokens are priced either by a Pragma
+//! oracle contract, or by the last swap of their pools: memecoins against
+//! their quote token, and quote tokens against stablecoins through the
+//! configured quote pools, whose swaps are indexed like the memecoin ones.
+//!
+//! The pools are loaded once, the ones registered later being added as
+//! they are traded. Known prices are cached by block until a swap of the
+//! block, which may change them, is indexed.
+use crate::analysis::candles::Trade;
+use crate::managers::swap_manager::{ekubo_pool_address, EkuboPoolParams};
+use crate::storage::types::{LiquidityType, Pool, StorageError, SwapEvent};
+use crate::storage::Storage;
+use ark_starknet::client::{StarknetClient, StarknetClientError};
+use ark_starknet::format::to_hex_str;
+use starknet::core::types::{BlockId, FieldElement};
+use starknet::macros::{felt, selector};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// USDC token on mainnet.
+pub const USDC_ADDRESS: FieldElement =
+    felt!("0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8");
+
+/// USDT token on mainnet.
+pub const USDT_ADDRESS: FieldElement =
+    felt!("0x068f5c6a61780768455de69077e07e89787839bf8166decfbf92b645209c0fb8");
+
+/// Pragma oracle on mainnet.
+pub const PRAGMA_ADDRESS: FieldElement =
+    felt!("0x02a85bd616f912537c50a49a4076db02c00b29b2cdc8a197ce92ed1837fa875b");
+
+/// Decimals of the tokens which are not stablecoins.
+const DEFAULT_DECIMALS: u32 = 18;
+
+/// Maximum number of pools crossed to reach a stablecoin.
+const MAX_HOPS: usize = 3;
+
+/// Maximum number of blocks whose prices are cached, the oldest
+/// being dropped once reached.
+const CACHE_BLOCKS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct Stablecoin {
+    pub address: FieldElement,
+    pub decimals: u32,
+}
+
+/// A pool pricing a quote token against a stablecoin.
+#[derive(Debug, Clone)]
+pub struct QuotePool {
+    pub exchange: LiquidityType,
+    pub token: FieldElement,
+    pub stablecoin: FieldElement,
+    /// Pair contract, required for JediSwap.
+    pub jediswap_pair: Option<FieldElement>,
+    /// Pool parameters, required for Ekubo.
+    pub ekubo_pool: Option<EkuboPoolParams>,
+}
+
+impl QuotePool {
+    /// Returns the pool to be indexed, the token being priced
+    /// as a memecoin against the stablecoin.
+    pub fn to_pool(&self) -> Option<Pool> {
+        let (token0, token1) = if self.token < self.stablecoin {
+            (self.token, self.stablecoin)
+        } else {
+            (self.stablecoin, self.token)
+        };
+
+        let pool_address = match self.exchange {
+            LiquidityType::JediSwap => self.jediswap_pair?,
+            LiquidityType::Ekubo => ekubo_pool_address(token0, token1, self.ekubo_pool.as_ref()?),
+            LiquidityType::StarkDeFi | LiquidityType::Unknown => return None,
+        };
+
+        Some(Pool {
+            pool_address: to_hex_str(&pool_address),
+            exchange: self.exchange.clone(),
+            memecoin_address: to_hex_str(&self.token),
+            quote_token: to_hex_str(&self.stablecoin),
+            token0: to_hex_str(&token0),
+            token1: to_hex_str(&token1),
+            block_timestamp: 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PragmaConfig {
+    pub address: FieldElement,
+    /// Tokens priced by the oracle, with their pair id (like `ETH/USD`
+    /// as a short string).
+    pub pairs: Vec<(FieldElement, FieldElement)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PricingConfig {
+    pub stablecoins: Vec<Stablecoin>,
+    pub quote_pools: Vec<QuotePool>,
+    /// Oracle used for the tokens it prices, before the pools.
+    pub pragma: Option<PragmaConfig>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        PricingConfig {
+            stablecoins: vec![
+                Stablecoin {
+                    address: USDC_ADDRESS,
+                    decimals: 6,
+                },
+                Stablecoin {
+                    address: USDT_ADDRESS,
+                    decimals: 6,
+                },
+            ],
+            quote_pools: vec![],
+            pragma: None,
+        }
+    }
+}
+
+impl PricingConfig {
+    /// Returns the pools of the quote tokens, whose swaps are indexed
+    /// but which are not registered with the memecoin pools.
+    pub fn pools(&self) -> Vec<Pool> {
+        self.quote_pools
+            .iter()
+            .filter_map(|p| p.to_pool())
+            .collect()
+    }
+}
+
+pub struct PriceOracle<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    client: Arc<C>,
+    /// Decimals of the stablecoins, keyed by address.
+    stablecoins: HashMap<String, u32>,
+    /// Pragma address, and pair ids keyed by token address.
+    pragma: Option<(FieldElement, HashMap<String, FieldElement>)>,
+    /// Pools of the quote tokens, not in the storage.
+    quote_pools: Vec<Pool>,
+    /// Pools with the quote pools, keyed by the token they price,
+    /// `None` until loaded from the storage.
+    pools: RwLock<Option<HashMap<String, Vec<Pool>>>>,
+    /// Known prices keyed by block, then by token.
+    cache: Mutex<BTreeMap<u64, HashMap<String, f64>>>,
+}
+
+impl<S: Storage, C: StarknetClient> PriceOracle<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, client: Arc<C>, config: &PricingConfig) -> Self {
+        Self {
+            storage,
+            client,
+            stablecoins: config
+                .stablecoins
+                .iter()
+                .map(|s| (to_hex_str(&s.address), s.decimals))
+                .collect(),
+            pragma: config.pragma.as_ref().map(|p| {
+                (
+                    p.address,
+                    p.pairs
+                        .iter()
+                        .map(|(token, pair)| (to_hex_str(token), *pair))
+                        .collect(),
+                )
+            }),
+            quote_pools: config.pools(),
+            pools: RwLock::new(None),
+            cache: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds a pool registered after the pools were loaded.
+    pub fn add_pool(&self, pool: &Pool) {
+        if let Some(pools) = self.pools.write().unwrap().as_mut() {
+            let token_pools = pools.entry(pool.memecoin_address.clone()).or_default();
+            if !token_pools
+                .iter()
+                .any(|p| p.pool_address == pool.pool_address)
+            {
+                token_pools.push(pool.clone());
+            }
+        }
+    }
+
+    /// Drops the prices cached from the block, as a swap of the block may
+    /// change the price of its tokens and of the tokens priced through them.
+    pub fn invalidate(&self, block_number: u64) {
+        self.cache.lock().unwrap().split_off(&block_number);
+    }
+
+    /// Returns the decimals of the token, the default ones
+    /// if it's not a stablecoin.
+    pub fn decimals(&self, token_address: &str) -> u32 {
+        self.stablecoins
+            .get(token_address)
+            .cloned()
+            .unwrap_or(DEFAULT_DECIMALS)
+    }
+
+    /// Returns the USD price of one token (not of its smallest unit)
+    /// at the given block, `None` if it can't be priced.
+    pub async fn price_usd(
+        &self,
+        token_address: &str,
+        block_number: u64,
+    ) -> Result<Option<f64>, StorageError> {
+        if let Some(price) = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&block_number)
+            .and_then(|prices| prices.get(token_address))
+        {
+            return Ok(Some(*price));
+        }
+
+        let key = (token_address.to_string(), block_number);
+
+        let mut token = token_address.to_string();
+        // USD price of the token being priced, for one `token`.
+        let mut factor = 1.0;
+
+        for _ in 0..MAX_HOPS {
+            if self.stablecoins.contains_key(&token) {
+                return Ok(self.cache_price(key, Some(factor)));
+            }
+
+            if let Some(pair) = self.pragma_pair(&token) {
+                return match self.pragma_price(pair, block_number).await {
+                    Ok(p) => Ok(self.cache_price(key, p.map(|p| p * factor))),
+                    // Not cached, the oracle may answer later.
+                    Err(e) => {
+                        warn!("Can't get Pragma price of {}: {:?}", token, e);
+                        Ok(None)
+                    }
+                };
+            }
+
+            match self.pool_price(&token, block_number).await? {
+                Some((quote, price)) => {
+                    factor *= price;
+                    token = quote;
+                }
+                None => break,
+            }
+        }
+
+        Ok(self.cache_price(key, None))
+    }
+
+    /// Returns the trade of the swap, valued in USD if the quote
+    /// token price is known at the swap block.
+    pub async fn trade(
+        &self,
+        pool: &Pool,
+        swap: &SwapEvent,
+    ) -> Result<Option<Trade>, StorageError> {
+        let trade = match Trade::from_swap(pool, swap) {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+
+        Ok(Some(
+            match self.price_usd(&pool.quote_token, swap.block_number).await? {
+                Some(p) => trade.with_usd(p, self.decimals(&pool.quote_token)),
+                None => trade,
+            },
+        ))
+    }
+
+    /// Returns the quote token of the most recent swap of the token
+    /// up to the block, with the token price in that quote token.
+    async fn pool_price(
+        &self,
+        token_address: &str,
+        block_number: u64,
+    ) -> Result<Option<(String, f64)>, StorageError> {
+        let mut last: Option<(Pool, SwapEvent)> = None;
+
+        for pool in self.token_pools(token_address).await? {
+            let swap = match self
+                .storage
+                .get_last_swap(&pool.pool_address, block_number)
+                .await
+            {
+                Ok(s) => s,
+                Err(StorageError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+
+            if last
+                .as_ref()
+                .map_or(true, |(_, s)| swap.block_number > s.block_number)
+            {
+                last = Some((pool, swap));
+            }
+        }
+
+        Ok(last.and_then(|(pool, swap)| {
+            let trade = Trade::from_swap(&pool, &swap)?;
+            let scale =
+                self.decimals(token_address) as i32 - self.decimals(&pool.quote_token) as i32;
+
+            Some((pool.quote_token, trade.price * 10f64.powi(scale)))
+        }))
+    }
+
+    /// Returns the pools pricing the token, loading the pools once.
+    async fn token_pools(&self, token_address: &str) -> Result<Vec<Pool>, StorageError> {
+        let loaded = self.pools.read().unwrap().is_some();
+        if !loaded {
+            let mut pools: HashMap<String, Vec<Pool>> = HashMap::new();
+            for p in self
+                .quote_pools
+                .iter()
+                .cloned()
+                .chain(self.storage.get_pools().await?)
+            {
+                pools.entry(p.memecoin_address.clone()).or_default().push(p);
+            }
+            self.pools.write().unwrap().get_or_insert(pools);
+        }
+
+        Ok(self
+            .pools
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|pools| pools.get(token_address))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn pragma_pair(&self, token_address: &str) -> Option<(FieldElement, FieldElement)> {
+        let (address, pairs) = self.pragma.as_ref()?;
+        pairs.get(token_address).map(|pair| (*address, *pair))
+    }
+
+    /// Reads the median spot price of the pair, returned with its decimals.
+    async fn pragma_price(
+        &self,
+        (address, pair): (FieldElement, FieldElement),
+        block_number: u64,
+    ) -> Result<Option<f64>, StarknetClientError> {
+        // `DataType::SpotEntry(pair)`.
+        let response = self
+            .client
+            .call_contract(
+                address,
+                selector!("get_data_median"),
+                vec![FieldElement::ZERO, pair],
+                BlockId::Number(block_number),
+            )
+            .await?;
+
+        if response.len() < 2 {
+            return Err(StarknetClientError::Other(
+                "Invalid Pragma response".to_string(),
+            ));
+        }
+
+        let price: u128 = response[0]
+            .try_into()
+            .map_err(|_| StarknetClientError::Other("Invalid Pragma price".to_string()))?;
+        let decimals: u32 = response[1]
+            .try_into()
+            .map_err(|_| StarknetClientError::Other("Invalid Pragma decimals".to_string()))?;
+
+        // No source has a price for the pair.
+        if price == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(price as f64 / 10f64.powi(decimals as i32)))
+    }
+
+    /// Caches the price if known, a token without price
+    /// being possibly priced later at the same block.
+    fn cache_price(&self, key: (String, u64), price: Option<f64>) -> Option<f64> {
+        debug!("USD price of {} at block {}: {:?}", key.0, key.1, price);
+
+        if let Some(p) = price {
+            let mut cache = self.cache.lock().unwrap();
+            cache.entry(key.1).or_default().insert(key.0, p);
+            if cache.len() > CACHE_BLOCKS {
+                cache.pop_first();
+            }
+        }
+
+        price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockStorage;
+    use crate::testing::MemoryStorage;
+    use ark_starknet::client::MockStarknetClient;
+
+    fn address(v: u64) -> String {
+        to_hex_str(&FieldElement::from(v))
+    }
+
+    fn pool(pool_address: &str, memecoin: &str, quote: &str) -> Pool {
+        Pool {
+            pool_address: pool_address.to_string(),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: memecoin.to_string(),
+            quote_token: quote.to_string(),
+            token0: memecoin.to_string(),
+            token1: quote.to_string(),
+            block_timestamp: 0,
+        }
+    }
+
+    fn config() -> PricingConfig {
+        PricingConfig {
+            stablecoins: vec![Stablecoin {
+                address: FieldElement::from(0x5dc_u64),
+                decimals: 6,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_through_quote_pool() {
+        let (memecoin, eth, usdc) = (address(0x10), address(0xe7), address(0x5dc));
+
+        let mut storage = MockStorage::default();
+        let (m, e) = (memecoin.clone(), eth.clone());
+        // The quote pool is not in the storage, whose pools are loaded once.
+        storage
+            .expect_get_pools()
+            .times(1)
+            .returning(move || Box::pin(futures::future::ready(Ok(vec![pool("0x9a1", &m, &e)]))));
+        storage
+            .expect_get_last_swap()
+            .times(2)
+            .returning(move |pool_address, _| {
+                Box::pin(futures::future::ready(Ok(if pool_address == "0x9a1" {
+                    // 1000 memecoins bought for 0.001 ETH.
+                    SwapEvent {
+                        pool_address: pool_address.to_string(),
+                        token_in: address(0xe7),
+                        token_out: address(0x10),
+                        amount_in: "1000000000000000".to_string(),
+                        amount_out: "1000000000000000000000".to_string(),
+                        ..Default::default()
+                    }
+                } else {
+                    // 1 ETH sold for 2000 USDC.
+                    SwapEvent {
+                        pool_address: pool_address.to_string(),
+                        token_in: address(0xe7),
+                        token_out: address(0x5dc),
+                        amount_in: "1000000000000000000".to_string(),
+                        amount_out: "2000000000".to_string(),
+                        ..Default::default()
+                    }
+                })))
+            });
+
+        let mut config = config();
+        config.quote_pools = vec![QuotePool {
+            exchange: LiquidityType::JediSwap,
+            token: FieldElement::from(0xe7_u64),
+            stablecoin: FieldElement::from(0x5dc_u64),
+            jediswap_pair: Some(FieldElement::from(0x9a2_u64)),
+            ekubo_pool: None,
+        }];
+
+        let oracle = PriceOracle::new(
+            Arc::new(storage),
+            Arc::new(MockStarknetClient::default()),
+            &config,
+        );
+
+        let price = oracle.price_usd(&memecoin, 5).await.unwrap().unwrap();
+        assert!((price - 0.002).abs() < 1e-12);
+
+        // Cached for the block.
+        assert_eq!(oracle.price_usd(&memecoin, 5).await.unwrap(), Some(price));
+        assert_eq!(oracle.price_usd(&usdc, 5).await.unwrap(), Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_prices_invalidated_by_swaps() {
+        let (memecoin, usdc) = (address(0x10), address(0x5dc));
+        let storage = Arc::new(MemoryStorage::new());
+        let oracle = PriceOracle::new(
+            Arc::clone(&storage),
+            Arc::new(MockStarknetClient::default()),
+            &config(),
+        );
+
+        // 1000 memecoins bought for the amount of USDC, with 6 decimals.
+        let buy = |pool_address: &str, amount_in: &str, block_number: u64| SwapEvent {
+            event_id: format!("{pool_address}{block_number}"),
+            pool_address: pool_address.to_string(),
+            token_in: usdc.clone(),
+            token_out: memecoin.clone(),
+            amount_in: amount_in.to_string(),
+            amount_out: "1000000000000000000000".to_string(),
+            block_number,
+            ..Default::default()
+        };
+
+        storage
+            .register_pool(&pool("0x9a1", &memecoin, &usdc), 0)
+            .await
+            .unwrap();
+        // Not traded yet, and not cached.
+        assert_eq!(oracle.price_usd(&memecoin, 5).await.unwrap(), None);
+
+        storage
+            .register_swap(&buy("0x9a1", "1000000", 5), 0)
+            .await
+            .unwrap();
+        assert!((oracle.price_usd(&memecoin, 5).await.unwrap().unwrap() - 0.001).abs() < 1e-12);
+        assert!((oracle.price_usd(&memecoin, 6).await.unwrap().unwrap() - 0.001).abs() < 1e-12);
+
+        // A pool registered once the pools are loaded.
+        let pool = pool("0x9a2", &memecoin, &usdc);
+        storage.register_pool(&pool, 0).await.unwrap();
+        storage
+            .register_swap(&buy("0x9a2", "2000000", 6), 0)
+            .await
+            .unwrap();
+        oracle.add_pool(&pool);
+
+        // Until the swap is indexed, the price of the block is cached.
+        assert!((oracle.price_usd(&memecoin, 6).await.unwrap().unwrap() - 0.001).abs() < 1e-12);
+        oracle.invalidate(6);
+        assert!((oracle.price_usd(&memecoin, 6).await.unwrap().unwrap() - 0.002).abs() < 1e-12);
+        assert!((oracle.price_usd(&memecoin, 5).await.unwrap().unwrap() - 0.001).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_pragma_price() {
+        let mut client = MockStarknetClient::default();
+        client
+            .expect_call_contract()
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(vec![
+                    FieldElement::from(350_000_000_000_u64),
+                    FieldElement::from(8_u64),
+                ])
+            });
+
+        let eth = FieldElement::from(0xe7_u64);
+        let mut config = config();
+        config.pragma = Some(PragmaConfig {
+            address: PRAGMA_ADDRESS,
+            pairs: vec![(eth, FieldElement::ONE)],
+        });
+
+        let oracle = PriceOracle::new(Arc::new(MockStorage::default()), Arc::new(client), &config);
+
+        assert_eq!(
+            oracle.price_usd(&address(0xe7), 5).await.unwrap(),
+            Some(3500.0)
+        );
+        assert_eq!(
+            oracle.price_usd(&address(0xe7), 5).await.unwrap(),
+            Some(3500.0)
+        );
+    }
+
+    #[test]
+    fn test_quote_pool() {
+        let token = FieldElement::from(0xe7_u64);
+        let stablecoin = FieldElement::from(0x5dc_u64);
+
+        let params = EkuboPoolParams {
+            fee: FieldElement::from(0x20c49ba5e353f80000000000000000_u128),
+            tick_spacing: FieldElement::from(1000_u64),
+            extension: FieldElement::ZERO,
+        };
+
+        let pool = QuotePool {
+            exchange: LiquidityType::Ekubo,
+            token,
+            stablecoin,
+            jediswap_pair: None,
+            ekubo_pool: Some(params),
+        }
+        .to_pool()
+        .unwrap();
+
+        assert_eq!(pool.memecoin_address, to_hex_str(&token));
+        assert_eq!(pool.token0, to_hex_str(&token));
+        assert_eq!(
+            pool.pool_address,
+            to_hex_str(&ekubo_pool_address(token, stablecoin, &params))
+        );
+
+        assert!(QuotePool {
+            exchange: LiquidityType::JediSwap,
+            token,
+            stablecoin,
+            jediswap_pair: None,
+            ekubo_pool: None,
+        }
+        .to_pool()
+        .is_none());
+        assert!(QuotePool {
+            exchange: LiquidityType::Ekubo,
+            token,
+            stablecoin,
+            jediswap_pair: None,
+            ekubo_pool: None,
+        }
+        .to_pool()
+        .is_none());
+    }
+}
@@ -0,0 +1,499 @@
+//! Safety evaluation of the memecoins, from the indexed on-chain state.
+//!
+//! Each criterion is evaluated independently, and the score is the
+//! share of the points obtained: a passing criterion gives all its
+//! points, a warning half of them, and a failure none.
+//!
+//! As for the market metrics, the balances are loaded from the transfers
+//! once per memecoin and then kept up to date with the indexed transfers.
+use crate::analysis::balances::{apply_transfer, balances_from_transfers, zero_address};
+use crate::storage::types::{
+    LiquidityType, MemecoinInfo, Pool, SafetyCriterion, SafetyCriterionKind, SafetyReport,
+    SafetyStatus, StorageError, TokenTransfer,
+};
+use crate::storage::Storage;
+use ark_starknet::client::{StarknetClient, StarknetClientError};
+use ark_starknet::format::to_hex_str;
+use num_bigint::BigInt;
+use starknet::core::types::{BlockId, FieldElement};
+use starknet::macros::selector;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+
+const DAY: u64 = 86_400;
+
+/// Number of holders taken into account for the concentration.
+const TOP_HOLDERS: usize = 10;
+
+/// Blocks before the owner of a memecoin is fetched again, unless
+/// the ownership is renounced which is final.
+const OWNER_REFRESH_BLOCKS: u64 = 100;
+
+pub struct SafetyAnalyzer<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    client: Arc<C>,
+    /// Ekubo core, holding the tokens of all the Ekubo pools.
+    ekubo_core: String,
+    /// Balances of the memecoins, keyed by memecoin, loaded by `evaluate`.
+    balances: Mutex<HashMap<String, HashMap<String, BigInt>>>,
+    /// Pool addresses, keyed by memecoin, loaded once.
+    pools: RwLock<Option<HashMap<String, HashSet<String>>>>,
+    /// Owner of the memecoins with the block it was fetched at.
+    owners: Mutex<HashMap<FieldElement, (Option<FieldElement>, u64)>>,
+}
+
+impl<S: Storage, C: StarknetClient> SafetyAnalyzer<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, client: Arc<C>, ekubo_core: FieldElement) -> Self {
+        Self {
+            storage,
+            client,
+            ekubo_core: to_hex_str(&ekubo_core),
+            balances: Mutex::new(HashMap::new()),
+            pools: RwLock::new(None),
+            owners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Updates the balances of the memecoin with the indexed transfer.
+    /// Ignored until the balances are loaded by `evaluate`.
+    pub fn on_transfer(&self, transfer: &TokenTransfer) {
+        if let Some(b) = self
+            .balances
+            .lock()
+            .unwrap()
+            .get_mut(&transfer.token_address)
+        {
+            apply_transfer(b, transfer);
+        }
+    }
+
+    /// Drops the balances, to be loaded again from the transfers once
+    /// some were removed from the storage, as when a block is reindexed.
+    pub fn reset(&self) {
+        self.balances.lock().unwrap().clear();
+    }
+
+    /// Adds a pool registered after the pools were loaded.
+    pub fn add_pool(&self, pool: &Pool) {
+        if let Some(pools) = self.pools.write().unwrap().as_mut() {
+            pools
+                .entry(pool.memecoin_address.clone())
+                .or_default()
+                .insert(pool.pool_address.clone());
+        }
+    }
+
+    /// Evaluates the memecoin at the given block, and persists the report.
+    pub async fn evaluate(
+        &self,
+        memecoin_address: &str,
+        block_number: u64,
+        block_timestamp: u64,
+    ) -> Result<SafetyReport, StorageError> {
+        let memecoin = self.storage.get_memecoin(memecoin_address).await?;
+
+        let criteria = vec![
+            team_allocation(&memecoin),
+            self.liquidity_lock(&memecoin).await?,
+            self.holder_concentration(&memecoin, block_number).await?,
+            self.ownership(&memecoin, block_number).await,
+        ];
+
+        let report = SafetyReport {
+            memecoin_address: memecoin_address.to_string(),
+            score: score(&criteria),
+            criteria,
+            block_number,
+            block_timestamp,
+        };
+
+        debug!("Safety report: {:?}", report);
+
+        self.storage.set_safety_report(&report).await?;
+        Ok(report)
+    }
+
+    async fn liquidity_lock(
+        &self,
+        memecoin: &MemecoinInfo,
+    ) -> Result<SafetyCriterion, StorageError> {
+        let kind = SafetyCriterionKind::LiquidityLock;
+
+        let launch = match &memecoin.launch {
+            Some(l) => l,
+            None => return Ok(criterion(kind, SafetyStatus::Fail, "Not launched yet")),
+        };
+
+        match launch.liquidity_type {
+            LiquidityType::Ekubo => Ok(criterion(
+                kind,
+                SafetyStatus::Pass,
+                "Liquidity position is locked forever in Ekubo",
+            )),
+            LiquidityType::JediSwap | LiquidityType::StarkDeFi => {
+                let pair = match &launch.locked_liquidity {
+                    Some(p) => p,
+                    None => return Ok(criterion(kind, SafetyStatus::Warn, "Unknown pair")),
+                };
+
+                let unlock_time = self
+                    .storage
+                    .get_liquidity_locks(pair)
+                    .await?
+                    .iter()
+                    .map(|l| l.unlock_time)
+                    .max();
+
+                Ok(match unlock_time {
+                    Some(t) => lock_duration_criterion(t.saturating_sub(launch.block_timestamp)),
+                    None => criterion(kind, SafetyStatus::Fail, "Liquidity is not locked"),
+                })
+            }
+            LiquidityType::Unknown => Ok(criterion(
+                kind,
+                SafetyStatus::Warn,
+                "Unknown liquidity type",
+            )),
+        }
+    }
+
+    async fn holder_concentration(
+        &self,
+        memecoin: &MemecoinInfo,
+        block_number: u64,
+    ) -> Result<SafetyCriterion, StorageError> {
+        let address = &memecoin.memecoin_address;
+        let loaded = self.balances.lock().unwrap().contains_key(address);
+        if !loaded {
+            let transfers = self
+                .storage
+                .get_token_transfers(address, Some(block_number))
+                .await?;
+            self.balances
+                .lock()
+                .unwrap()
+                .insert(address.clone(), balances_from_transfers(&transfers));
+        }
+
+        // The pools and the memecoin itself are not holders. Ekubo pools
+        // hold their tokens in the core contract, and their locked
+        // liquidity is a position id, not an address.
+        let mut excluded = self.pools(address).await?;
+        excluded.insert(zero_address());
+        excluded.insert(memecoin.memecoin_address.clone());
+        excluded.insert(self.ekubo_core.clone());
+        if let Some(launch) = &memecoin.launch {
+            if let (LiquidityType::JediSwap | LiquidityType::StarkDeFi, Some(pair)) =
+                (&launch.liquidity_type, &launch.locked_liquidity)
+            {
+                excluded.insert(pair.clone());
+            }
+        }
+
+        let mut balances: Vec<BigInt> = self
+            .balances
+            .lock()
+            .unwrap()
+            .get(address)
+            .into_iter()
+            .flatten()
+            .filter(|(a, b)| !excluded.contains(*a) && **b > BigInt::from(0))
+            .map(|(_, b)| b.clone())
+            .collect();
+
+        let supply = match BigInt::from_str(&memecoin.initial_supply) {
+            Ok(s) if s > BigInt::from(0) => s,
+            _ => {
+                return Ok(criterion(
+                    SafetyCriterionKind::HolderConcentration,
+                    SafetyStatus::Warn,
+                    "Unknown supply",
+                ))
+            }
+        };
+
+        balances.sort_by(|a, b| b.cmp(a));
+        let top: BigInt = balances.iter().take(TOP_HOLDERS).sum();
+
+        Ok(concentration_criterion(percent(&top, &supply)))
+    }
+
+    async fn ownership(&self, memecoin: &MemecoinInfo, block_number: u64) -> SafetyCriterion {
+        let kind = SafetyCriterionKind::OwnershipRenounced;
+
+        let address = match FieldElement::from_hex_be(&memecoin.memecoin_address) {
+            Ok(a) => a,
+            Err(_) => return criterion(kind, SafetyStatus::Warn, "Invalid memecoin address"),
+        };
+
+        match self.owner(address, block_number).await {
+            Ok(Some(owner)) if owner == FieldElement::ZERO => {
+                criterion(kind, SafetyStatus::Pass, "Ownership is renounced")
+            }
+            Ok(Some(owner)) => criterion(
+                kind,
+                SafetyStatus::Warn,
+                &format!("Owned by {}", to_hex_str(&owner)),
+            ),
+            Ok(None) => criterion(kind, SafetyStatus::Warn, "Unknown owner"),
+            Err(e) => {
+                warn!("Can't get owner of {}: {:?}", memecoin.memecoin_address, e);
+                criterion(kind, SafetyStatus::Warn, "Unknown owner")
+            }
+        }
+    }
+
+    /// Returns the owner of the memecoin, from the cache if it was fetched
+    /// less than `OWNER_REFRESH_BLOCKS` ago or renounced.
+    async fn owner(
+        &self,
+        address: FieldElement,
+        block_number: u64,
+    ) -> Result<Option<FieldElement>, StarknetClientError> {
+        if let Some((owner, fetched_at)) = self.owners.lock().unwrap().get(&address).copied() {
+            if owner == Some(FieldElement::ZERO) || block_number < fetched_at + OWNER_REFRESH_BLOCKS
+            {
+                return Ok(owner);
+            }
+        }
+
+        let owner = self
+            .client
+            .call_contract(
+                address,
+                selector!("owner"),
+                vec![],
+                BlockId::Number(block_number),
+            )
+            .await?
+            .first()
+            .copied();
+
+        self.owners
+            .lock()
+            .unwrap()
+            .insert(address, (owner, block_number));
+        Ok(owner)
+    }
+
+    /// Returns the pool addresses of the memecoin.
+    async fn pools(&self, memecoin_address: &str) -> Result<HashSet<String>, StorageError> {
+        if self.pools.read().unwrap().is_none() {
+            let mut pools: HashMap<String, HashSet<String>> = HashMap::new();
+            for p in self.storage.get_pools().await? {
+                pools
+                    .entry(p.memecoin_address)
+                    .or_default()
+                    .insert(p.pool_address);
+            }
+            *self.pools.write().unwrap() = Some(pools);
+        }
+
+        Ok(self
+            .pools
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|pools| pools.get(memecoin_address))
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Returns the score (0 to 100) of the criteria.
+pub fn score(criteria: &[SafetyCriterion]) -> u8 {
+    if criteria.is_empty() {
+        return 0;
+    }
+
+    let points: usize = criteria
+        .iter()
+        .map(|c| match c.status {
+            SafetyStatus::Pass => 2,
+            SafetyStatus::Warn => 1,
+            SafetyStatus::Fail => 0,
+        })
+        .sum();
+
+    (points * 100 / (criteria.len() * 2)) as u8
+}
+
+fn team_allocation(memecoin: &MemecoinInfo) -> SafetyCriterion {
+    let kind = SafetyCriterionKind::TeamAllocation;
+
+    let allocation = memecoin
+        .launch
+        .as_ref()
+        .and_then(|l| l.team_allocation.as_ref())
+        .and_then(|a| BigInt::from_str(a).ok());
+
+    match (allocation, BigInt::from_str(&memecoin.initial_supply)) {
+        (Some(a), Ok(s)) if s > BigInt::from(0) => allocation_criterion(percent(&a, &s)),
+        _ => criterion(kind, SafetyStatus::Warn, "Unknown team allocation"),
+    }
+}
+
+fn allocation_criterion(percent: f64) -> SafetyCriterion {
+    let status = if percent <= 5.0 {
+        SafetyStatus::Pass
+    } else if percent <= 10.0 {
+        SafetyStatus::Warn
+    } else {
+        SafetyStatus::Fail
+    };
+
+    criterion(
+        SafetyCriterionKind::TeamAllocation,
+        status,
+        &format!("Team holds {:.2}% of the supply", percent),
+    )
+}
+
+fn lock_duration_criterion(duration: u64) -> SafetyCriterion {
+    let status = if duration >= 180 * DAY {
+        SafetyStatus::Pass
+    } else if duration >= 30 * DAY {
+        SafetyStatus::Warn
+    } else {
+        SafetyStatus::Fail
+    };
+
+    criterion(
+        SafetyCriterionKind::LiquidityLock,
+        status,
+        &format!("Liquidity is locked for {} days", duration / DAY),
+    )
+}
+
+fn concentration_criterion(percent: f64) -> SafetyCriterion {
+    let status = if percent <= 30.0 {
+        SafetyStatus::Pass
+    } else if percent <= 50.0 {
+        SafetyStatus::Warn
+    } else {
+        SafetyStatus::Fail
+    };
+
+    criterion(
+        SafetyCriterionKind::HolderConcentration,
+        status,
+        &format!(
+            "Top {} holders own {:.2}% of the supply",
+            TOP_HOLDERS, percent
+        ),
+    )
+}
+
+/// Returns `part` as a percentage of `total`, with a 0.01 precision.
+pub(crate) fn percent(part: &BigInt, total: &BigInt) -> f64 {
+    let basis_points: BigInt = part * BigInt::from(10_000) / total;
+    basis_points.to_string().parse::<f64>().unwrap_or(f64::MAX) / 100.0
+}
+
+fn criterion(kind: SafetyCriterionKind, status: SafetyStatus, reason: &str) -> SafetyCriterion {
+    SafetyCriterion {
+        kind,
+        status,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockStorage;
+    use ark_starknet::client::MockStarknetClient;
+
+    fn transfer(from: &str, to: &str, amount: &str) -> TokenTransfer {
+        TokenTransfer {
+            token_address: "0x1".to_string(),
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: amount.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_running_balances_and_owner() {
+        let mut storage = MockStorage::default();
+        storage.expect_get_memecoin().returning(|address| {
+            Box::pin(futures::future::ready(Ok(MemecoinInfo {
+                memecoin_address: address.to_string(),
+                owner: String::new(),
+                name: String::new(),
+                symbol: String::new(),
+                initial_supply: "1000".to_string(),
+                created_at: 0,
+                launch: None,
+            })))
+        });
+        // The transfers, the pools and the owner are loaded once.
+        storage
+            .expect_get_token_transfers()
+            .times(1)
+            .returning(|_, _| {
+                Box::pin(futures::future::ready(Ok(vec![transfer(
+                    &zero_address(),
+                    "0xa",
+                    "1000",
+                )])))
+            });
+        storage
+            .expect_get_pools()
+            .times(1)
+            .returning(|| Box::pin(futures::future::ready(Ok(vec![]))));
+        storage
+            .expect_set_safety_report()
+            .returning(|_| Box::pin(futures::future::ready(Ok(()))));
+
+        let mut client = MockStarknetClient::default();
+        client
+            .expect_call_contract()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![FieldElement::ZERO]));
+
+        let analyzer = SafetyAnalyzer::new(Arc::new(storage), Arc::new(client), FieldElement::ONE);
+
+        let concentration = |r: &SafetyReport| r.criteria[2].status;
+        let report = analyzer.evaluate("0x1", 1, 1000).await.unwrap();
+        assert_eq!(concentration(&report), SafetyStatus::Fail);
+        assert_eq!(report.criteria[3].status, SafetyStatus::Pass);
+
+        // Spread among 20 holders, each holding 5%.
+        for i in 0..20 {
+            analyzer.on_transfer(&transfer("0xa", &format!("0x{:x}", 0x100 + i), "50"));
+        }
+        let report = analyzer.evaluate("0x1", 2, 1010).await.unwrap();
+        assert_eq!(concentration(&report), SafetyStatus::Warn);
+        assert_eq!(report.criteria[3].status, SafetyStatus::Pass);
+    }
+
+    #[test]
+    fn test_score() {
+        let criteria = vec![
+            allocation_criterion(2.0),
+            lock_duration_criterion(60 * DAY),
+            concentration_criterion(80.0),
+            criterion(
+                SafetyCriterionKind::OwnershipRenounced,
+                SafetyStatus::Pass,
+                "",
+            ),
+        ];
+
+        assert_eq!(criteria[1].status, SafetyStatus::Warn);
+        assert_eq!(criteria[2].status, SafetyStatus::Fail);
+        // 2 + 1 + 0 + 2 points out of 8.
+        assert_eq!(score(&criteria), 62);
+        assert_eq!(score(&[]), 0);
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(&BigInt::from(1234), &BigInt::from(10_000)), 12.34);
+    }
+}
@@ -0,0 +1,220 @@
+//! Detection of the wallets sniping a memecoin launch.
+//!
+//! A sniper is a wallet having received the memecoin in the first blocks
+//! of the launch. Snipers funded from the same source are clustered, as
+//! they are likely controlled by the same entity buying in bundles.
+//!
+//! The funding of the wallets is only recorded for the configured funding
+//! tokens, none by default as every transfer of these tokens is then read.
+//! The funder of a wallet is the sender of the first transfer indexed since
+//! the start block, which may not be its first funding ever.
+use crate::analysis::balances::{balances_from_transfers, zero_address};
+use crate::analysis::safety::percent;
+use crate::storage::types::{Sniper, SniperReport, StorageError};
+use crate::storage::Storage;
+use num_bigint::BigInt;
+use starknet::core::types::FieldElement;
+use starknet::macros::felt;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::debug;
+
+/// ETH token on mainnet.
+pub const ETH_ADDRESS: FieldElement =
+    felt!("0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7");
+
+/// STRK token on mainnet.
+pub const STRK_ADDRESS: FieldElement =
+    felt!("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
+
+/// Tokens funding the wallets on mainnet, to cluster the snipers.
+pub const MAINNET_FUNDING_TOKENS: [FieldElement; 2] = [ETH_ADDRESS, STRK_ADDRESS];
+
+#[derive(Debug, Clone)]
+pub struct SniperConfig {
+    /// Number of blocks, from the launch block included, in which
+    /// receiving the memecoin is considered sniping.
+    pub window_blocks: u64,
+    /// Tokens whose first transfer to a wallet is its funding. Empty by
+    /// default, each transfer of these tokens being a storage write for
+    /// a wallet not funded yet: see `MAINNET_FUNDING_TOKENS`.
+    pub funding_tokens: Vec<FieldElement>,
+}
+
+impl Default for SniperConfig {
+    fn default() -> Self {
+        SniperConfig {
+            window_blocks: 3,
+            funding_tokens: vec![],
+        }
+    }
+}
+
+pub struct SniperDetector<S: Storage> {
+    storage: Arc<S>,
+    window_blocks: u64,
+}
+
+impl<S: Storage> SniperDetector<S> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, config: &SniperConfig) -> Self {
+        Self {
+            storage,
+            window_blocks: config.window_blocks,
+        }
+    }
+
+    /// Detects the snipers of the memecoin at the given block, and persists
+    /// the report. Returns `None` if the memecoin is not launched yet.
+    pub async fn analyze(
+        &self,
+        memecoin_address: &str,
+        block_number: u64,
+        block_timestamp: u64,
+    ) -> Result<Option<SniperReport>, StorageError> {
+        let memecoin = self.storage.get_memecoin(memecoin_address).await?;
+        let launch = match &memecoin.launch {
+            Some(l) => l,
+            None => return Ok(None),
+        };
+
+        let transfers = self
+            .storage
+            .get_token_transfers(memecoin_address, Some(block_number))
+            .await?;
+
+        // Neither the pools nor the team allocation are snipes.
+        let mut excluded: HashSet<String> = self
+            .storage
+            .get_pools()
+            .await?
+            .into_iter()
+            .filter(|p| p.memecoin_address == memecoin_address)
+            .map(|p| p.pool_address)
+            .collect();
+        excluded.extend(launch.locked_liquidity.clone());
+        excluded.insert(zero_address());
+        excluded.insert(memecoin.memecoin_address.clone());
+        excluded.insert(memecoin.owner.clone());
+
+        let window_end = launch.block_number + self.window_blocks;
+        let mut received: BTreeMap<&str, BigInt> = BTreeMap::new();
+
+        for t in transfers
+            .iter()
+            .filter(|t| t.block_number >= launch.block_number && t.block_number < window_end)
+            .filter(|t| !excluded.contains(&t.to_address))
+        {
+            *received.entry(&t.to_address).or_default() +=
+                BigInt::from_str(&t.amount).unwrap_or_default();
+        }
+
+        let balances = balances_from_transfers(&transfers);
+
+        let mut snipers = vec![];
+        for (wallet, amount) in received {
+            let funder_address = match self.storage.get_wallet_funding(wallet).await {
+                Ok(f) => Some(f.from_address),
+                Err(StorageError::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+
+            snipers.push(Sniper {
+                wallet_address: wallet.to_string(),
+                funder_address,
+                received: amount.to_string(),
+                balance: balances
+                    .get(wallet)
+                    .cloned()
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+
+        let clusters = clusters(&snipers);
+        let supply = BigInt::from_str(&memecoin.initial_supply).unwrap_or_default();
+
+        let bundled: HashSet<&String> = clusters.iter().flatten().collect();
+
+        let report = SniperReport {
+            memecoin_address: memecoin_address.to_string(),
+            launch_block_number: launch.block_number,
+            window_blocks: self.window_blocks,
+            snipers_supply_percent: held_percent(snipers.iter(), &supply),
+            bundled_supply_percent: held_percent(
+                snipers
+                    .iter()
+                    .filter(|s| bundled.contains(&s.wallet_address)),
+                &supply,
+            ),
+            snipers,
+            clusters,
+            block_number,
+            block_timestamp,
+        };
+
+        debug!("Sniper report: {:?}", report);
+
+        self.storage.set_sniper_report(&report).await?;
+        Ok(Some(report))
+    }
+}
+
+/// Returns the share of the supply held by the snipers.
+fn held_percent<'a>(snipers: impl Iterator<Item = &'a Sniper>, supply: &BigInt) -> f64 {
+    if *supply <= BigInt::from(0) {
+        return 0.0;
+    }
+
+    let held: BigInt = snipers
+        .filter_map(|s| BigInt::from_str(&s.balance).ok())
+        .filter(|b| *b > BigInt::from(0))
+        .sum();
+
+    percent(&held, supply)
+}
+
+/// Groups the snipers by funder, keeping the groups of at least 2 wallets.
+fn clusters(snipers: &[Sniper]) -> Vec<Vec<String>> {
+    let mut by_funder: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+    for s in snipers {
+        if let Some(funder) = &s.funder_address {
+            by_funder
+                .entry(funder)
+                .or_default()
+                .push(s.wallet_address.clone());
+        }
+    }
+
+    by_funder.into_values().filter(|w| w.len() > 1).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniper(wallet: &str, funder: Option<&str>) -> Sniper {
+        Sniper {
+            wallet_address: wallet.to_string(),
+            funder_address: funder.map(|f| f.to_string()),
+            received: "1".to_string(),
+            balance: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_clusters() {
+        let clusters = clusters(&[
+            sniper("0x1", Some("0xf1")),
+            sniper("0x2", Some("0xf2")),
+            sniper("0x3", Some("0xf1")),
+            sniper("0x4", None),
+            sniper("0x5", None),
+        ]);
+
+        // Unknown funders are not a common source.
+        assert_eq!(clusters, vec![vec!["0x1".to_string(), "0x3".to_string()]]);
+    }
+}
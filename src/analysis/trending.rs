@@ -0,0 +1,595 @@
+//! Rolling trending score of the memecoins.
+//!
+//! For each window, the score is the weighted sum of `ln(1 + metric)`
+//! for the USD swap volume, the unique buyers, the new holders and the
+//! transfer count, so that no metric overwhelms the others. Swaps are
+//! valued through the price oracle, whatever the decimals of their quote
+//! token, and count for no volume while their quote token has no price.
+//! Buyers of the Ekubo swaps without a trader, hops of a multi-hop route,
+//! are not counted.
+//!
+//! The activity of a memecoin over the longest window is loaded once and
+//! then kept up to date with the indexed swaps and transfers. The updates
+//! are throttled to one per `update_interval` of chain time.
+use crate::analysis::balances::zero_address;
+use crate::analysis::candles::Trade;
+use crate::analysis::pricing::PriceOracle;
+use crate::storage::types::{Pool, StorageError, SwapEvent, TokenTransfer, TrendingScore};
+use crate::storage::Storage;
+use ark_starknet::client::StarknetClient;
+use num_bigint::BigInt;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct TrendingConfig {
+    /// Rolling windows, in seconds, for which a score is computed.
+    pub windows: Vec<u64>,
+    pub volume_weight: f64,
+    pub buyers_weight: f64,
+    pub holders_weight: f64,
+    pub transfers_weight: f64,
+    /// Minimum chain time, in seconds, between two updates of the scores.
+    pub update_interval: u64,
+}
+
+impl Default for TrendingConfig {
+    fn default() -> Self {
+        TrendingConfig {
+            windows: vec![3_600, 86_400],
+            volume_weight: 1.0,
+            buyers_weight: 2.0,
+            holders_weight: 2.0,
+            transfers_weight: 0.5,
+            update_interval: 60,
+        }
+    }
+}
+
+/// Activity of a memecoin over a window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrendingMetrics {
+    /// Swap volume in the quote tokens, as raw amounts.
+    pub volume_quote: BigInt,
+    pub volume_usd: f64,
+    pub unique_buyers: u64,
+    pub new_holders: u64,
+    pub transfers: u64,
+}
+
+impl TrendingMetrics {
+    pub fn score(&self, config: &TrendingConfig) -> f64 {
+        config.volume_weight * self.volume_usd.ln_1p()
+            + config.buyers_weight * (self.unique_buyers as f64).ln_1p()
+            + config.holders_weight * (self.new_holders as f64).ln_1p()
+            + config.transfers_weight * (self.transfers as f64).ln_1p()
+    }
+}
+
+/// Activity of a memecoin, added in chain order and kept
+/// over the longest window.
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    /// Trades with their buyer, `None` for the sells.
+    trades: VecDeque<(Trade, Option<String>)>,
+    /// Timestamps of the transfers.
+    transfers: VecDeque<u64>,
+    /// Addresses which ever received the memecoin.
+    holders: HashSet<String>,
+    /// Timestamps at which an address first received the memecoin.
+    new_holders: VecDeque<u64>,
+}
+
+impl Activity {
+    /// Adds the trade of a swap of the memecoin.
+    pub fn add_swap(&mut self, memecoin_address: &str, swap: &SwapEvent, trade: Trade) {
+        let buyer = (swap.token_out == memecoin_address && !swap.trader.is_empty())
+            .then(|| swap.trader.clone());
+        self.trades.push_back((trade, buyer));
+    }
+
+    pub fn add_transfer(&mut self, transfer: &TokenTransfer) {
+        self.transfers.push_back(transfer.block_timestamp);
+
+        if transfer.to_address != zero_address() && self.holders.insert(transfer.to_address.clone())
+        {
+            self.new_holders.push_back(transfer.block_timestamp);
+        }
+    }
+
+    /// Drops the activity before the timestamp, the holders being kept.
+    pub fn prune(&mut self, from: u64) {
+        while self
+            .trades
+            .front()
+            .map_or(false, |(t, _)| t.block_timestamp < from)
+        {
+            self.trades.pop_front();
+        }
+        while self.transfers.front().map_or(false, |ts| *ts < from) {
+            self.transfers.pop_front();
+        }
+        while self.new_holders.front().map_or(false, |ts| *ts < from) {
+            self.new_holders.pop_front();
+        }
+    }
+
+    /// Returns the metrics of the window `[from, to]`.
+    pub fn metrics(&self, from: u64, to: u64) -> TrendingMetrics {
+        let in_window = |ts: u64| ts >= from && ts <= to;
+
+        let mut metrics = TrendingMetrics::default();
+        let mut buyers = HashSet::new();
+
+        for (t, buyer) in self
+            .trades
+            .iter()
+            .filter(|(t, _)| in_window(t.block_timestamp))
+        {
+            metrics.volume_quote += &t.quote_amount;
+            metrics.volume_usd += t.volume_usd;
+            if let Some(b) = buyer {
+                buyers.insert(b);
+            }
+        }
+
+        metrics.unique_buyers = buyers.len() as u64;
+        metrics.new_holders = self.new_holders.iter().filter(|ts| in_window(**ts)).count() as u64;
+        metrics.transfers = self.transfers.iter().filter(|ts| in_window(**ts)).count() as u64;
+        metrics
+    }
+}
+
+pub struct TrendingAnalyzer<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    oracle: Arc<PriceOracle<S, C>>,
+    config: TrendingConfig,
+    /// Timestamp of the block of the last update.
+    last_update: Mutex<Option<u64>>,
+    /// Activity of the memecoins, keyed by memecoin, loaded by `update`.
+    activity: Mutex<HashMap<String, Activity>>,
+}
+
+impl<S: Storage, C: StarknetClient> TrendingAnalyzer<S, C> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, oracle: Arc<PriceOracle<S, C>>, config: TrendingConfig) -> Self {
+        Self {
+            storage,
+            oracle,
+            config,
+            last_update: Mutex::new(None),
+            activity: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the scores of the window, the highest first.
+    pub async fn top(&self, window: u64, limit: u64) -> Result<Vec<TrendingScore>, StorageError> {
+        self.storage.get_trending_scores(window, limit).await
+    }
+
+    /// Adds the indexed swap to the activity of the memecoin of the pool.
+    /// Ignored until the activity is loaded by `update`.
+    pub async fn on_swap(&self, pool: &Pool, swap: &SwapEvent) -> Result<(), StorageError> {
+        if !self
+            .activity
+            .lock()
+            .unwrap()
+            .contains_key(&pool.memecoin_address)
+        {
+            return Ok(());
+        }
+
+        if let Some(trade) = self.oracle.trade(pool, swap).await? {
+            if let Some(a) = self
+                .activity
+                .lock()
+                .unwrap()
+                .get_mut(&pool.memecoin_address)
+            {
+                a.add_swap(&pool.memecoin_address, swap, trade);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the indexed transfer to the activity of the memecoin.
+    /// Ignored until the activity is loaded by `update`.
+    pub fn on_transfer(&self, transfer: &TokenTransfer) {
+        if let Some(a) = self
+            .activity
+            .lock()
+            .unwrap()
+            .get_mut(&transfer.token_address)
+        {
+            a.add_transfer(transfer);
+        }
+    }
+
+    /// Drops the activity, to be loaded again once some swaps or
+    /// transfers were removed from the storage, as when a block is
+    /// reindexed.
+    pub fn reset(&self) {
+        self.activity.lock().unwrap().clear();
+    }
+
+    /// Computes and persists the scores of every memecoin at the given block,
+    /// which must be the last processed one, unless the last update is more
+    /// recent than the update interval. Returns the updated scores.
+    pub async fn update(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+    ) -> Result<Vec<TrendingScore>, StorageError> {
+        if !self.is_due(block_timestamp) {
+            return Ok(vec![]);
+        }
+
+        let longest = self.config.windows.iter().max().copied().unwrap_or(0);
+        let from = block_timestamp.saturating_sub(longest);
+
+        let memecoins = self.storage.get_memecoins().await?;
+        // Only needed to load the activity of the memecoins.
+        let mut pools: Option<Vec<Pool>> = None;
+        let mut scores = vec![];
+
+        for m in memecoins {
+            let loaded = self
+                .activity
+                .lock()
+                .unwrap()
+                .contains_key(&m.memecoin_address);
+            if !loaded {
+                if pools.is_none() {
+                    pools = Some(self.storage.get_pools().await?);
+                }
+                let activity = self
+                    .load(
+                        &m.memecoin_address,
+                        pools.as_deref().unwrap_or_default(),
+                        block_number,
+                        from,
+                        block_timestamp,
+                    )
+                    .await?;
+                self.activity
+                    .lock()
+                    .unwrap()
+                    .insert(m.memecoin_address.clone(), activity);
+            }
+
+            let memecoin_scores: Vec<TrendingScore> = {
+                let mut activities = self.activity.lock().unwrap();
+                let activity = activities.entry(m.memecoin_address.clone()).or_default();
+                activity.prune(from);
+
+                self.config
+                    .windows
+                    .iter()
+                    .map(|window| {
+                        let metrics = activity
+                            .metrics(block_timestamp.saturating_sub(*window), block_timestamp);
+
+                        TrendingScore {
+                            memecoin_address: m.memecoin_address.clone(),
+                            window: *window,
+                            score: metrics.score(&self.config),
+                            volume_quote: metrics.volume_quote.to_string(),
+                            volume_usd: metrics.volume_usd,
+                            unique_buyers: metrics.unique_buyers,
+                            new_holders: metrics.new_holders,
+                            transfers: metrics.transfers,
+                            block_number,
+                            block_timestamp,
+                        }
+                    })
+                    .collect()
+            };
+
+            for score in memecoin_scores {
+                self.storage.set_trending_score(&score).await?;
+                scores.push(score);
+            }
+        }
+
+        debug!("{} trending scores updated", scores.len());
+
+        Ok(scores)
+    }
+
+    /// Loads the activity of the memecoin from the storage: its swaps since
+    /// the timestamp, and all its transfers for the holders.
+    async fn load(
+        &self,
+        memecoin_address: &str,
+        pools: &[Pool],
+        block_number: u64,
+        from: u64,
+        to: u64,
+    ) -> Result<Activity, StorageError> {
+        let mut activity = Activity::default();
+
+        let mut swaps = vec![];
+        for p in pools
+            .iter()
+            .filter(|p| p.memecoin_address == memecoin_address)
+        {
+            for s in self.storage.get_swaps(&p.pool_address, from, to).await? {
+                swaps.push((p, s));
+            }
+        }
+        swaps.sort_by_key(|(_, s)| s.block_number);
+
+        for (p, s) in swaps {
+            if let Some(trade) = self.oracle.trade(p, &s).await? {
+                activity.add_swap(memecoin_address, &s, trade);
+            }
+        }
+
+        for t in self
+            .storage
+            .get_token_transfers(memecoin_address, Some(block_number))
+            .await?
+        {
+            activity.add_transfer(&t);
+        }
+        activity.prune(from);
+
+        Ok(activity)
+    }
+
+    /// Returns true if the scores must be updated at the block, and
+    /// records the update. Blocks before the last update, indexed
+    /// again, are always updated.
+    fn is_due(&self, block_timestamp: u64) -> bool {
+        let mut last_update = self.last_update.lock().unwrap();
+
+        if let Some(t) = *last_update {
+            if block_timestamp >= t && block_timestamp < t + self.config.update_interval {
+                return false;
+            }
+        }
+
+        *last_update = Some(block_timestamp);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pricing::{PricingConfig, Stablecoin};
+    use crate::storage::types::{LiquidityType, MemecoinCreatedEvent};
+    use crate::testing::MemoryStorage;
+    use ark_starknet::client::MockStarknetClient;
+    use ark_starknet::format::to_hex_str;
+    use ark_starknet::CairoU256;
+    use starknet::core::types::FieldElement;
+
+    fn address(v: u64) -> String {
+        to_hex_str(&FieldElement::from(v))
+    }
+
+    fn pool() -> Pool {
+        Pool {
+            pool_address: "0x9a1".to_string(),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: "0x10".to_string(),
+            quote_token: "0x20".to_string(),
+            token0: "0x10".to_string(),
+            token1: "0x20".to_string(),
+            block_timestamp: 0,
+        }
+    }
+
+    fn buy(trader: &str, ts: u64) -> SwapEvent {
+        SwapEvent {
+            pool_address: "0x9a1".to_string(),
+            token_in: "0x20".to_string(),
+            token_out: "0x10".to_string(),
+            amount_in: "1000".to_string(),
+            amount_out: "10".to_string(),
+            trader: trader.to_string(),
+            block_timestamp: ts,
+            ..Default::default()
+        }
+    }
+
+    fn transfer(to: &str, ts: u64) -> TokenTransfer {
+        TokenTransfer {
+            from_address: "0xa".to_string(),
+            to_address: to.to_string(),
+            amount: "1".to_string(),
+            block_timestamp: ts,
+            ..Default::default()
+        }
+    }
+
+    fn analyzer(
+        storage: Arc<MemoryStorage>,
+        stablecoins: Vec<Stablecoin>,
+    ) -> TrendingAnalyzer<MemoryStorage, MockStarknetClient> {
+        let pricing = PricingConfig {
+            stablecoins,
+            quote_pools: vec![],
+            pragma: None,
+        };
+        let oracle = Arc::new(PriceOracle::new(
+            Arc::clone(&storage),
+            Arc::new(MockStarknetClient::default()),
+            &pricing,
+        ));
+
+        TrendingAnalyzer::new(
+            storage,
+            oracle,
+            TrendingConfig {
+                update_interval: 0,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_compute_metrics() {
+        let mut activity = Activity::default();
+        for t in [transfer("0xb", 50), transfer("0xb", 150)] {
+            activity.add_transfer(&t);
+        }
+        for s in [buy("0xb", 150), buy("0xb", 160), buy("0xc", 170)] {
+            let trade = Trade::from_swap(&pool(), &s).unwrap();
+            activity.add_swap("0x10", &s, trade);
+        }
+        for t in [transfer("0xc", 170), transfer("0xd", 250)] {
+            activity.add_transfer(&t);
+        }
+
+        let metrics = activity.metrics(100, 200);
+        assert_eq!(metrics.volume_quote, BigInt::from(3000));
+        assert_eq!(metrics.unique_buyers, 2);
+        // 0xb already held the memecoin before the window.
+        assert_eq!(metrics.new_holders, 1);
+        assert_eq!(metrics.transfers, 2);
+
+        // Pruning keeps the holders.
+        activity.prune(160);
+        assert_eq!(activity.metrics(0, 200).unique_buyers, 2);
+        assert_eq!(activity.metrics(0, 200).new_holders, 1);
+    }
+
+    #[tokio::test]
+    async fn test_volume_in_usd_kept_up_to_date() {
+        // USDC with 6 decimals, and a stablecoin with 18 decimals.
+        let (usdc, dai) = (FieldElement::from(0x20u64), FieldElement::from(0x30u64));
+        let storage = Arc::new(MemoryStorage::new());
+        let analyzer = analyzer(
+            Arc::clone(&storage),
+            vec![
+                Stablecoin {
+                    address: usdc,
+                    decimals: 6,
+                },
+                Stablecoin {
+                    address: dai,
+                    decimals: 18,
+                },
+            ],
+        );
+
+        // Both memecoins bought for 10 USD.
+        for (memecoin, quote, amount_in) in [
+            (0x10, usdc, "10000000"),
+            (0x11, dai, "10000000000000000000"),
+        ] {
+            let memecoin = address(memecoin);
+            let created = MemecoinCreatedEvent::new(
+                "0xa".to_string(),
+                "Doge".to_string(),
+                "DOGE".to_string(),
+                CairoU256 { low: 1000, high: 0 },
+                memecoin.clone(),
+            );
+            storage
+                .register_memecoin_created_event(&created, 0)
+                .await
+                .unwrap();
+
+            let pool = Pool {
+                pool_address: format!("{memecoin}01"),
+                memecoin_address: memecoin.clone(),
+                quote_token: to_hex_str(&quote),
+                ..pool()
+            };
+            storage.register_pool(&pool, 0).await.unwrap();
+
+            let swap = SwapEvent {
+                event_id: pool.pool_address.clone(),
+                pool_address: pool.pool_address.clone(),
+                token_in: pool.quote_token.clone(),
+                token_out: memecoin,
+                amount_in: amount_in.to_string(),
+                amount_out: "1000000000000000000000".to_string(),
+                trader: "0xb".to_string(),
+                block_number: 1,
+                block_timestamp: 1000,
+                ..Default::default()
+            };
+            storage.register_swap(&swap, 1000).await.unwrap();
+        }
+
+        let scores = analyzer.update(1, 1000).await.unwrap();
+        let volumes: Vec<f64> = scores
+            .iter()
+            .filter(|s| s.window == 3_600)
+            .map(|s| s.volume_usd)
+            .collect();
+        assert_eq!(volumes.len(), 2);
+        assert!(volumes.iter().all(|v| (v - 10.0).abs() < 1e-9));
+        assert_eq!(scores[0].score, scores[2].score);
+
+        // Swaps indexed once loaded are added without reading the storage.
+        let pool = Pool {
+            pool_address: format!("{}01", address(0x10)),
+            memecoin_address: address(0x10),
+            quote_token: to_hex_str(&usdc),
+            ..pool()
+        };
+        let swap = SwapEvent {
+            pool_address: pool.pool_address.clone(),
+            token_in: pool.quote_token.clone(),
+            token_out: address(0x10),
+            amount_in: "5000000".to_string(),
+            amount_out: "500000000000000000000".to_string(),
+            trader: "0xc".to_string(),
+            block_number: 2,
+            block_timestamp: 1010,
+            ..Default::default()
+        };
+        analyzer.on_swap(&pool, &swap).await.unwrap();
+
+        let scores = analyzer.update(2, 1010).await.unwrap();
+        let score = scores
+            .iter()
+            .find(|s| s.memecoin_address == address(0x10) && s.window == 3_600)
+            .unwrap();
+        assert!((score.volume_usd - 15.0).abs() < 1e-9);
+        assert_eq!(score.unique_buyers, 2);
+    }
+
+    #[test]
+    fn test_updates_are_throttled() {
+        let storage = Arc::new(MemoryStorage::new());
+        let analyzer = TrendingAnalyzer::new(
+            Arc::clone(&storage),
+            Arc::new(PriceOracle::new(
+                storage,
+                Arc::new(MockStarknetClient::default()),
+                &PricingConfig::default(),
+            )),
+            TrendingConfig::default(),
+        );
+
+        assert!(analyzer.is_due(1000));
+        assert!(!analyzer.is_due(1030));
+        assert!(analyzer.is_due(1060));
+        // Reindexed block.
+        assert!(analyzer.is_due(900));
+    }
+
+    #[test]
+    fn test_score_grows_with_activity() {
+        let config = TrendingConfig::default();
+        let quiet = TrendingMetrics {
+            unique_buyers: 1,
+            ..Default::default()
+        };
+        let busy = TrendingMetrics {
+            unique_buyers: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(TrendingMetrics::default().score(&config), 0.0);
+        assert!(busy.score(&config) > quiet.score(&config));
+    }
+}
@@ -0,0 +1,464 @@
+//! REST API serving the indexed data, enabled with the `api` feature.
+//!
+//! The responses are the JSON serializations of the storage types, lists
+//! being wrapped in a `Page` with the offset of the next page, if any.
+//! Missing items are answered with a 404, other storage errors with a 500,
+//! both with an `{"error": "..."}` body.
+use crate::analysis::PortfolioTracker;
+use crate::storage::types::{
+    Candle, CandleInterval, ContractInfo, EventFilter, EventType, MarketMetrics, MemecoinFilter,
+    MemecoinInfo, Pagination, Position, SafetyReport, StorageError, TokenEvent, TokenFilter,
+    TokenInfo, TrendingScore,
+};
+use crate::storage::Storage;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Upper bound of the page size, whatever is requested.
+pub const MAX_LIMIT: u64 = 500;
+
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Debug)]
+pub struct ApiError(StorageError);
+
+impl From<StorageError> for ApiError {
+    fn from(e: StorageError) -> Self {
+        ApiError(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            StorageError::NotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({ "error": self.0.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// A page of a list, with the offset to query the next one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_offset: Option<u64>,
+}
+
+impl<T> Page<T> {
+    fn new(items: Vec<T>, pagination: &Pagination) -> Self {
+        let next_offset = if items.len() as u64 == pagination.limit {
+            Some(pagination.offset + pagination.limit)
+        } else {
+            None
+        };
+        Page { items, next_offset }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+impl PageQuery {
+    fn pagination(&self) -> Pagination {
+        let default = Pagination::default();
+        Pagination {
+            offset: self.offset.unwrap_or(default.offset),
+            limit: self.limit.unwrap_or(default.limit).min(MAX_LIMIT),
+            after: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TokensQuery {
+    pub contract_address: Option<String>,
+    pub owner: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventsQuery {
+    pub contract_address: Option<String>,
+    pub token_id_hex: Option<String>,
+    pub address: Option<String>,
+    pub event_type: Option<EventType>,
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MemecoinsQuery {
+    pub owner: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CandlesQuery {
+    pub interval: CandleInterval,
+    pub from_time: Option<u64>,
+    pub to_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrendingQuery {
+    /// Window of the scores, in seconds, an hour by default.
+    pub window: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PositionsQuery {
+    /// Defaults to now.
+    pub timestamp: Option<u64>,
+}
+
+pub struct ApiState<S: Storage> {
+    storage: Arc<S>,
+    portfolio: PortfolioTracker<S>,
+}
+
+impl<S: Storage> ApiState<S> {
+    pub fn new(storage: Arc<S>) -> Self {
+        Self {
+            portfolio: PortfolioTracker::new(Arc::clone(&storage)),
+            storage,
+        }
+    }
+}
+
+/// Returns the router of the API over the storage.
+pub fn router<S>(storage: Arc<S>) -> Router
+where
+    S: Storage + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/contracts", get(contracts::<S>))
+        .route("/contracts/:address", get(contract::<S>))
+        .route("/tokens", get(tokens::<S>))
+        .route("/tokens/:contract_address/:token_id_hex", get(token::<S>))
+        .route("/events", get(events::<S>))
+        .route("/memecoins", get(memecoins::<S>))
+        .route("/memecoins/:address", get(memecoin::<S>))
+        .route("/memecoins/:address/safety", get(safety::<S>))
+        .route("/memecoins/:address/metrics", get(metrics::<S>))
+        .route("/memecoins/:address/candles", get(candles::<S>))
+        .route("/trending", get(trending::<S>))
+        .route("/wallets/:address/positions", get(positions::<S>))
+        .with_state(Arc::new(ApiState::new(storage)))
+}
+
+/// Serves the router on the given address, until the server fails.
+/// Other routers, like the GraphQL one, can be merged into the API one.
+pub async fn serve(app: Router, addr: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("API listening on {}", addr);
+    axum::serve(listener, app).await
+}
+
+pub async fn contracts<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Query(query): Query<PageQuery>,
+) -> ApiResult<Page<ContractInfo>> {
+    let pagination = query.pagination();
+    let contracts = state.storage.get_contract_infos(&pagination).await?;
+    Ok(Json(Page::new(contracts, &pagination)))
+}
+
+pub async fn contract<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+) -> ApiResult<ContractInfo> {
+    Ok(Json(state.storage.get_contract_info(&address).await?))
+}
+
+pub async fn tokens<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Query(query): Query<TokensQuery>,
+) -> ApiResult<Page<TokenInfo>> {
+    let pagination = PageQuery {
+        offset: query.offset,
+        limit: query.limit,
+    }
+    .pagination();
+    let filter = TokenFilter {
+        contract_address: query.contract_address,
+        owner: query.owner,
+    };
+    let tokens = state.storage.get_tokens(&filter, &pagination).await?;
+    Ok(Json(Page::new(tokens, &pagination)))
+}
+
+pub async fn token<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path((contract_address, token_id_hex)): Path<(String, String)>,
+) -> ApiResult<TokenInfo> {
+    Ok(Json(
+        state
+            .storage
+            .get_token(&contract_address, &token_id_hex)
+            .await?,
+    ))
+}
+
+pub async fn events<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Query(query): Query<EventsQuery>,
+) -> ApiResult<Page<TokenEvent>> {
+    let pagination = PageQuery {
+        offset: query.offset,
+        limit: query.limit,
+    }
+    .pagination();
+    let filter = EventFilter {
+        contract_address: query.contract_address,
+        token_id_hex: query.token_id_hex,
+        address: query.address,
+        event_type: query.event_type,
+        from_timestamp: query.from_timestamp,
+        to_timestamp: query.to_timestamp,
+    };
+    let events = state.storage.get_events(&filter, &pagination).await?;
+    Ok(Json(Page::new(events, &pagination)))
+}
+
+pub async fn memecoins<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Query(query): Query<MemecoinsQuery>,
+) -> ApiResult<Page<MemecoinInfo>> {
+    let pagination = PageQuery {
+        offset: query.offset,
+        limit: query.limit,
+    }
+    .pagination();
+    let filter = MemecoinFilter { owner: query.owner };
+    let memecoins = state
+        .storage
+        .get_filtered_memecoins(&filter, &pagination)
+        .await?;
+    Ok(Json(Page::new(memecoins, &pagination)))
+}
+
+pub async fn memecoin<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+) -> ApiResult<MemecoinInfo> {
+    Ok(Json(state.storage.get_memecoin(&address).await?))
+}
+
+pub async fn safety<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+) -> ApiResult<SafetyReport> {
+    Ok(Json(state.storage.get_safety_report(&address).await?))
+}
+
+pub async fn metrics<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+) -> ApiResult<MarketMetrics> {
+    Ok(Json(state.storage.get_market_metrics(&address).await?))
+}
+
+pub async fn candles<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+    Query(query): Query<CandlesQuery>,
+) -> ApiResult<Vec<Candle>> {
+    let candles = state
+        .storage
+        .get_candles(
+            &address,
+            query.interval,
+            query.from_time.unwrap_or(0),
+            query.to_time.unwrap_or(u64::MAX),
+        )
+        .await?;
+    Ok(Json(candles))
+}
+
+pub async fn trending<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Query(query): Query<TrendingQuery>,
+) -> ApiResult<Vec<TrendingScore>> {
+    let limit = PageQuery {
+        offset: None,
+        limit: query.limit,
+    }
+    .pagination()
+    .limit;
+    let scores = state
+        .storage
+        .get_trending_scores(query.window.unwrap_or(3_600), limit)
+        .await?;
+    Ok(Json(scores))
+}
+
+pub async fn positions<S: Storage>(
+    State(state): State<Arc<ApiState<S>>>,
+    Path(address): Path<String>,
+    Query(query): Query<PositionsQuery>,
+) -> ApiResult<Vec<Position>> {
+    let timestamp = query.timestamp.unwrap_or_else(now);
+    Ok(Json(state.portfolio.positions(&address, timestamp).await?))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::MemecoinCreatedEvent;
+    use crate::testing::MemoryStorage;
+    use ark_starknet::CairoU256;
+
+    fn token_info(contract_address: &str, token_id_hex: &str, owner: &str) -> TokenInfo {
+        TokenInfo {
+            contract_address: contract_address.to_string(),
+            token_id: token_id_hex.to_string(),
+            token_id_hex: token_id_hex.to_string(),
+            owner: owner.to_string(),
+        }
+    }
+
+    async fn state() -> State<Arc<ApiState<MemoryStorage>>> {
+        let storage = Arc::new(MemoryStorage::new());
+        for (contract, token_id, owner) in [
+            ("0x1", "0x1", "0xa"),
+            ("0x1", "0x2", "0xb"),
+            ("0x1", "0x3", "0xa"),
+            ("0x2", "0x1", "0xa"),
+        ] {
+            storage
+                .register_token(&token_info(contract, token_id, owner), 1)
+                .await
+                .unwrap();
+        }
+        for (i, event_type) in [EventType::Mint, EventType::Transfer, EventType::Transfer]
+            .into_iter()
+            .enumerate()
+        {
+            let event = TokenEvent {
+                timestamp: 100 + i as u64,
+                contract_address: "0x1".to_string(),
+                token_id_hex: "0x1".to_string(),
+                to_address: "0xa".to_string(),
+                event_type,
+                event_id: format!("0x{i}"),
+                ..Default::default()
+            };
+            storage
+                .register_event(&event, event.timestamp)
+                .await
+                .unwrap();
+        }
+        for (i, owner) in ["0xa", "0xb", "0xa"].into_iter().enumerate() {
+            let created = MemecoinCreatedEvent {
+                owner: owner.to_string(),
+                name: "Doge".to_string(),
+                symbol: "DOGE".to_string(),
+                initial_supply: CairoU256 { low: 1000, high: 0 },
+                memecoin_address: format!("0x{}", 10 + i),
+            };
+            storage
+                .register_memecoin_created_event(&created, 100 + i as u64)
+                .await
+                .unwrap();
+        }
+        State(Arc::new(ApiState::new(storage)))
+    }
+
+    #[tokio::test]
+    async fn test_tokens_pagination() {
+        let state = state().await;
+
+        let query = TokensQuery {
+            owner: Some("0xa".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let Json(page) = tokens(state.clone(), Query(query)).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.next_offset, Some(2));
+
+        let query = TokensQuery {
+            owner: Some("0xa".to_string()),
+            offset: Some(2),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let Json(page) = tokens(state.clone(), Query(query)).await.unwrap();
+        assert_eq!(page.items, vec![token_info("0x2", "0x1", "0xa")]);
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn test_memecoins_filter() {
+        let state = state().await;
+
+        let query = MemecoinsQuery {
+            owner: Some("0xa".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let Json(page) = memecoins(state.clone(), Query(query)).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].memecoin_address, "0x12");
+        assert_eq!(page.next_offset, Some(1));
+
+        let query = MemecoinsQuery {
+            owner: Some("0xa".to_string()),
+            offset: Some(1),
+            limit: Some(2),
+        };
+        let Json(page) = memecoins(state, Query(query)).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].memecoin_address, "0x10");
+        assert_eq!(page.next_offset, None);
+    }
+
+    #[tokio::test]
+    async fn test_events_filter() {
+        let state = state().await;
+
+        let query = EventsQuery {
+            event_type: Some(EventType::Transfer),
+            from_timestamp: Some(102),
+            ..Default::default()
+        };
+        let Json(page) = events(state, Query(query)).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].timestamp, 102);
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        let state = state().await;
+
+        let error = token(state, Path(("0x3".to_string(), "0x1".to_string())))
+            .await
+            .unwrap_err();
+        assert_eq!(error.into_response().status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,36 @@
+//! Serves the REST API over the database filled by the indexer, and the
+//! GraphQL one on `/graphql` if the `graphql` feature is enabled.
+//!
+//! Configured by the environment (or a `.env` file):
+//! - `DATABASE_URL`: database of the indexer.
+//! - `API_ADDRESS`: address to listen on, `0.0.0.0:8080` by default.
+use dotenv::dotenv;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tiny_stark::api;
+use tiny_stark::storage::DefaultSqlxStorage;
+
+const DEFAULT_API_ADDRESS: &str = "0.0.0.0:8080";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+    sqlx::any::install_default_drivers();
+
+    let db_url = std::env::var("DATABASE_URL")?;
+    let addr: SocketAddr = std::env::var("API_ADDRESS")
+        .unwrap_or_else(|_| DEFAULT_API_ADDRESS.to_string())
+        .parse()?;
+
+    let storage = DefaultSqlxStorage::new_any(&db_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let storage = Arc::new(storage);
+    let app = api::router(Arc::clone(&storage));
+    #[cfg(feature = "graphql")]
+    let app = app.merge(tiny_stark::graphql::router(storage));
+
+    api::serve(app, addr).await?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! Resolution of the class hash of deployed contracts.
+//!
+//! `StarknetClient` doesn't expose `starknet_getClassHashAt`, this trait is
+//! then implemented separately, and is optional for Pontos.
+use ark_starknet::client::StarknetClientError;
+use async_trait::async_trait;
+use starknet::core::types::{BlockId, FieldElement};
+use starknet::providers::jsonrpc::{HttpTransport, JsonRpcClient};
+use starknet::providers::Provider;
+
+#[async_trait]
+pub trait ClassHashProvider {
+    /// Returns the class hash of the contract deployed at the given address,
+    /// at the given block.
+    async fn class_hash_at(
+        &self,
+        contract_address: FieldElement,
+        block: BlockId,
+    ) -> Result<FieldElement, StarknetClientError>;
+}
+
+#[async_trait]
+impl ClassHashProvider for JsonRpcClient<HttpTransport> {
+    async fn class_hash_at(
+        &self,
+        contract_address: FieldElement,
+        block: BlockId,
+    ) -> Result<FieldElement, StarknetClientError> {
+        self.get_class_hash_at(block, contract_address)
+            .await
+            .map_err(|e| StarknetClientError::Other(format!("Can't get class hash: {}", e)))
+    }
+}
@@ -0,0 +1,462 @@
+//! Composite client spreading the requests over several RPC endpoints.
+//!
+//! Each endpoint is monitored (latency, consecutive errors and block height)
+//! and the requests are always sent to the healthiest and fastest one first.
+//! On transport errors, the next endpoint is tried, so the indexation
+//! can continue when one of the providers goes down.
+use ark_starknet::client::{FetchEventsResult, StarknetClient, StarknetClientError};
+use async_trait::async_trait;
+use starknet::core::types::{BlockId, EmittedEvent, FieldElement};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock as AsyncRwLock;
+use tracing::{debug, info, trace, warn};
+
+/// Weight of the latest measure in the latency moving average.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+#[derive(Debug, Clone)]
+pub struct FailoverConfig {
+    /// Maximum number of blocks an endpoint can lag behind the most
+    /// advanced endpoint before being considered as unhealthy.
+    pub max_block_lag: u64,
+    /// Number of consecutive transport errors before an endpoint
+    /// is considered as unhealthy.
+    pub max_consecutive_failures: u32,
+    /// Delay between two health checks in `run_health_checks`.
+    pub health_check_interval: Duration,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        FailoverConfig {
+            max_block_lag: 5,
+            max_consecutive_failures: 3,
+            health_check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointStatus {
+    /// False after too many consecutive transport errors.
+    pub healthy: bool,
+    /// Lagging too far behind the chain head during the last health check.
+    pub lagging: bool,
+    /// Moving average of the response time, in milliseconds.
+    pub latency_ms: f64,
+    pub consecutive_failures: u32,
+    /// Last block number reported by the endpoint during a health check.
+    pub last_block_number: Option<u64>,
+}
+
+impl Default for EndpointStatus {
+    fn default() -> Self {
+        EndpointStatus {
+            healthy: true,
+            lagging: false,
+            latency_ms: 0.0,
+            consecutive_failures: 0,
+            last_block_number: None,
+        }
+    }
+}
+
+struct Endpoint<C> {
+    name: String,
+    client: Arc<C>,
+    status: AsyncRwLock<EndpointStatus>,
+}
+
+pub struct FailoverClient<C: StarknetClient> {
+    endpoints: Vec<Endpoint<C>>,
+    config: FailoverConfig,
+}
+
+impl<C: StarknetClient + Send + Sync> FailoverClient<C> {
+    /// Initializes a new instance from already built clients,
+    /// each client being identified by a name used in the logs.
+    pub fn from_clients(clients: Vec<(String, Arc<C>)>, config: FailoverConfig) -> Self {
+        let endpoints = clients
+            .into_iter()
+            .map(|(name, client)| Endpoint {
+                name,
+                client,
+                status: AsyncRwLock::new(EndpointStatus::default()),
+            })
+            .collect();
+
+        FailoverClient { endpoints, config }
+    }
+
+    /// Returns the current status of every endpoint.
+    pub async fn statuses(&self) -> Vec<(String, EndpointStatus)> {
+        let mut statuses = vec![];
+        for e in &self.endpoints {
+            statuses.push((e.name.clone(), e.status.read().await.clone()));
+        }
+        statuses
+    }
+
+    /// Queries the block number of every endpoint, updating their latency,
+    /// and flags the ones lagging too far behind the chain head.
+    pub async fn health_check(&self) {
+        let mut head: Option<u64> = None;
+
+        for (idx, e) in self.endpoints.iter().enumerate() {
+            let start = Instant::now();
+            match e.client.block_number().await {
+                Ok(n) => {
+                    self.record_success(idx, start.elapsed()).await;
+                    e.status.write().await.last_block_number = Some(n);
+                    head = Some(head.map_or(n, |h| h.max(n)));
+                }
+                Err(err) => {
+                    warn!("Health check failed for endpoint {}: {:?}", e.name, err);
+                    self.record_failure(idx).await;
+                }
+            }
+        }
+
+        let head = match head {
+            Some(h) => h,
+            None => {
+                warn!("No endpoint answered the health check");
+                return;
+            }
+        };
+
+        for e in &self.endpoints {
+            let mut status = e.status.write().await;
+            if let Some(n) = status.last_block_number {
+                let lag = head.saturating_sub(n);
+                let lagging = lag > self.config.max_block_lag;
+                if lagging && !status.lagging {
+                    warn!(
+                        "Endpoint {} is lagging {} blocks behind head #{}",
+                        e.name, lag, head
+                    );
+                }
+                status.lagging = lagging;
+            }
+        }
+    }
+
+    /// Starts a loop running the health checks at the configured interval.
+    pub async fn run_health_checks(&self) {
+        loop {
+            self.health_check().await;
+            trace!("Endpoints status: {:?}", self.statuses().await);
+            tokio::time::sleep(self.config.health_check_interval).await;
+        }
+    }
+
+    /// Returns the endpoints indexes in the order they must be tried:
+    /// healthy endpoints first, each group sorted by latency.
+    /// Lagging endpoints are skipped, unless every endpoint is lagging.
+    async fn ordered_endpoints(&self) -> Vec<usize> {
+        let mut candidates = vec![];
+        for (idx, e) in self.endpoints.iter().enumerate() {
+            let status = e.status.read().await;
+            candidates.push((idx, status.healthy, status.lagging, status.latency_ms));
+        }
+
+        if candidates.iter().any(|c| !c.2) {
+            candidates.retain(|c| !c.2);
+        }
+
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.3.total_cmp(&b.3)));
+        candidates.into_iter().map(|(idx, _, _, _)| idx).collect()
+    }
+
+    async fn record_success(&self, idx: usize, elapsed: Duration) {
+        let mut status = self.endpoints[idx].status.write().await;
+        let ms = elapsed.as_secs_f64() * 1000.0;
+
+        status.latency_ms = if status.latency_ms == 0.0 {
+            ms
+        } else {
+            status.latency_ms * (1.0 - LATENCY_SMOOTHING) + ms * LATENCY_SMOOTHING
+        };
+
+        if status.consecutive_failures >= self.config.max_consecutive_failures {
+            info!("Endpoint {} is reachable again", self.endpoints[idx].name);
+            status.healthy = true;
+        }
+        status.consecutive_failures = 0;
+    }
+
+    async fn record_failure(&self, idx: usize) {
+        let mut status = self.endpoints[idx].status.write().await;
+        status.consecutive_failures += 1;
+
+        if status.healthy && status.consecutive_failures >= self.config.max_consecutive_failures {
+            warn!(
+                "Endpoint {} marked as unhealthy after {} failures",
+                self.endpoints[idx].name, status.consecutive_failures
+            );
+            status.healthy = false;
+        }
+    }
+
+    /// Runs the given request on the endpoints, in order, until one succeeds.
+    /// Errors related to the contract itself are returned directly, as
+    /// an other endpoint would answer the same.
+    async fn call<T, F, Fut>(&self, method: &str, f: F) -> Result<T, StarknetClientError>
+    where
+        F: Fn(Arc<C>) -> Fut,
+        Fut: Future<Output = Result<T, StarknetClientError>>,
+    {
+        let mut last_error = None;
+
+        for idx in self.ordered_endpoints().await {
+            let endpoint = &self.endpoints[idx];
+            let start = Instant::now();
+
+            match f(Arc::clone(&endpoint.client)).await {
+                Ok(r) => {
+                    self.record_success(idx, start.elapsed()).await;
+                    return Ok(r);
+                }
+                Err(e) if !is_transport_error(&e) => {
+                    self.record_success(idx, start.elapsed()).await;
+                    return Err(e);
+                }
+                Err(e) => {
+                    debug!("{} failed on endpoint {}: {:?}", method, endpoint.name, e);
+                    self.record_failure(idx).await;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            StarknetClientError::Other(format!("No endpoint available for {}", method))
+        }))
+    }
+}
+
+/// Returns false if the error was raised by the contract execution,
+/// true for any other error that may be related to the endpoint.
+fn is_transport_error(e: &StarknetClientError) -> bool {
+    !matches!(
+        e,
+        StarknetClientError::Contract(_)
+            | StarknetClientError::EntrypointNotFound(_)
+            | StarknetClientError::InputTooLong
+    )
+}
+
+#[async_trait]
+impl<C: StarknetClient + Send + Sync> StarknetClient for FailoverClient<C> {
+    /// Expects a comma separated list of RPC urls.
+    fn new(rpc_url: &str) -> Result<Self, StarknetClientError>
+    where
+        Self: Sized,
+    {
+        let mut clients = vec![];
+        for url in rpc_url.split(',').map(str::trim).filter(|u| !u.is_empty()) {
+            clients.push((url.to_string(), Arc::new(C::new(url)?)));
+        }
+
+        if clients.is_empty() {
+            return Err(StarknetClientError::Other(
+                "At least one RPC url is expected".to_string(),
+            ));
+        }
+
+        Ok(FailoverClient::from_clients(
+            clients,
+            FailoverConfig::default(),
+        ))
+    }
+
+    async fn block_txs_hashes(
+        &self,
+        block: BlockId,
+    ) -> Result<(u64, Vec<FieldElement>), StarknetClientError> {
+        self.call("block_txs_hashes", |c| async move {
+            c.block_txs_hashes(block).await
+        })
+        .await
+    }
+
+    async fn block_time(&self, block: BlockId) -> Result<u64, StarknetClientError> {
+        self.call("block_time", |c| async move { c.block_time(block).await })
+            .await
+    }
+
+    async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64, StarknetClientError> {
+        let id = *id;
+        self.call("block_id_to_u64", |c| async move {
+            c.block_id_to_u64(&id).await
+        })
+        .await
+    }
+
+    fn parse_block_range(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(BlockId, BlockId), StarknetClientError> {
+        // Parsing is local to the client, any endpoint can do it.
+        self.endpoints[0].client.parse_block_range(from, to)
+    }
+
+    fn parse_block_id(&self, id: &str) -> Result<BlockId, StarknetClientError> {
+        self.endpoints[0].client.parse_block_id(id)
+    }
+
+    async fn block_number(&self) -> Result<u64, StarknetClientError> {
+        self.call("block_number", |c| async move { c.block_number().await })
+            .await
+    }
+
+    async fn fetch_events(
+        &self,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+        continuation_token: Option<String>,
+    ) -> Result<FetchEventsResult, StarknetClientError> {
+        self.call("fetch_events", |c| {
+            let keys = keys.clone();
+            let continuation_token = continuation_token.clone();
+            async move {
+                c.fetch_events(from_block, to_block, keys, address, continuation_token)
+                    .await
+            }
+        })
+        .await
+    }
+
+    async fn fetch_all_block_events(
+        &self,
+        block: BlockId,
+        keys: Option<Vec<Vec<FieldElement>>>,
+    ) -> Result<HashMap<u64, Vec<EmittedEvent>>, StarknetClientError> {
+        self.call("fetch_all_block_events", |c| {
+            let keys = keys.clone();
+            async move { c.fetch_all_block_events(block, keys).await }
+        })
+        .await
+    }
+
+    async fn call_contract(
+        &self,
+        contract_address: FieldElement,
+        selector: FieldElement,
+        calldata: Vec<FieldElement>,
+        block: BlockId,
+    ) -> Result<Vec<FieldElement>, StarknetClientError> {
+        self.call("call_contract", |c| {
+            let calldata = calldata.clone();
+            async move {
+                c.call_contract(contract_address, selector, calldata, block)
+                    .await
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_starknet::client::MockStarknetClient;
+
+    fn failover(clients: Vec<MockStarknetClient>) -> FailoverClient<MockStarknetClient> {
+        FailoverClient::from_clients(
+            clients
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (format!("node{}", i), Arc::new(c)))
+                .collect(),
+            FailoverConfig {
+                max_block_lag: 2,
+                max_consecutive_failures: 1,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failover_on_transport_error() {
+        let mut down = MockStarknetClient::default();
+        down.expect_block_time()
+            .returning(|_| Err(StarknetClientError::Other("connection refused".to_string())));
+
+        let mut up = MockStarknetClient::default();
+        up.expect_block_time().returning(|_| Ok(1234));
+
+        let client = failover(vec![down, up]);
+
+        let ts = client.block_time(BlockId::Number(1)).await.unwrap();
+        assert_eq!(ts, 1234);
+
+        let statuses = client.statuses().await;
+        assert!(!statuses[0].1.healthy);
+        assert!(statuses[1].1.healthy);
+    }
+
+    #[tokio::test]
+    async fn test_contract_error_is_not_retried() {
+        let mut first = MockStarknetClient::default();
+        first
+            .expect_call_contract()
+            .times(1)
+            .returning(|_, _, _, _| Err(StarknetClientError::EntrypointNotFound("".to_string())));
+
+        let mut second = MockStarknetClient::default();
+        second.expect_call_contract().never();
+
+        let client = failover(vec![first, second]);
+
+        let result = client
+            .call_contract(
+                FieldElement::ONE,
+                FieldElement::TWO,
+                vec![],
+                BlockId::Number(1),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(StarknetClientError::EntrypointNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_refuses_lagging_endpoint() {
+        let mut lagging = MockStarknetClient::default();
+        lagging.expect_block_number().returning(|| Ok(100));
+
+        let mut synced = MockStarknetClient::default();
+        synced.expect_block_number().returning(|| Ok(110));
+
+        let client = failover(vec![lagging, synced]);
+        client.health_check().await;
+
+        let statuses = client.statuses().await;
+        assert!(statuses[0].1.lagging);
+        assert!(!statuses[1].1.lagging);
+        assert_eq!(client.ordered_endpoints().await, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_endpoints_are_used_if_all_lag() {
+        let client = failover(vec![
+            MockStarknetClient::default(),
+            MockStarknetClient::default(),
+        ]);
+        for e in &client.endpoints {
+            e.status.write().await.lagging = true;
+        }
+
+        assert_eq!(client.ordered_endpoints().await.len(), 2);
+    }
+}
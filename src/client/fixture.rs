@@ -0,0 +1,496 @@
+//! Record & replay of RPC responses.
+//!
+//! `RecordingClient` wraps a real client and keeps every response
+//! (including errors, as contract identification relies on them)
+//! into a `Fixture`, which can be saved as JSON.
+//! `ReplayClient` loads such a fixture and answers the exact same requests
+//! offline, allowing deterministic tests of Pontos over real blocks.
+use ark_starknet::client::{FetchEventsResult, StarknetClient, StarknetClientError};
+use ark_starknet::format::to_hex_str;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use starknet::core::types::{BlockId, BlockTag, EmittedEvent, FieldElement};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock as AsyncRwLock;
+use tracing::trace;
+
+/// Serializable version of `StarknetClientError`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedError {
+    Contract(String),
+    EntrypointNotFound(String),
+    InputTooLong,
+    Other(String),
+}
+
+impl From<&StarknetClientError> for RecordedError {
+    fn from(e: &StarknetClientError) -> Self {
+        match e {
+            StarknetClientError::Contract(s) => RecordedError::Contract(s.clone()),
+            StarknetClientError::EntrypointNotFound(s) => {
+                RecordedError::EntrypointNotFound(s.clone())
+            }
+            StarknetClientError::InputTooLong => RecordedError::InputTooLong,
+            e => RecordedError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<RecordedError> for StarknetClientError {
+    fn from(e: RecordedError) -> Self {
+        match e {
+            RecordedError::Contract(s) => StarknetClientError::Contract(s),
+            RecordedError::EntrypointNotFound(s) => StarknetClientError::EntrypointNotFound(s),
+            RecordedError::InputTooLong => StarknetClientError::InputTooLong,
+            RecordedError::Other(s) => StarknetClientError::Other(s),
+        }
+    }
+}
+
+pub type Recorded<T> = Result<T, RecordedError>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEventsPage {
+    pub events: BTreeMap<u64, Vec<EmittedEvent>>,
+    pub continuation_token: Option<String>,
+}
+
+/// All the responses recorded for a session, keyed by request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub block_number: Option<Recorded<u64>>,
+    pub block_times: BTreeMap<String, Recorded<u64>>,
+    pub block_ids: BTreeMap<String, Recorded<u64>>,
+    pub block_txs_hashes: BTreeMap<String, Recorded<(u64, Vec<FieldElement>)>>,
+    pub events_pages: BTreeMap<String, Recorded<RecordedEventsPage>>,
+    pub block_events: BTreeMap<String, Recorded<BTreeMap<u64, Vec<EmittedEvent>>>>,
+    pub calls: BTreeMap<String, Recorded<Vec<FieldElement>>>,
+}
+
+impl Fixture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, StarknetClientError> {
+        let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            StarknetClientError::Other(format!(
+                "Can't read fixture {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| StarknetClientError::Other(format!("Invalid fixture: {}", e)))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), StarknetClientError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| StarknetClientError::Other(format!("Can't serialize fixture: {}", e)))?;
+
+        std::fs::write(path.as_ref(), content).map_err(|e| {
+            StarknetClientError::Other(format!(
+                "Can't write fixture {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+}
+
+fn block_key(block: &BlockId) -> String {
+    match block {
+        BlockId::Number(n) => n.to_string(),
+        BlockId::Hash(h) => to_hex_str(h),
+        BlockId::Tag(BlockTag::Latest) => "latest".to_string(),
+        BlockId::Tag(BlockTag::Pending) => "pending".to_string(),
+    }
+}
+
+fn felts_key(felts: &[FieldElement]) -> String {
+    felts.iter().map(to_hex_str).collect::<Vec<_>>().join(",")
+}
+
+fn keys_key(keys: &Option<Vec<Vec<FieldElement>>>) -> String {
+    keys.as_ref()
+        .map(|k| k.iter().map(|f| felts_key(f)).collect::<Vec<_>>().join("|"))
+        .unwrap_or_default()
+}
+
+fn events_key(
+    from_block: &Option<BlockId>,
+    to_block: &Option<BlockId>,
+    keys: &Option<Vec<Vec<FieldElement>>>,
+    address: &Option<FieldElement>,
+    continuation_token: &Option<String>,
+) -> String {
+    format!(
+        "{}:{}:{}:{}:{}",
+        from_block.as_ref().map(block_key).unwrap_or_default(),
+        to_block.as_ref().map(block_key).unwrap_or_default(),
+        keys_key(keys),
+        address.as_ref().map(to_hex_str).unwrap_or_default(),
+        continuation_token.clone().unwrap_or_default(),
+    )
+}
+
+fn call_key(
+    contract_address: &FieldElement,
+    selector: &FieldElement,
+    calldata: &[FieldElement],
+    block: &BlockId,
+) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        to_hex_str(contract_address),
+        to_hex_str(selector),
+        felts_key(calldata),
+        block_key(block)
+    )
+}
+
+/// Parses a block id the same way the RPC clients do:
+/// `latest`, `pending`, an hexadecimal hash or a block number.
+pub fn parse_block_id(id: &str) -> Result<BlockId, StarknetClientError> {
+    match id {
+        "latest" => Ok(BlockId::Tag(BlockTag::Latest)),
+        "pending" => Ok(BlockId::Tag(BlockTag::Pending)),
+        _ if id.starts_with("0x") => FieldElement::from_hex_be(id)
+            .map(BlockId::Hash)
+            .map_err(|e| StarknetClientError::Other(format!("Invalid block hash {}: {}", id, e))),
+        _ => id
+            .parse::<u64>()
+            .map(BlockId::Number)
+            .map_err(|e| StarknetClientError::Other(format!("Invalid block id {}: {}", id, e))),
+    }
+}
+
+fn to_recorded<T: Clone>(r: &Result<T, StarknetClientError>) -> Recorded<T> {
+    match r {
+        Ok(v) => Ok(v.clone()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Client forwarding every request to the inner client,
+/// and recording the responses.
+pub struct RecordingClient<C: StarknetClient> {
+    inner: Arc<C>,
+    fixture: AsyncRwLock<Fixture>,
+}
+
+impl<C: StarknetClient + Send + Sync> RecordingClient<C> {
+    pub fn wrap(inner: Arc<C>) -> Self {
+        RecordingClient {
+            inner,
+            fixture: AsyncRwLock::new(Fixture::default()),
+        }
+    }
+
+    /// Returns a copy of the responses recorded so far.
+    pub async fn fixture(&self) -> Fixture {
+        self.fixture.read().await.clone()
+    }
+
+    pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), StarknetClientError> {
+        self.fixture.read().await.save(path)
+    }
+}
+
+#[async_trait]
+impl<C: StarknetClient + Send + Sync> StarknetClient for RecordingClient<C> {
+    fn new(rpc_url: &str) -> Result<Self, StarknetClientError>
+    where
+        Self: Sized,
+    {
+        Ok(RecordingClient::wrap(Arc::new(C::new(rpc_url)?)))
+    }
+
+    async fn block_txs_hashes(
+        &self,
+        block: BlockId,
+    ) -> Result<(u64, Vec<FieldElement>), StarknetClientError> {
+        let r = self.inner.block_txs_hashes(block).await;
+        self.fixture
+            .write()
+            .await
+            .block_txs_hashes
+            .insert(block_key(&block), to_recorded(&r));
+        r
+    }
+
+    async fn block_time(&self, block: BlockId) -> Result<u64, StarknetClientError> {
+        let r = self.inner.block_time(block).await;
+        self.fixture
+            .write()
+            .await
+            .block_times
+            .insert(block_key(&block), to_recorded(&r));
+        r
+    }
+
+    async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64, StarknetClientError> {
+        let r = self.inner.block_id_to_u64(id).await;
+        self.fixture
+            .write()
+            .await
+            .block_ids
+            .insert(block_key(id), to_recorded(&r));
+        r
+    }
+
+    fn parse_block_range(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(BlockId, BlockId), StarknetClientError> {
+        self.inner.parse_block_range(from, to)
+    }
+
+    fn parse_block_id(&self, id: &str) -> Result<BlockId, StarknetClientError> {
+        self.inner.parse_block_id(id)
+    }
+
+    async fn block_number(&self) -> Result<u64, StarknetClientError> {
+        let r = self.inner.block_number().await;
+        self.fixture.write().await.block_number = Some(to_recorded(&r));
+        r
+    }
+
+    async fn fetch_events(
+        &self,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+        continuation_token: Option<String>,
+    ) -> Result<FetchEventsResult, StarknetClientError> {
+        let key = events_key(&from_block, &to_block, &keys, &address, &continuation_token);
+
+        let r = self
+            .inner
+            .fetch_events(from_block, to_block, keys, address, continuation_token)
+            .await;
+
+        let recorded = match &r {
+            Ok(page) => Ok(RecordedEventsPage {
+                events: page
+                    .events
+                    .iter()
+                    .map(|(n, events)| (*n, events.clone()))
+                    .collect(),
+                continuation_token: page.continuation_token.clone(),
+            }),
+            Err(e) => Err(e.into()),
+        };
+
+        self.fixture
+            .write()
+            .await
+            .events_pages
+            .insert(key, recorded);
+        r
+    }
+
+    async fn fetch_all_block_events(
+        &self,
+        block: BlockId,
+        keys: Option<Vec<Vec<FieldElement>>>,
+    ) -> Result<HashMap<u64, Vec<EmittedEvent>>, StarknetClientError> {
+        let key = format!("{}:{}", block_key(&block), keys_key(&keys));
+        let r = self.inner.fetch_all_block_events(block, keys).await;
+
+        let recorded = match &r {
+            Ok(events) => Ok(events
+                .iter()
+                .map(|(n, events)| (*n, events.clone()))
+                .collect()),
+            Err(e) => Err(e.into()),
+        };
+
+        self.fixture
+            .write()
+            .await
+            .block_events
+            .insert(key, recorded);
+        r
+    }
+
+    async fn call_contract(
+        &self,
+        contract_address: FieldElement,
+        selector: FieldElement,
+        calldata: Vec<FieldElement>,
+        block: BlockId,
+    ) -> Result<Vec<FieldElement>, StarknetClientError> {
+        let key = call_key(&contract_address, &selector, &calldata, &block);
+        let r = self
+            .inner
+            .call_contract(contract_address, selector, calldata, block)
+            .await;
+
+        self.fixture
+            .write()
+            .await
+            .calls
+            .insert(key, to_recorded(&r));
+        r
+    }
+}
+
+/// Client answering only from a recorded fixture, without any network access.
+/// A request that was not recorded returns an `Other` error.
+pub struct ReplayClient {
+    fixture: Fixture,
+}
+
+impl ReplayClient {
+    pub fn from_fixture(fixture: Fixture) -> Self {
+        ReplayClient { fixture }
+    }
+
+    fn replay<T: Clone>(
+        entry: Option<&Recorded<T>>,
+        request: &str,
+    ) -> Result<T, StarknetClientError> {
+        trace!("Replaying {}", request);
+
+        match entry {
+            Some(Ok(v)) => Ok(v.clone()),
+            Some(Err(e)) => Err(e.clone().into()),
+            None => Err(StarknetClientError::Other(format!(
+                "No recorded response for {}",
+                request
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl StarknetClient for ReplayClient {
+    /// Expects the path of the fixture file.
+    fn new(rpc_url: &str) -> Result<Self, StarknetClientError>
+    where
+        Self: Sized,
+    {
+        Ok(ReplayClient::from_fixture(Fixture::load(rpc_url)?))
+    }
+
+    async fn block_txs_hashes(
+        &self,
+        block: BlockId,
+    ) -> Result<(u64, Vec<FieldElement>), StarknetClientError> {
+        let key = block_key(&block);
+        Self::replay(self.fixture.block_txs_hashes.get(&key), &key)
+    }
+
+    async fn block_time(&self, block: BlockId) -> Result<u64, StarknetClientError> {
+        let key = block_key(&block);
+        Self::replay(self.fixture.block_times.get(&key), &key)
+    }
+
+    async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64, StarknetClientError> {
+        if let BlockId::Number(n) = id {
+            return Ok(*n);
+        }
+
+        let key = block_key(id);
+        Self::replay(self.fixture.block_ids.get(&key), &key)
+    }
+
+    fn parse_block_range(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(BlockId, BlockId), StarknetClientError> {
+        Ok((parse_block_id(from)?, parse_block_id(to)?))
+    }
+
+    fn parse_block_id(&self, id: &str) -> Result<BlockId, StarknetClientError> {
+        parse_block_id(id)
+    }
+
+    async fn block_number(&self) -> Result<u64, StarknetClientError> {
+        Self::replay(self.fixture.block_number.as_ref(), "block_number")
+    }
+
+    async fn fetch_events(
+        &self,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+        continuation_token: Option<String>,
+    ) -> Result<FetchEventsResult, StarknetClientError> {
+        let key = events_key(&from_block, &to_block, &keys, &address, &continuation_token);
+        let page = Self::replay(self.fixture.events_pages.get(&key), &key)?;
+
+        Ok(FetchEventsResult {
+            events: page.events.into_iter().collect(),
+            continuation_token: page.continuation_token,
+        })
+    }
+
+    async fn fetch_all_block_events(
+        &self,
+        block: BlockId,
+        keys: Option<Vec<Vec<FieldElement>>>,
+    ) -> Result<HashMap<u64, Vec<EmittedEvent>>, StarknetClientError> {
+        let key = format!("{}:{}", block_key(&block), keys_key(&keys));
+        let events = Self::replay(self.fixture.block_events.get(&key), &key)?;
+        Ok(events.into_iter().collect())
+    }
+
+    async fn call_contract(
+        &self,
+        contract_address: FieldElement,
+        selector: FieldElement,
+        calldata: Vec<FieldElement>,
+        block: BlockId,
+    ) -> Result<Vec<FieldElement>, StarknetClientError> {
+        let key = call_key(&contract_address, &selector, &calldata, &block);
+        Self::replay(self.fixture.calls.get(&key), &key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_starknet::client::MockStarknetClient;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let mut mock_client = MockStarknetClient::default();
+        mock_client
+            .expect_block_time()
+            .returning(|_| Ok(1700000000));
+        mock_client.expect_call_contract().returning(|_, _, _, _| {
+            Err(StarknetClientError::EntrypointNotFound(
+                "owner_of".to_string(),
+            ))
+        });
+
+        let recorder = RecordingClient::wrap(Arc::new(mock_client));
+        let block = BlockId::Number(500000);
+
+        recorder.block_time(block).await.unwrap();
+        let _ = recorder
+            .call_contract(FieldElement::ONE, FieldElement::TWO, vec![], block)
+            .await;
+
+        let path = crate::testing::unique_temp_path("fixture.json");
+        recorder.save(&path).await.unwrap();
+
+        let replay = ReplayClient::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(replay.block_time(block).await.unwrap(), 1700000000);
+        assert!(matches!(
+            replay
+                .call_contract(FieldElement::ONE, FieldElement::TWO, vec![], block)
+                .await,
+            Err(StarknetClientError::EntrypointNotFound(_))
+        ));
+        assert!(replay.block_time(BlockId::Number(1)).await.is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
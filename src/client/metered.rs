@@ -0,0 +1,161 @@
+//! Client forwarding every request to the inner client, and recording
+//! the count and latency of the requests in the metrics.
+use crate::metrics::Metrics;
+use ark_starknet::client::{FetchEventsResult, StarknetClient, StarknetClientError};
+use async_trait::async_trait;
+use starknet::core::types::{BlockId, EmittedEvent, FieldElement};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub struct MeteredClient<C: StarknetClient> {
+    inner: Arc<C>,
+    metrics: Arc<Metrics>,
+}
+
+impl<C: StarknetClient + Send + Sync> MeteredClient<C> {
+    /// Wraps the client, recording in the metrics which are expected
+    /// to be the ones given to Pontos with `Pontos::with_metrics`.
+    pub fn wrap(inner: Arc<C>, metrics: Arc<Metrics>) -> Self {
+        MeteredClient { inner, metrics }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    async fn observe<T, F>(&self, method: &str, request: F) -> Result<T, StarknetClientError>
+    where
+        F: Future<Output = Result<T, StarknetClientError>>,
+    {
+        let start = Instant::now();
+        let r = request.await;
+        self.metrics.rpc_request(method, start.elapsed(), r.is_ok());
+        r
+    }
+}
+
+#[async_trait]
+impl<C: StarknetClient + Send + Sync> StarknetClient for MeteredClient<C> {
+    fn new(rpc_url: &str) -> Result<Self, StarknetClientError>
+    where
+        Self: Sized,
+    {
+        Ok(MeteredClient::wrap(
+            Arc::new(C::new(rpc_url)?),
+            Arc::new(Metrics::new()),
+        ))
+    }
+
+    async fn block_txs_hashes(
+        &self,
+        block: BlockId,
+    ) -> Result<(u64, Vec<FieldElement>), StarknetClientError> {
+        self.observe("block_txs_hashes", self.inner.block_txs_hashes(block))
+            .await
+    }
+
+    async fn block_time(&self, block: BlockId) -> Result<u64, StarknetClientError> {
+        self.observe("block_time", self.inner.block_time(block))
+            .await
+    }
+
+    async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64, StarknetClientError> {
+        self.observe("block_id_to_u64", self.inner.block_id_to_u64(id))
+            .await
+    }
+
+    fn parse_block_range(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(BlockId, BlockId), StarknetClientError> {
+        self.inner.parse_block_range(from, to)
+    }
+
+    fn parse_block_id(&self, id: &str) -> Result<BlockId, StarknetClientError> {
+        self.inner.parse_block_id(id)
+    }
+
+    /// Also updates the chain head of the metrics.
+    async fn block_number(&self) -> Result<u64, StarknetClientError> {
+        let r = self
+            .observe("block_number", self.inner.block_number())
+            .await;
+        if let Ok(block_number) = r {
+            self.metrics.set_chain_head(block_number);
+        }
+        r
+    }
+
+    async fn fetch_events(
+        &self,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+        continuation_token: Option<String>,
+    ) -> Result<FetchEventsResult, StarknetClientError> {
+        self.observe(
+            "fetch_events",
+            self.inner
+                .fetch_events(from_block, to_block, keys, address, continuation_token),
+        )
+        .await
+    }
+
+    async fn fetch_all_block_events(
+        &self,
+        block: BlockId,
+        keys: Option<Vec<Vec<FieldElement>>>,
+    ) -> Result<HashMap<u64, Vec<EmittedEvent>>, StarknetClientError> {
+        self.observe(
+            "fetch_all_block_events",
+            self.inner.fetch_all_block_events(block, keys),
+        )
+        .await
+    }
+
+    async fn call_contract(
+        &self,
+        contract_address: FieldElement,
+        selector: FieldElement,
+        calldata: Vec<FieldElement>,
+        block: BlockId,
+    ) -> Result<Vec<FieldElement>, StarknetClientError> {
+        self.observe(
+            "call_contract",
+            self.inner
+                .call_contract(contract_address, selector, calldata, block),
+        )
+        .await
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::testing::{FailurePoint, SyntheticChain, SyntheticClient};
+
+    #[tokio::test]
+    async fn test_records_requests() {
+        let chain = SyntheticChain::new().block(1, 1000).block(2, 1010);
+        let inner = Arc::new(SyntheticClient::from_chain(chain));
+        inner.inject_failure(FailurePoint::BlockTime(2), 1).await;
+
+        let client = MeteredClient::wrap(inner, Arc::new(Metrics::new()));
+        client.block_time(BlockId::Number(1)).await.unwrap();
+        assert!(client.block_time(BlockId::Number(2)).await.is_err());
+        assert_eq!(client.block_number().await.unwrap(), 2);
+
+        let metrics = client.metrics();
+        let text = metrics.render();
+        assert!(text.contains("pontos_rpc_requests_total{method=\"block_time\",status=\"ok\"} 1"));
+        assert!(
+            text.contains("pontos_rpc_requests_total{method=\"block_time\",status=\"error\"} 1")
+        );
+        assert!(text.contains("pontos_chain_head 2"));
+        assert_eq!(metrics.lag(), 2);
+    }
+}
@@ -0,0 +1,13 @@
+//! `StarknetClient` implementations composing or wrapping
+//! other clients, to be used as the client of Pontos.
+pub mod class_hash;
+pub use class_hash::ClassHashProvider;
+
+pub mod failover;
+pub use failover::{FailoverClient, FailoverConfig};
+
+pub mod fixture;
+pub use fixture::{Fixture, RecordingClient, ReplayClient};
+
+pub mod metered;
+pub use metered::MeteredClient;
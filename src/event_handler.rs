@@ -1,6 +1,11 @@
 //! Trait related to any events that Pontos can emit to be handled.
-use crate::storage::types::{TokenEvent, TokenInfo};
+use crate::storage::types::{
+    DeveloperActivity, FailedEvent, IndexingError, MemecoinCreatedEvent, MemecoinLaunchedEvent,
+    RpcError, SafetyReport, SwapEvent, TokenEvent, TokenInfo,
+};
 use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 
 /// A trait to be implemented in order to handle
 /// events emitted by Pontos, in an external code.
@@ -8,7 +13,8 @@ use async_trait::async_trait;
 /// Any long computation in the code of those functions
 /// will directly impact Pontos performances.
 /// Please consider spawning tasks if some work may
-/// be too heavy and impact negatively Pontos performances.
+/// be too heavy and impact negatively Pontos performances,
+/// or subscribing to the events with `Pontos::subscribe`.
 #[async_trait]
 #[allow(unused)]
 pub trait EventHandler {
@@ -29,4 +35,166 @@ pub trait EventHandler {
 
     // A new latest block has been detected.
     async fn on_new_latest_block(&self, block_number: u64) {}
+
+    /// A new memecoin has been deployed by the factory.
+    async fn on_memecoin_created(&self, event: MemecoinCreatedEvent) {}
+
+    /// A memecoin has been launched, its liquidity being added and locked.
+    async fn on_memecoin_launched(&self, event: MemecoinLaunchedEvent) {}
+
+    /// The safety report of a memecoin has been updated by new events.
+    async fn on_safety_report_updated(&self, report: SafetyReport) {}
+
+    /// A swap has been registered on a memecoin pool.
+    async fn on_swap_registered(&self, swap: SwapEvent) {}
+
+    /// A deployer or team allocation wallet has transferred or sold memecoins.
+    async fn on_developer_activity(&self, activity: DeveloperActivity) {}
+
+    /// An event couldn't be indexed, and is kept in the dead-letter store.
+    async fn on_event_failed(&self, failure: FailedEvent) {}
+
+    /// A block has been skipped without being indexed, because of the error.
+    async fn on_block_skipped(&self, block_number: u64, error: IndexingError) {}
+
+    /// A request to the node failed, and will be retried.
+    async fn on_rpc_error(&self, error: RpcError) {}
+}
+
+/// Events emitted by Pontos, one for each `EventHandler` callback.
+#[derive(Debug, Clone)]
+pub enum PontosEvent {
+    BlockProcessed {
+        block_number: u64,
+        indexation_progress: f64,
+    },
+    BlockProcessing {
+        block_timestamp: u64,
+        block_number: Option<u64>,
+    },
+    IndexationRangeCompleted,
+    TokenRegistered(TokenInfo),
+    EventRegistered(TokenEvent),
+    NewLatestBlock(u64),
+    MemecoinCreated(MemecoinCreatedEvent),
+    MemecoinLaunched(MemecoinLaunchedEvent),
+    SafetyReportUpdated(SafetyReport),
+    SwapRegistered(SwapEvent),
+    DeveloperActivity(DeveloperActivity),
+    EventFailed(FailedEvent),
+    BlockSkipped {
+        block_number: u64,
+        error: IndexingError,
+    },
+    RpcError(RpcError),
+}
+
+/// Forwards the events to the handler, and broadcasts them to the
+/// subscribers. Sending never waits: subscribers lagging behind
+/// lose the oldest events.
+pub(crate) struct EventDispatcher<E: EventHandler> {
+    handler: Arc<E>,
+    sender: broadcast::Sender<PontosEvent>,
+}
+
+impl<E: EventHandler> EventDispatcher<E> {
+    pub fn new(handler: Arc<E>, capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { handler, sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PontosEvent> {
+        self.sender.subscribe()
+    }
+
+    fn send(&self, event: PontosEvent) {
+        // Fails only when there is no subscriber.
+        let _ = self.sender.send(event);
+    }
+}
+
+#[async_trait]
+impl<E: EventHandler + Send + Sync> EventHandler for EventDispatcher<E> {
+    async fn on_block_processed(&self, block_number: u64, indexation_progress: f64) {
+        self.send(PontosEvent::BlockProcessed {
+            block_number,
+            indexation_progress,
+        });
+        self.handler
+            .on_block_processed(block_number, indexation_progress)
+            .await;
+    }
+
+    async fn on_block_processing(&self, block_timestamp: u64, block_number: Option<u64>) {
+        self.send(PontosEvent::BlockProcessing {
+            block_timestamp,
+            block_number,
+        });
+        self.handler
+            .on_block_processing(block_timestamp, block_number)
+            .await;
+    }
+
+    async fn on_indexation_range_completed(&self) {
+        self.send(PontosEvent::IndexationRangeCompleted);
+        self.handler.on_indexation_range_completed().await;
+    }
+
+    async fn on_token_registered(&self, token: TokenInfo) {
+        self.send(PontosEvent::TokenRegistered(token.clone()));
+        self.handler.on_token_registered(token).await;
+    }
+
+    async fn on_event_registered(&self, event: TokenEvent) {
+        self.send(PontosEvent::EventRegistered(event.clone()));
+        self.handler.on_event_registered(event).await;
+    }
+
+    async fn on_new_latest_block(&self, block_number: u64) {
+        self.send(PontosEvent::NewLatestBlock(block_number));
+        self.handler.on_new_latest_block(block_number).await;
+    }
+
+    async fn on_memecoin_created(&self, event: MemecoinCreatedEvent) {
+        self.send(PontosEvent::MemecoinCreated(event.clone()));
+        self.handler.on_memecoin_created(event).await;
+    }
+
+    async fn on_memecoin_launched(&self, event: MemecoinLaunchedEvent) {
+        self.send(PontosEvent::MemecoinLaunched(event.clone()));
+        self.handler.on_memecoin_launched(event).await;
+    }
+
+    async fn on_safety_report_updated(&self, report: SafetyReport) {
+        self.send(PontosEvent::SafetyReportUpdated(report.clone()));
+        self.handler.on_safety_report_updated(report).await;
+    }
+
+    async fn on_swap_registered(&self, swap: SwapEvent) {
+        self.send(PontosEvent::SwapRegistered(swap.clone()));
+        self.handler.on_swap_registered(swap).await;
+    }
+
+    async fn on_developer_activity(&self, activity: DeveloperActivity) {
+        self.send(PontosEvent::DeveloperActivity(activity.clone()));
+        self.handler.on_developer_activity(activity).await;
+    }
+
+    async fn on_event_failed(&self, failure: FailedEvent) {
+        self.send(PontosEvent::EventFailed(failure.clone()));
+        self.handler.on_event_failed(failure).await;
+    }
+
+    async fn on_block_skipped(&self, block_number: u64, error: IndexingError) {
+        self.send(PontosEvent::BlockSkipped {
+            block_number,
+            error: error.clone(),
+        });
+        self.handler.on_block_skipped(block_number, error).await;
+    }
+
+    async fn on_rpc_error(&self, error: RpcError) {
+        self.send(PontosEvent::RpcError(error.clone()));
+        self.handler.on_rpc_error(error).await;
+    }
 }
@@ -0,0 +1,931 @@
+//! GraphQL API over the indexed data, enabled with the `graphql` feature.
+//!
+//! The memecoins, tokens, contracts, events and blocks are exposed with
+//! their relations, so that a memecoin can be fetched with its holders,
+//! swaps and candles in a single query. Lists are Relay connections, the
+//! cursors being the timestamp and id of the items, for the next page not
+//! to shift when items are registered meanwhile.
+//!
+//! The depth and the complexity of the queries are limited, a query
+//! nesting the lists being otherwise able to load the whole storage.
+use crate::analysis::holders::{holders_from_transfers, Holder};
+use crate::storage::types::{
+    BlockInfo, Candle, CandleInterval, ContractInfo, Cursor, EventFilter, EventType, MarketMetrics,
+    MemecoinFilter, MemecoinInfo, MemecoinLaunchedEvent, Pagination, SafetyReport, StorageError,
+    SwapEvent, TokenEvent, TokenFilter, TokenInfo,
+};
+use crate::storage::Storage;
+use async_graphql::connection::{query, Connection, CursorType, Edge};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Error, Object, OutputType, Result};
+use async_graphql_axum::GraphQL;
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::Router;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// Upper bound of the page size, whatever is requested.
+pub const MAX_PAGE_SIZE: usize = 500;
+/// Upper bound of the nesting of the queries.
+pub const MAX_DEPTH: usize = 12;
+/// Upper bound of the number of fields resolved by a query.
+pub const MAX_COMPLEXITY: usize = 1_000;
+
+pub type PontosSchema = async_graphql::Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Storage the queries are resolved against, shared in the schema data.
+pub type SharedStorage = Arc<dyn Storage + Send + Sync>;
+
+pub type Page<T> = Connection<Cursor, T>;
+
+pub fn schema(storage: SharedStorage) -> PontosSchema {
+    async_graphql::Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(storage)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Cursors are formatted as `<timestamp>:<id>`.
+impl CursorType for Cursor {
+    type Error = String;
+
+    fn decode_cursor(s: &str) -> Result<Self, Self::Error> {
+        s.split_once(':')
+            .and_then(|(timestamp, id)| {
+                Some(Cursor {
+                    timestamp: timestamp.parse().ok()?,
+                    id: id.to_string(),
+                })
+            })
+            .ok_or_else(|| format!("Invalid cursor {s}"))
+    }
+
+    fn encode_cursor(&self) -> String {
+        format!("{}:{}", self.timestamp, self.id)
+    }
+}
+
+/// An item of a list, with its position in the list.
+trait Keyed {
+    fn cursor(&self) -> Cursor;
+}
+
+/// Returns the router serving the schema on `/graphql`, with a GraphiQL
+/// playground on `GET`.
+pub fn router(storage: SharedStorage) -> Router {
+    Router::new().route(
+        "/graphql",
+        get(graphiql).post_service(GraphQL::new(schema(storage))),
+    )
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn storage<'a>(ctx: &Context<'a>) -> &'a SharedStorage {
+    ctx.data_unchecked::<SharedStorage>()
+}
+
+/// Turns a missing item into `None`.
+fn optional<T>(result: Result<T, StorageError>) -> Result<Option<T>, StorageError> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(StorageError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the page of the list starting after the `after` cursor.
+/// One more item than requested is fetched to know if there is a next page.
+async fn paginate<T, F, Fut>(after: Option<String>, first: Option<i32>, fetch: F) -> Result<Page<T>>
+where
+    T: OutputType + Keyed,
+    F: FnOnce(Pagination) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, StorageError>>,
+{
+    query(
+        after,
+        None,
+        first,
+        None,
+        |after: Option<Cursor>, _before: Option<Cursor>, first, _last| async move {
+            let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+            let has_previous_page = after.is_some();
+
+            let mut items = fetch(Pagination {
+                offset: 0,
+                limit: limit as u64 + 1,
+                after,
+            })
+            .await?;
+
+            let has_next_page = items.len() > limit;
+            items.truncate(limit);
+
+            let mut connection = Connection::new(has_previous_page, has_next_page);
+            connection
+                .edges
+                .extend(items.into_iter().map(|item| Edge::new(item.cursor(), item)));
+            Ok::<_, Error>(connection)
+        },
+    )
+    .await
+}
+
+/// Paginates a list computed for the query, the page starting after the
+/// item of the cursor. Empty if that item is no longer in the list.
+fn slice<T: Keyed>(items: Vec<T>, pagination: Pagination) -> Vec<T> {
+    let start = match &pagination.after {
+        Some(c) => items
+            .iter()
+            .position(|i| i.cursor() == *c)
+            .map_or(items.len(), |i| i + 1),
+        None => 0,
+    };
+
+    items
+        .into_iter()
+        .skip(start)
+        .take(pagination.limit as usize)
+        .collect()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn memecoin(&self, ctx: &Context<'_>, address: String) -> Result<Option<Memecoin>> {
+        Ok(optional(storage(ctx).get_memecoin(&address).await)?.map(Memecoin))
+    }
+
+    /// Memecoins, the most recent first.
+    async fn memecoins(
+        &self,
+        ctx: &Context<'_>,
+        owner: Option<String>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Memecoin>> {
+        let storage = storage(ctx);
+        let filter = MemecoinFilter { owner };
+        paginate(after, first, |p| async move {
+            Ok(storage
+                .get_filtered_memecoins(&filter, &p)
+                .await?
+                .into_iter()
+                .map(Memecoin)
+                .collect())
+        })
+        .await
+    }
+
+    async fn token(
+        &self,
+        ctx: &Context<'_>,
+        contract_address: String,
+        token_id_hex: String,
+    ) -> Result<Option<Token>> {
+        Ok(optional(
+            storage(ctx)
+                .get_token(&contract_address, &token_id_hex)
+                .await,
+        )?
+        .map(Token))
+    }
+
+    async fn tokens(
+        &self,
+        ctx: &Context<'_>,
+        contract_address: Option<String>,
+        owner: Option<String>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Token>> {
+        let filter = TokenFilter {
+            contract_address,
+            owner,
+        };
+        tokens(storage(ctx), filter, after, first).await
+    }
+
+    async fn contract(&self, ctx: &Context<'_>, address: String) -> Result<Option<Contract>> {
+        Ok(optional(storage(ctx).get_contract_info(&address).await)?.map(Contract))
+    }
+
+    /// Contracts, the most recently registered first.
+    async fn contracts(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Contract>> {
+        let storage = storage(ctx);
+        paginate(after, first, |p| async move {
+            Ok(storage
+                .get_contract_infos(&p)
+                .await?
+                .into_iter()
+                .map(Contract)
+                .collect())
+        })
+        .await
+    }
+
+    /// Events, the most recent first. The type is one of `MINT`, `BURN`
+    /// or `TRANSFER`.
+    #[allow(clippy::too_many_arguments)]
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        contract_address: Option<String>,
+        token_id_hex: Option<String>,
+        address: Option<String>,
+        event_type: Option<String>,
+        from_timestamp: Option<u64>,
+        to_timestamp: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Event>> {
+        let event_type = match event_type {
+            Some(t) => Some(
+                EventType::from_str(&t)
+                    .map_err(|_| Error::new(format!("Invalid event type {t}")))?,
+            ),
+            None => None,
+        };
+        let filter = EventFilter {
+            contract_address,
+            token_id_hex,
+            address,
+            event_type,
+            from_timestamp,
+            to_timestamp,
+        };
+        events(storage(ctx), filter, after, first).await
+    }
+
+    async fn block(&self, ctx: &Context<'_>, number: u64) -> Result<Option<Block>> {
+        Ok(optional(storage(ctx).get_block(number).await)?.map(Block::from))
+    }
+
+    /// Blocks, the most recent first.
+    async fn blocks(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Block>> {
+        let storage = storage(ctx);
+        paginate(after, first, |p| async move {
+            Ok(storage
+                .get_blocks(&p)
+                .await?
+                .into_iter()
+                .map(Block::from)
+                .collect())
+        })
+        .await
+    }
+}
+
+async fn tokens(
+    storage: &SharedStorage,
+    filter: TokenFilter,
+    after: Option<String>,
+    first: Option<i32>,
+) -> Result<Page<Token>> {
+    paginate(after, first, |p| async move {
+        Ok(storage
+            .get_tokens(&filter, &p)
+            .await?
+            .into_iter()
+            .map(Token)
+            .collect())
+    })
+    .await
+}
+
+async fn events(
+    storage: &SharedStorage,
+    filter: EventFilter,
+    after: Option<String>,
+    first: Option<i32>,
+) -> Result<Page<Event>> {
+    paginate(after, first, |p| async move {
+        Ok(storage
+            .get_events(&filter, &p)
+            .await?
+            .into_iter()
+            .map(Event)
+            .collect())
+    })
+    .await
+}
+
+impl Keyed for Holder {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: 0,
+            id: self.address.clone(),
+        }
+    }
+}
+
+impl Keyed for SwapEvent {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.block_timestamp,
+            id: self.event_id.clone(),
+        }
+    }
+}
+
+pub struct Memecoin(MemecoinInfo);
+
+impl Keyed for Memecoin {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.0.created_at,
+            id: self.0.memecoin_address.clone(),
+        }
+    }
+}
+
+#[Object]
+impl Memecoin {
+    async fn address(&self) -> &str {
+        &self.0.memecoin_address
+    }
+
+    async fn owner(&self) -> &str {
+        &self.0.owner
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    /// Decimal string.
+    async fn initial_supply(&self) -> &str {
+        &self.0.initial_supply
+    }
+
+    async fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+
+    async fn launch(&self) -> Option<Launch> {
+        self.0.launch.clone().map(Launch)
+    }
+
+    /// Holders at the given block, the last indexed one by default,
+    /// ordered by decreasing balance.
+    async fn holders(
+        &self,
+        ctx: &Context<'_>,
+        block_number: Option<u64>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Holder>> {
+        let transfers = storage(ctx)
+            .get_token_transfers(&self.0.memecoin_address, block_number)
+            .await?;
+        let holders = holders_from_transfers(&transfers);
+        paginate(after, first, |p| async move { Ok(slice(holders, p)) }).await
+    }
+
+    /// Swaps on all the pools of the memecoin, the most recent first.
+    async fn swaps(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<SwapEvent>> {
+        let storage = storage(ctx);
+
+        let mut swaps = vec![];
+        for pool in storage.get_pools().await? {
+            if pool.memecoin_address == self.0.memecoin_address {
+                swaps.extend(storage.get_swaps(&pool.pool_address, 0, u64::MAX).await?);
+            }
+        }
+        swaps.sort_by(|a, b| {
+            b.block_timestamp
+                .cmp(&a.block_timestamp)
+                .then_with(|| a.event_id.cmp(&b.event_id))
+        });
+
+        paginate(after, first, |p| async move { Ok(slice(swaps, p)) }).await
+    }
+
+    /// Candles opened between the two times included.
+    async fn candles(
+        &self,
+        ctx: &Context<'_>,
+        interval: CandleInterval,
+        from_time: Option<u64>,
+        to_time: Option<u64>,
+    ) -> Result<Vec<Candle>> {
+        Ok(storage(ctx)
+            .get_candles(
+                &self.0.memecoin_address,
+                interval,
+                from_time.unwrap_or(0),
+                to_time.unwrap_or(u64::MAX),
+            )
+            .await?)
+    }
+
+    async fn safety(&self, ctx: &Context<'_>) -> Result<Option<SafetyReport>> {
+        Ok(optional(
+            storage(ctx)
+                .get_safety_report(&self.0.memecoin_address)
+                .await,
+        )?)
+    }
+
+    async fn metrics(&self, ctx: &Context<'_>) -> Result<Option<MarketMetrics>> {
+        Ok(optional(
+            storage(ctx)
+                .get_market_metrics(&self.0.memecoin_address)
+                .await,
+        )?)
+    }
+}
+
+pub struct Launch(MemecoinLaunchedEvent);
+
+#[Object]
+impl Launch {
+    async fn quote_token(&self) -> &str {
+        &self.0.quote_token
+    }
+
+    async fn liquidity_type(&self) -> String {
+        self.0.liquidity_type.to_string()
+    }
+
+    async fn locked_liquidity(&self) -> Option<&str> {
+        self.0.locked_liquidity.as_deref()
+    }
+
+    async fn team_allocation(&self) -> Option<&str> {
+        self.0.team_allocation.as_deref()
+    }
+
+    async fn transaction_hash(&self) -> &str {
+        &self.0.transaction_hash
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+        Ok(optional(storage(ctx).get_block(self.0.block_number).await)?.map(Block::from))
+    }
+}
+
+pub struct Token(TokenInfo);
+
+impl Keyed for Token {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: 0,
+            id: format!("{}/{}", self.0.contract_address, self.0.token_id_hex),
+        }
+    }
+}
+
+#[Object]
+impl Token {
+    async fn contract_address(&self) -> &str {
+        &self.0.contract_address
+    }
+
+    async fn token_id(&self) -> &str {
+        &self.0.token_id
+    }
+
+    async fn token_id_hex(&self) -> &str {
+        &self.0.token_id_hex
+    }
+
+    async fn owner(&self) -> &str {
+        &self.0.owner
+    }
+
+    async fn contract(&self, ctx: &Context<'_>) -> Result<Option<Contract>> {
+        Ok(optional(
+            storage(ctx)
+                .get_contract_info(&self.0.contract_address)
+                .await,
+        )?
+        .map(Contract))
+    }
+
+    /// Events of the token, the most recent first.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Event>> {
+        let filter = EventFilter {
+            contract_address: Some(self.0.contract_address.clone()),
+            token_id_hex: Some(self.0.token_id_hex.clone()),
+            ..Default::default()
+        };
+        events(storage(ctx), filter, after, first).await
+    }
+}
+
+pub struct Contract(ContractInfo);
+
+impl Keyed for Contract {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.0.block_timestamp,
+            id: self.0.contract_address.clone(),
+        }
+    }
+}
+
+#[Object]
+impl Contract {
+    async fn address(&self) -> &str {
+        &self.0.contract_address
+    }
+
+    async fn contract_type(&self) -> &str {
+        &self.0.contract_type
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn symbol(&self) -> Option<&str> {
+        self.0.symbol.as_deref()
+    }
+
+    async fn image(&self) -> Option<&str> {
+        self.0.image.as_deref()
+    }
+
+    async fn identification_method(&self) -> Option<&str> {
+        self.0.identification_method.as_deref()
+    }
+
+    async fn tokens(
+        &self,
+        ctx: &Context<'_>,
+        owner: Option<String>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Token>> {
+        let filter = TokenFilter {
+            contract_address: Some(self.0.contract_address.clone()),
+            owner,
+        };
+        tokens(storage(ctx), filter, after, first).await
+    }
+
+    /// Events of the contract, the most recent first.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Event>> {
+        let filter = EventFilter {
+            contract_address: Some(self.0.contract_address.clone()),
+            ..Default::default()
+        };
+        events(storage(ctx), filter, after, first).await
+    }
+}
+
+pub struct Event(TokenEvent);
+
+impl Keyed for Event {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.0.timestamp,
+            id: self.0.event_id.clone(),
+        }
+    }
+}
+
+#[Object]
+impl Event {
+    async fn event_id(&self) -> &str {
+        &self.0.event_id
+    }
+
+    async fn event_type(&self) -> String {
+        self.0.event_type.to_string()
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    async fn from_address(&self) -> &str {
+        &self.0.from_address
+    }
+
+    async fn to_address(&self) -> &str {
+        &self.0.to_address
+    }
+
+    async fn contract_address(&self) -> &str {
+        &self.0.contract_address
+    }
+
+    async fn contract_type(&self) -> &str {
+        &self.0.contract_type
+    }
+
+    async fn transaction_hash(&self) -> &str {
+        &self.0.transaction_hash
+    }
+
+    async fn token_id(&self) -> &str {
+        &self.0.token_id
+    }
+
+    async fn token_id_hex(&self) -> &str {
+        &self.0.token_id_hex
+    }
+
+    async fn token(&self, ctx: &Context<'_>) -> Result<Option<Token>> {
+        Ok(optional(
+            storage(ctx)
+                .get_token(&self.0.contract_address, &self.0.token_id_hex)
+                .await,
+        )?
+        .map(Token))
+    }
+
+    async fn contract(&self, ctx: &Context<'_>) -> Result<Option<Contract>> {
+        Ok(optional(
+            storage(ctx)
+                .get_contract_info(&self.0.contract_address)
+                .await,
+        )?
+        .map(Contract))
+    }
+
+    async fn block(&self, ctx: &Context<'_>) -> Result<Option<Block>> {
+        match self.0.block_number {
+            Some(n) => Ok(optional(storage(ctx).get_block(n).await)?.map(Block::from)),
+            None => Ok(None),
+        }
+    }
+}
+
+pub struct Block {
+    timestamp: u64,
+    info: BlockInfo,
+}
+
+impl Keyed for Block {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            timestamp: self.timestamp,
+            id: self.info.block_number.to_string(),
+        }
+    }
+}
+
+impl From<(u64, BlockInfo)> for Block {
+    fn from((timestamp, info): (u64, BlockInfo)) -> Self {
+        Block { timestamp, info }
+    }
+}
+
+#[Object]
+impl Block {
+    async fn number(&self) -> u64 {
+        self.info.block_number
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    async fn status(&self) -> String {
+        self.info.status.to_string()
+    }
+
+    async fn indexer_version(&self) -> &str {
+        &self.info.indexer_version
+    }
+
+    async fn indexer_identifier(&self) -> &str {
+        &self.info.indexer_identifier
+    }
+
+    /// Events of the block, the most recent first.
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Page<Event>> {
+        let filter = EventFilter {
+            from_timestamp: Some(self.timestamp),
+            to_timestamp: Some(self.timestamp),
+            ..Default::default()
+        };
+        events(storage(ctx), filter, after, first).await
+    }
+
+    async fn swaps(&self, ctx: &Context<'_>) -> Result<Vec<SwapEvent>> {
+        Ok(storage(ctx).get_block_swaps(self.timestamp).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::{BlockIndexingStatus, MemecoinCreatedEvent, TokenTransfer};
+    use crate::testing::MemoryStorage;
+    use ark_starknet::CairoU256;
+
+    async fn storage() -> Arc<MemoryStorage> {
+        let storage = Arc::new(MemoryStorage::new());
+
+        let info = BlockInfo {
+            indexer_version: "0.0.1".to_string(),
+            indexer_identifier: "test".to_string(),
+            status: BlockIndexingStatus::Terminated,
+            block_number: 10,
+        };
+        storage.set_block_info(10, 1000, info).await.unwrap();
+
+        let created = MemecoinCreatedEvent::new(
+            "0xa".to_string(),
+            "Meme".to_string(),
+            "MEME".to_string(),
+            CairoU256 { low: 60, high: 0 },
+            "0xe1".to_string(),
+        );
+        storage
+            .register_memecoin_created_event(&created, 1000)
+            .await
+            .unwrap();
+
+        for (i, (to, amount)) in [("0xb", "10"), ("0xc", "30"), ("0xd", "20")]
+            .into_iter()
+            .enumerate()
+        {
+            let transfer = TokenTransfer {
+                token_address: "0xe1".to_string(),
+                from_address: "0x0".to_string(),
+                to_address: to.to_string(),
+                amount: amount.to_string(),
+                event_id: format!("0x{i}"),
+                block_number: 10,
+                ..Default::default()
+            };
+            storage
+                .register_token_transfer(&transfer, 1000)
+                .await
+                .unwrap();
+        }
+
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_memecoin_holders() {
+        let schema = schema(storage().await);
+
+        let response = schema
+            .execute(
+                r#"{
+                    memecoin(address: "0xe1") {
+                        symbol
+                        holders(first: 2) {
+                            edges { cursor node { address balance } }
+                            pageInfo { hasNextPage endCursor }
+                        }
+                    }
+                }"#,
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        let memecoin = &data["memecoin"];
+        assert_eq!(memecoin["symbol"], "MEME");
+        let holders = &memecoin["holders"];
+        assert_eq!(holders["edges"][0]["node"]["address"], "0xc");
+        assert_eq!(holders["edges"][1]["node"]["address"], "0xd");
+        assert_eq!(holders["pageInfo"]["hasNextPage"], true);
+
+        let cursor = holders["pageInfo"]["endCursor"].as_str().unwrap();
+        let response = schema
+            .execute(format!(
+                r#"{{
+                    memecoin(address: "0xe1") {{
+                        holders(first: 2, after: "{cursor}") {{
+                            edges {{ node {{ address }} }}
+                            pageInfo {{ hasNextPage }}
+                        }}
+                    }}
+                }}"#
+            ))
+            .await;
+        let data = response.data.into_json().unwrap();
+        let holders = &data["memecoin"]["holders"];
+        assert_eq!(holders["edges"][0]["node"]["address"], "0xb");
+        assert_eq!(holders["pageInfo"]["hasNextPage"], false);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_after_new_items() {
+        let storage = storage().await;
+        let schema = schema(Arc::clone(&storage) as SharedStorage);
+
+        let created = |address: &str| {
+            MemecoinCreatedEvent::new(
+                "0xa".to_string(),
+                "Meme".to_string(),
+                "MEME".to_string(),
+                CairoU256 { low: 60, high: 0 },
+                address.to_string(),
+            )
+        };
+        storage
+            .register_memecoin_created_event(&created("0xe2"), 1010)
+            .await
+            .unwrap();
+
+        let response = schema
+            .execute(
+                "{ memecoins(first: 1) { edges { node { address } } pageInfo { endCursor } } }",
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["memecoins"]["edges"][0]["node"]["address"], "0xe2");
+        let cursor = data["memecoins"]["pageInfo"]["endCursor"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(cursor, "1010:0xe2");
+
+        // A memecoin created meanwhile doesn't shift the next page.
+        storage
+            .register_memecoin_created_event(&created("0xe3"), 1020)
+            .await
+            .unwrap();
+
+        let response = schema
+            .execute(format!(
+                r#"{{ memecoins(first: 1, after: "{cursor}") {{ edges {{ node {{ address }} }} }} }}"#
+            ))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["memecoins"]["edges"][0]["node"]["address"], "0xe1");
+    }
+
+    #[tokio::test]
+    async fn test_depth_limit() {
+        let schema = schema(storage().await);
+
+        let mut query = "number".to_string();
+        for _ in 0..MAX_DEPTH {
+            query = format!("events {{ edges {{ node {{ block {{ {query} }} }} }} }}");
+        }
+        let response = schema
+            .execute(format!("{{ block(number: 10) {{ {query} }} }}"))
+            .await;
+        assert!(!response.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_block() {
+        let schema = schema(storage().await);
+
+        let response = schema
+            .execute(
+                "{ block(number: 10) { timestamp status } missing: block(number: 11) { number } }",
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["block"]["timestamp"], 1000);
+        assert!(data["missing"].is_null());
+    }
+}
@@ -0,0 +1,203 @@
+//! Liveness and readiness of the indexer, served on `/health` and
+//! `/ready` if the `health` feature is enabled.
+//!
+//! The indexer is live while its loop keeps iterating, and ready once
+//! it is also close enough to the chain head. The chain head is the one
+//! last fetched by the indexing loop, the probes never requesting the node.
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[cfg(feature = "health")]
+use crate::{event_handler::EventHandler, storage::Storage, Pontos};
+#[cfg(feature = "health")]
+use ark_starknet::client::StarknetClient;
+#[cfg(feature = "health")]
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+#[cfg(feature = "health")]
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Maximum number of blocks behind the chain head to be ready.
+    pub max_lag: u64,
+    /// Maximum delay without any iteration of the indexing loop
+    /// before it is considered as stalled.
+    pub max_stall: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig {
+            max_lag: 10,
+            max_stall: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// The indexing loop iterated within `max_stall`.
+    pub progressing: bool,
+    /// Progressing and at most `max_lag` blocks behind the chain head.
+    pub ready: bool,
+    pub last_indexed_block: Option<u64>,
+    pub last_indexed_block_timestamp: Option<u64>,
+    /// `None` until fetched by the indexing loop.
+    pub chain_head: Option<u64>,
+    pub lag: Option<u64>,
+    pub seconds_since_progress: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    last_iteration: Option<Instant>,
+    /// Number and timestamp of the last indexed block.
+    last_block: Option<(u64, u64)>,
+    chain_head: Option<u64>,
+}
+
+/// Progress of the indexing loop, updated by Pontos.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    progress: Mutex<Progress>,
+}
+
+impl ProgressTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an iteration of the indexing loop, even without new block.
+    pub fn iteration(&self) {
+        self.progress.lock().unwrap().last_iteration = Some(Instant::now());
+    }
+
+    pub fn block_indexed(&self, block_number: u64, block_timestamp: u64) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.last_iteration = Some(Instant::now());
+        progress.last_block = Some((block_number, block_timestamp));
+    }
+
+    pub fn set_chain_head(&self, block_number: u64) {
+        self.progress.lock().unwrap().chain_head = Some(block_number);
+    }
+
+    pub fn report(&self, config: &HealthConfig) -> HealthReport {
+        let progress = self.progress.lock().unwrap();
+        let chain_head = progress.chain_head;
+
+        let since_progress = progress.last_iteration.map(|i| i.elapsed());
+        let progressing = since_progress.map_or(false, |d| d <= config.max_stall);
+        let last_block = progress.last_block.map(|(number, _)| number);
+        let lag = chain_head
+            .zip(last_block)
+            .map(|(head, last)| head.saturating_sub(last));
+
+        HealthReport {
+            progressing,
+            ready: progressing && lag.map_or(false, |l| l <= config.max_lag),
+            last_indexed_block: last_block,
+            last_indexed_block_timestamp: progress.last_block.map(|(_, ts)| ts),
+            chain_head,
+            lag,
+            seconds_since_progress: since_progress.map(|d| d.as_secs()),
+        }
+    }
+}
+
+/// Returns the router serving the liveness on `/health` and the
+/// readiness on `/ready`, both answering `503` when failing.
+#[cfg(feature = "health")]
+pub fn router<S, C, E>(pontos: Arc<Pontos<S, C, E>>) -> Router
+where
+    S: Storage + Send + Sync + 'static,
+    C: StarknetClient + Send + Sync + 'static,
+    E: EventHandler + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/health", get(health::<S, C, E>))
+        .route("/ready", get(ready::<S, C, E>))
+        .with_state(pontos)
+}
+
+#[cfg(feature = "health")]
+async fn health<S, C, E>(
+    State(pontos): State<Arc<Pontos<S, C, E>>>,
+) -> (StatusCode, Json<HealthReport>)
+where
+    S: Storage + Send + Sync + 'static,
+    C: StarknetClient + Send + Sync + 'static,
+    E: EventHandler + Send + Sync + 'static,
+{
+    let report = pontos.health();
+    (status(report.progressing), Json(report))
+}
+
+#[cfg(feature = "health")]
+async fn ready<S, C, E>(
+    State(pontos): State<Arc<Pontos<S, C, E>>>,
+) -> (StatusCode, Json<HealthReport>)
+where
+    S: Storage + Send + Sync + 'static,
+    C: StarknetClient + Send + Sync + 'static,
+    E: EventHandler + Send + Sync + 'static,
+{
+    let report = pontos.health();
+    (status(report.ready), Json(report))
+}
+
+#[cfg(feature = "health")]
+fn status(ok: bool) -> StatusCode {
+    if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_report() {
+        let config = HealthConfig {
+            max_lag: 2,
+            max_stall: Duration::from_secs(60),
+        };
+        let tracker = ProgressTracker::new();
+
+        let report = tracker.report(&config);
+        assert!(!report.progressing);
+        assert!(!report.ready);
+        assert_eq!(report.lag, None);
+
+        // The chain head isn't fetched yet.
+        tracker.block_indexed(7, 1000);
+        let report = tracker.report(&config);
+        assert!(report.progressing);
+        assert!(!report.ready);
+        assert_eq!(report.chain_head, None);
+
+        tracker.set_chain_head(10);
+        let report = tracker.report(&config);
+        assert!(!report.ready);
+        assert_eq!(report.lag, Some(3));
+
+        tracker.block_indexed(8, 1010);
+        let report = tracker.report(&config);
+        assert!(report.ready);
+        assert_eq!(report.last_indexed_block_timestamp, Some(1010));
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        let report = tracker.report(&config);
+        assert!(!report.progressing);
+        assert!(!report.ready);
+        assert_eq!(report.seconds_since_progress, Some(61));
+
+        tracker.iteration();
+        assert!(tracker.report(&config).ready);
+    }
+}
@@ -1,23 +1,62 @@
+pub mod analysis;
+#[cfg(feature = "api")]
+pub mod api;
+pub mod client;
 pub mod event_handler;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod health;
 pub mod managers;
+pub mod metrics;
 pub mod storage;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
+use crate::analysis::holders::{HolderSnapshot, HolderVerification};
+use crate::analysis::{
+    CandleAggregator, DeveloperTracker, HolderSnapshotter, MarketAnalyzer, PortfolioTracker,
+    PriceOracle, PricingConfig, SafetyAnalyzer, SniperConfig, SniperDetector, TrendingAnalyzer,
+    TrendingConfig,
+};
 use crate::storage::types::BlockIndexingStatus;
 use anyhow::Result;
 use ark_starknet::client::{StarknetClient, StarknetClientError};
 use ark_starknet::format::to_hex_str;
-use event_handler::EventHandler;
-use managers::{BlockManager, ContractManager, EventManager, PendingBlockData, TokenManager};
+use ark_starknet::CairoU256;
+use client::ClassHashProvider;
+use event_handler::{EventDispatcher, EventHandler, PontosEvent};
+use health::{HealthConfig, HealthReport, ProgressTracker};
+use managers::{
+    BlockIndexing, BlockManager, ContractCacheConfig, ContractCacheStats, ContractManager,
+    EkuboRecipients, EventManager, FailureManager, MemecoinEvent, MemecoinManager,
+    PendingBlockData, ReplayReport, SwapManager, TokenManager,
+};
+use metrics::Metrics;
 use starknet::core::types::*;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
-use storage::types::{ContractType, StorageError};
+use std::time::{Duration, Instant};
+use storage::types::{
+    ContractType, DeveloperActivity, DeveloperWallet, EventFailureKind, FailedEvent,
+    FailedEventStatus, IndexingError, MarketMetrics, PortfolioSnapshot, Position, RpcError,
+    StorageError, TokenEvent, TrendingScore,
+};
 use storage::Storage;
-use tokio::sync::RwLock as AsyncRwLock;
+use tokio::sync::{broadcast, RwLock as AsyncRwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, trace, warn};
 
 pub type IndexerResult<T> = Result<T, IndexerError>;
 
+/// Number of events kept for the slowest subscriber.
+pub const EVENTS_CAPACITY: usize = 1024;
+
+/// Minimum delay between two fetches of the chain head while indexing.
+pub const CHAIN_HEAD_REFRESH: Duration = Duration::from_secs(10);
+
 /// Generic errors for Pontos.
 #[derive(Debug)]
 pub enum IndexerError {
@@ -59,20 +98,53 @@ impl std::error::Error for IndexerError {}
 pub struct PontosConfig {
     pub indexer_version: String,
     pub indexer_identifier: String,
+    pub contract_cache: ContractCacheConfig,
+    /// Unruggable factories whose memecoin events are indexed.
+    /// Events from any emitter are accepted if empty.
+    pub memecoin_factories: Vec<FieldElement>,
+    /// Lock managers whose liquidity locks are indexed.
+    /// Events from any emitter are accepted if empty.
+    pub lock_managers: Vec<FieldElement>,
+    /// Ekubo core contract, emitting the swaps of every Ekubo pool.
+    /// See `managers::swap_manager::EKUBO_CORE_ADDRESS` for mainnet.
+    pub ekubo_core: FieldElement,
+    pub trending: TrendingConfig,
+    pub sniper: SniperConfig,
+    pub pricing: PricingConfig,
+    /// Attempts of a failed event, including its first processing, before
+    /// it is permanently failed and not replayed anymore.
+    pub max_replay_attempts: u32,
+    pub health: HealthConfig,
 }
 
 pub struct Pontos<S: Storage, C: StarknetClient, E: EventHandler> {
     client: Arc<C>,
-    event_handler: Arc<E>,
+    event_handler: Arc<EventDispatcher<E>>,
     config: PontosConfig,
     block_manager: Arc<BlockManager<S>>,
     event_manager: Arc<EventManager<S>>,
     token_manager: Arc<TokenManager<S, C>>,
-    contract_manager: Arc<AsyncRwLock<ContractManager<S, C>>>,
+    contract_manager: Arc<ContractManager<S, C>>,
+    memecoin_manager: Arc<MemecoinManager<S, C>>,
+    safety_analyzer: Arc<SafetyAnalyzer<S, C>>,
+    swap_manager: Arc<SwapManager<S>>,
+    price_oracle: Arc<PriceOracle<S, C>>,
+    candle_aggregator: Arc<CandleAggregator<S, C>>,
+    market_analyzer: Arc<MarketAnalyzer<S, C>>,
+    trending_analyzer: Arc<TrendingAnalyzer<S, C>>,
+    sniper_detector: Arc<SniperDetector<S>>,
+    developer_tracker: Arc<DeveloperTracker<S>>,
+    portfolio_tracker: Arc<PortfolioTracker<S>>,
+    holder_snapshotter: Arc<HolderSnapshotter<S, C>>,
+    failure_manager: Arc<FailureManager<S>>,
+    metrics: Arc<Metrics>,
+    progress: Arc<ProgressTracker>,
     pending_cache: Arc<AsyncRwLock<PendingBlockData>>,
 }
 
-impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C, E> {
+impl<S: Storage + Send + Sync + 'static, C: StarknetClient, E: EventHandler + Send + Sync>
+    Pontos<S, C, E>
+{
     ///
     pub fn new(
         client: Arc<C>,
@@ -80,27 +152,360 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
         event_handler: Arc<E>,
         config: PontosConfig,
     ) -> Self {
+        let price_oracle = Arc::new(PriceOracle::new(
+            Arc::clone(&storage),
+            Arc::clone(&client),
+            &config.pricing,
+        ));
+
         Pontos {
-            config,
             client: Arc::clone(&client),
-            event_handler: Arc::clone(&event_handler),
+            event_handler: Arc::new(EventDispatcher::new(event_handler, EVENTS_CAPACITY)),
             block_manager: Arc::new(BlockManager::new(Arc::clone(&storage))),
             event_manager: Arc::new(EventManager::new(Arc::clone(&storage))),
             token_manager: Arc::new(TokenManager::new(Arc::clone(&storage), Arc::clone(&client))),
-            // Contract manager has internal cache, so some functions are using `&mut self`.
-            // For this reason, we must protect the write operations in order to share
-            // the cache with any possible thread using `index_block_range` of this instance.
-            contract_manager: Arc::new(AsyncRwLock::new(ContractManager::new(
+            // Contract manager cache is internally synchronized, and can be
+            // shared with any possible thread using `index_block_range` of this instance.
+            contract_manager: Arc::new(ContractManager::new(
+                Arc::clone(&storage),
+                Arc::clone(&client),
+                config.contract_cache.clone(),
+            )),
+            memecoin_manager: Arc::new(MemecoinManager::new(
+                Arc::clone(&storage),
+                Arc::clone(&client),
+                config.memecoin_factories.clone(),
+                config.lock_managers.clone(),
+                config.sniper.funding_tokens.clone(),
+            )),
+            safety_analyzer: Arc::new(SafetyAnalyzer::new(
                 Arc::clone(&storage),
                 Arc::clone(&client),
-            ))),
+                config.ekubo_core,
+            )),
+            swap_manager: Arc::new(SwapManager::new(
+                Arc::clone(&storage),
+                config.ekubo_core,
+                config.pricing.pools(),
+            )),
+            candle_aggregator: Arc::new(CandleAggregator::new(
+                Arc::clone(&storage),
+                Arc::clone(&price_oracle),
+            )),
+            market_analyzer: Arc::new(MarketAnalyzer::new(
+                Arc::clone(&storage),
+                Arc::clone(&price_oracle),
+                config.ekubo_core,
+            )),
+            trending_analyzer: Arc::new(TrendingAnalyzer::new(
+                Arc::clone(&storage),
+                Arc::clone(&price_oracle),
+                config.trending.clone(),
+            )),
+            price_oracle,
+            sniper_detector: Arc::new(SniperDetector::new(Arc::clone(&storage), &config.sniper)),
+            developer_tracker: Arc::new(DeveloperTracker::new(
+                Arc::clone(&storage),
+                config.ekubo_core,
+            )),
+            portfolio_tracker: Arc::new(PortfolioTracker::new(Arc::clone(&storage))),
+            holder_snapshotter: Arc::new(HolderSnapshotter::new(
+                Arc::clone(&storage),
+                Arc::clone(&client),
+            )),
+            failure_manager: Arc::new(FailureManager::new(
+                Arc::clone(&storage),
+                config.max_replay_attempts,
+            )),
+            metrics: Arc::new(Metrics::new()),
+            progress: Arc::new(ProgressTracker::new()),
             pending_cache: Arc::new(AsyncRwLock::new(PendingBlockData::new())),
+            config,
         }
     }
 
+    /// Enables the identification of contracts by their class hash, which
+    /// avoids probing every contract deployed with an already known class.
+    pub fn with_class_hash_provider(
+        mut self,
+        provider: Arc<dyn ClassHashProvider + Send + Sync>,
+    ) -> Self {
+        Arc::get_mut(&mut self.contract_manager)
+            .expect("Contract manager is not shared before Pontos is built")
+            .set_class_hash_provider(provider);
+        self
+    }
+
+    /// Records the metrics in the given ones instead of its own, to share
+    /// them with a `client::MeteredClient`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Returns the metrics of the indexation.
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Returns the liveness and readiness of the indexation, comparing
+    /// the last indexed block to the chain head last fetched by the
+    /// indexing loop. The node is never requested.
+    pub fn health(&self) -> HealthReport {
+        self.progress.report(&self.config.health)
+    }
+
+    /// Returns a receiver of the events, also sent to the event handler.
+    /// Any number of receivers can be attached without slowing the
+    /// indexation: a receiver lagging behind by more than
+    /// `EVENTS_CAPACITY` events loses the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<PontosEvent> {
+        self.event_handler.subscribe()
+    }
+
+    /// Loads the most recently known contracts from the storage into
+    /// the contract cache. Should be called once before indexing.
+    pub async fn warm_up_contract_cache(&self) -> IndexerResult<usize> {
+        Ok(self.contract_manager.warm_up_cache().await?)
+    }
+
+    /// Returns the hit/miss statistics of the contract cache.
+    pub fn contract_cache_stats(&self) -> ContractCacheStats {
+        self.contract_manager.cache_stats()
+    }
+
+    /// Returns the USD price of one token at the given block,
+    /// `None` if it can't be priced from the indexed pools or the oracle.
+    pub async fn price_usd(
+        &self,
+        token_address: &str,
+        block_number: u64,
+    ) -> IndexerResult<Option<f64>> {
+        Ok(self
+            .price_oracle
+            .price_usd(token_address, block_number)
+            .await?)
+    }
+
+    /// Returns the market metrics of the memecoin, as of the last block
+    /// they changed.
+    pub async fn market_metrics(&self, memecoin_address: &str) -> IndexerResult<MarketMetrics> {
+        Ok(self.market_analyzer.metrics(memecoin_address).await?)
+    }
+
+    /// Returns the market metrics of the memecoins, the highest market cap first.
+    pub async fn top_market_metrics(&self, limit: u64) -> IndexerResult<Vec<MarketMetrics>> {
+        Ok(self.market_analyzer.top(limit).await?)
+    }
+
+    /// Returns the trending scores of the memecoins for the window,
+    /// in seconds, the highest first.
+    pub async fn trending_scores(
+        &self,
+        window: u64,
+        limit: u64,
+    ) -> IndexerResult<Vec<TrendingScore>> {
+        Ok(self.trending_analyzer.top(window, limit).await?)
+    }
+
+    /// Returns the current positions of the wallet on the memecoins.
+    pub async fn wallet_positions(&self, wallet_address: &str) -> IndexerResult<Vec<Position>> {
+        Ok(self
+            .portfolio_tracker
+            .positions(wallet_address, u64::MAX)
+            .await?)
+    }
+
+    /// Returns the daily snapshots of the wallet portfolio, between
+    /// the two days (timestamps of the start of the day) included.
+    pub async fn wallet_history(
+        &self,
+        wallet_address: &str,
+        from_day: u64,
+        to_day: u64,
+    ) -> IndexerResult<Vec<PortfolioSnapshot>> {
+        Ok(self
+            .portfolio_tracker
+            .history(wallet_address, from_day, to_day)
+            .await?)
+    }
+
+    /// Returns the deployer and team allocation wallets of the memecoin.
+    pub async fn developer_wallets(
+        &self,
+        memecoin_address: &str,
+    ) -> IndexerResult<Vec<DeveloperWallet>> {
+        Ok(self.developer_tracker.wallets(memecoin_address).await?)
+    }
+
+    /// Returns the transfers and sells of the developer wallets of the memecoin.
+    pub async fn developer_history(
+        &self,
+        memecoin_address: &str,
+    ) -> IndexerResult<Vec<DeveloperActivity>> {
+        Ok(self.developer_tracker.history(memecoin_address).await?)
+    }
+
+    /// Returns the holders of the memecoin at the given block, computed
+    /// from the indexed transfers. Only the transfers of the memecoins
+    /// are indexed: other tokens, as blocks not indexed yet, are not found.
+    pub async fn holder_snapshot(
+        &self,
+        token_address: &str,
+        block_number: u64,
+    ) -> IndexerResult<HolderSnapshot> {
+        Ok(self
+            .holder_snapshotter
+            .snapshot(token_address, block_number)
+            .await?)
+    }
+
+    /// Verifies a sample of the snapshot balances against the
+    /// `balance_of` of the token at the snapshot block.
+    pub async fn verify_holder_snapshot(
+        &self,
+        snapshot: &HolderSnapshot,
+        sample_size: usize,
+    ) -> IndexerResult<HolderVerification> {
+        Ok(self
+            .holder_snapshotter
+            .verify(snapshot, sample_size)
+            .await?)
+    }
+
+    /// Returns the events which couldn't be indexed with the status,
+    /// or all of them if `None`.
+    pub async fn failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> IndexerResult<Vec<FailedEvent>> {
+        Ok(self.failure_manager.failed_events(status).await?)
+    }
+
+    /// Processes again the pending failed events, fetched again from
+    /// their block. An event failing again is permanently failed once
+    /// it reached `max_replay_attempts` attempts.
+    pub async fn replay_failed(&self) -> IndexerResult<ReplayReport> {
+        let mut failures_by_block: BTreeMap<u64, Vec<FailedEvent>> = BTreeMap::new();
+        for failed in self
+            .failure_manager
+            .failed_events(Some(FailedEventStatus::Pending))
+            .await?
+        {
+            failures_by_block
+                .entry(failed.block_number)
+                .or_default()
+                .push(failed);
+        }
+
+        let mut report = ReplayReport::default();
+
+        for (block_number, failures) in failures_by_block {
+            let events: Vec<EmittedEvent> = match self
+                .client
+                .fetch_all_block_events(
+                    BlockId::Number(block_number),
+                    self.event_manager.keys_selector(),
+                )
+                .await
+            {
+                Ok(events) => events.into_values().flatten().collect(),
+                Err(e) => {
+                    error!(
+                        "Can't fetch events of block {} to replay: {:?}",
+                        block_number, e
+                    );
+                    self.rpc_error("fetch_all_block_events", Some(block_number), &e)
+                        .await;
+                    report.pending += failures.len();
+                    continue;
+                }
+            };
+
+            for failed in failures {
+                let event = match events
+                    .iter()
+                    .find(|e| FailureManager::<S>::failure_id(e) == failed.failure_id)
+                {
+                    Some(event) => event.clone(),
+                    None => {
+                        self.failure_manager
+                            .give_up(
+                                &failed,
+                                IndexingError::Other("Event not found in its block".to_string()),
+                            )
+                            .await?;
+                        report.failed += 1;
+                        continue;
+                    }
+                };
+
+                self.replay_event(event, &events, &failed).await?;
+
+                match self.failure_manager.replayed(&failed).await? {
+                    FailedEventStatus::Resolved => report.resolved += 1,
+                    FailedEventStatus::Failed => report.failed += 1,
+                    FailedEventStatus::Pending => report.pending += 1,
+                }
+            }
+        }
+
+        info!("Failed events replayed: {:?}", report);
+        Ok(report)
+    }
+
+    /// Processes again the event of the failure, among the events of its
+    /// block. A failed token registration resumes after its event, already
+    /// registered.
+    async fn replay_event(
+        &self,
+        event: EmittedEvent,
+        block_events: &[EmittedEvent],
+        failed: &FailedEvent,
+    ) -> IndexerResult<()> {
+        let block_timestamp = failed.block_timestamp;
+        if failed.kind != EventFailureKind::TokenRegistration {
+            let recipients = self.swap_manager.ekubo_recipients(block_events);
+            return self
+                .process_block_events(vec![event], &recipients, block_timestamp)
+                .await;
+        }
+
+        let formatted = match self
+            .contract_manager
+            .identify_contract(event.from_address, event.block_number, block_timestamp)
+            .await
+        {
+            Ok(contract_type) => {
+                self.event_manager
+                    .format_event(&event, contract_type, block_timestamp)
+            }
+            Err(err) => Err(err),
+        };
+
+        match formatted {
+            Ok((token_id, token_event)) => {
+                self.register_token(&event, &token_id, &token_event, block_timestamp)
+                    .await
+            }
+            Err(err) => {
+                self.fail_event(
+                    &event,
+                    block_timestamp,
+                    EventFailureKind::TokenRegistration,
+                    IndexingError::from(&err),
+                )
+                .await
+            }
+        }
+
+        Ok(())
+    }
+
     /// Starts a loop to only index the pending block.
     pub async fn index_pending(&self) -> IndexerResult<()> {
         loop {
+            self.progress.iteration();
             let mut cache = self.pending_cache.write().await;
 
             let (pending_ts, txs) = match self
@@ -111,6 +516,7 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 Ok((ts, txs)) => (ts, txs),
                 Err(e) => {
                     error!("Error while fetching pending block txs: {:?}", e);
+                    self.rpc_error("block_txs_hashes", None, &e).await;
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
@@ -135,6 +541,7 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                     Ok(n) => n,
                     Err(e) => {
                         error!("Error while fetching latest block number: {:?}", e);
+                        self.rpc_error("block_number", None, &e).await;
                         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                         continue;
                     }
@@ -192,6 +599,8 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                         }
                         Err(e) => {
                             error!("Error while fetching block timestamp: {:?}", e);
+                            self.rpc_error("block_time", Some(block_number), &e).await;
+                            self.skip_block(block_number, (&e).into()).await;
                         }
                     };
                 } else {
@@ -235,9 +644,18 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
         // second attempt.
         let max_attempt = 5;
         let mut attempt = 0;
+        let mut portfolio_snapshot = None;
+        let mut chain_head_refreshed: Option<Instant> = None;
 
         loop {
             trace!("Indexing block range: {} {}", current_u64, to_u64);
+            self.progress.iteration();
+
+            // Keeps the lag up to date while indexing.
+            if chain_head_refreshed.map_or(true, |i| i.elapsed() >= CHAIN_HEAD_REFRESH) {
+                self.refresh_chain_head().await;
+                chain_head_refreshed = Some(Instant::now());
+            }
 
             if current_u64 > to_u64 {
                 info!("End of indexing block range");
@@ -253,6 +671,7 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                         current_u64,
                         e
                     );
+                    self.rpc_error("block_time", Some(current_u64), &e).await;
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     attempt += 1;
 
@@ -261,6 +680,7 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                             "Skipping block {} as timestamp is not available",
                             current_u64
                         );
+                        self.skip_block(current_u64, (&e).into()).await;
                         current_u64 += 1;
                     }
 
@@ -268,34 +688,68 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 }
             };
 
-            if self
+            let (skip, cleaned_swaps) = match self
                 .block_manager
-                .should_skip_indexing(
-                    current_u64,
-                    block_ts,
-                    &self.config.indexer_version,
-                    do_force,
-                )
+                .indexing(current_u64, &self.config.indexer_version, do_force)
                 .await?
             {
+                BlockIndexing::Index => (false, vec![]),
+                BlockIndexing::Skip => (true, vec![]),
+                BlockIndexing::Reindex { forced } => {
+                    // Swaps removed by the cleaning, whose candles must then be rebuilt.
+                    let swaps = match self.candle_aggregator.block_swaps(block_ts).await {
+                        Ok(swaps) => swaps,
+                        Err(e) => {
+                            error!(
+                                "Error while fetching swaps of block {}: {:?}",
+                                current_u64, e
+                            );
+                            vec![]
+                        }
+                    };
+
+                    let cleaned = self
+                        .block_manager
+                        .clean_for_reindex(current_u64, block_ts, forced)
+                        .await?;
+                    if cleaned {
+                        // The balances are loaded again without the removed transfers.
+                        self.market_analyzer.reset();
+                        self.safety_analyzer.reset();
+                        self.trending_analyzer.reset();
+                        self.price_oracle.invalidate(current_u64);
+                    }
+                    (!cleaned, swaps)
+                }
+            };
+
+            if skip {
                 info!("Skipping block {}", current_u64);
+                // Already indexed, as much a progress as a block indexed now.
+                self.progress.block_indexed(current_u64, block_ts);
                 current_u64 += 1;
                 continue;
             }
 
+            if !cleaned_swaps.is_empty() {
+                if let Err(e) = self
+                    .candle_aggregator
+                    .rebuild(&cleaned_swaps, block_ts)
+                    .await
+                {
+                    error!(
+                        "Error while rebuilding candles of block {}: {:?}",
+                        current_u64, e
+                    );
+                }
+            }
+
             self.event_handler
                 .on_block_processing(block_ts, Some(current_u64))
                 .await;
 
             // Set block as processing.
-            self.block_manager
-                .set_block_info(
-                    current_u64,
-                    block_ts,
-                    &self.config.indexer_version,
-                    &self.config.indexer_identifier,
-                    BlockIndexingStatus::Processing,
-                )
+            self.set_block_info(current_u64, block_ts, BlockIndexingStatus::Processing)
                 .await?;
 
             let blocks_events = match self
@@ -309,6 +763,8 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 Ok(events) => events,
                 Err(e) => {
                     error!("Error while fetching events: {:?}", e);
+                    self.rpc_error("fetch_all_block_events", Some(current_u64), &e)
+                        .await;
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
@@ -324,15 +780,12 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 self.process_events(events, block_ts).await?;
             }
 
-            self.block_manager
-                .set_block_info(
-                    current_u64,
-                    block_ts,
-                    &self.config.indexer_version,
-                    &self.config.indexer_identifier,
-                    BlockIndexingStatus::Terminated,
-                )
+            self.set_block_info(current_u64, block_ts, BlockIndexingStatus::Terminated)
                 .await?;
+            self.metrics.block_indexed(current_u64);
+            self.progress.block_indexed(current_u64, block_ts);
+            self.metrics
+                .contract_cache(&self.contract_manager.cache_stats());
 
             let progress = if to_u64 == from_u64 {
                 if current_u64 == to_u64 {
@@ -344,6 +797,18 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 ((current_u64 - from_u64) as f64 / (to_u64 - from_u64) as f64) * 100.0
             };
 
+            if let Err(e) = self.trending_analyzer.update(current_u64, block_ts).await {
+                error!("Error while updating trending scores: {:?}", e);
+            }
+
+            if let Some(day) = self.portfolio_tracker.on_block(current_u64, block_ts).await {
+                // Snapshotted in the background, one day at a time.
+                if let Some(previous) = portfolio_snapshot.take() {
+                    let _ = previous.await;
+                }
+                portfolio_snapshot = Some(self.snapshot_portfolios(day));
+            }
+
             self.event_handler
                 .on_block_processed(current_u64, progress)
                 .await;
@@ -351,6 +816,10 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
             current_u64 += 1;
         }
 
+        if let Some(snapshot) = portfolio_snapshot {
+            let _ = snapshot.await;
+        }
+
         self.event_handler.on_indexation_range_completed().await;
 
         Ok(())
@@ -362,6 +831,28 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
         events: Vec<EmittedEvent>,
         block_timestamp: u64,
     ) -> IndexerResult<()> {
+        let recipients = self.swap_manager.ekubo_recipients(&events);
+        self.process_block_events(events, &recipients, block_timestamp)
+            .await
+    }
+
+    /// Processes the events, the traders of the Ekubo swaps
+    /// being resolved from the recipients of their block.
+    async fn process_block_events(
+        &self,
+        events: Vec<EmittedEvent>,
+        ekubo_recipients: &EkuboRecipients,
+        block_timestamp: u64,
+    ) -> IndexerResult<()> {
+        // Memecoins to be evaluated again once the events are processed.
+        let mut memecoins: HashSet<String> = HashSet::new();
+        let mut locked_tokens: HashSet<String> = HashSet::new();
+        // Memecoins whose price may have changed, with the evaluated ones.
+        let mut traded: HashSet<String> = HashSet::new();
+        // Quote tokens whose price may have changed.
+        let mut quote_tokens: HashSet<String> = HashSet::new();
+        let mut last_block_number = None;
+
         for e in events {
             let contract_address = e.from_address;
             info!(
@@ -369,57 +860,1756 @@ impl<S: Storage, C: StarknetClient, E: EventHandler + Send + Sync> Pontos<S, C,
                 e.block_number, e.transaction_hash
             );
 
+            if self.memecoin_manager.is_memecoin_event(&e).await {
+                last_block_number = Some(e.block_number);
+
+                let registered = self
+                    .memecoin_manager
+                    .format_and_register_event(&e, block_timestamp)
+                    .await;
+                if let Ok(event) = &registered {
+                    self.metrics.event(event.name());
+                }
+
+                match registered {
+                    Ok(MemecoinEvent::Created(created)) => {
+                        memecoins.insert(created.memecoin_address.clone());
+
+                        if let Err(err) = self
+                            .developer_tracker
+                            .register_deployer(&created, e.block_number, block_timestamp)
+                            .await
+                        {
+                            error!("Error while registering deployer {:?}\n{:?}", err, created);
+                        }
+
+                        self.event_handler.on_memecoin_created(created).await
+                    }
+                    Ok(MemecoinEvent::Launched(launched)) => {
+                        memecoins.insert(launched.memecoin_address.clone());
+
+                        match self
+                            .swap_manager
+                            .register_pool_from_launch(&launched, block_timestamp)
+                            .await
+                        {
+                            Ok(Some(pool)) => {
+                                self.developer_tracker.add_pool(&pool);
+                                self.safety_analyzer.add_pool(&pool);
+                            }
+                            Ok(None) => (),
+                            Err(err) => {
+                                error!("Error while registering pool {:?}\n{:?}", err, launched)
+                            }
+                        }
+
+                        self.event_handler.on_memecoin_launched(launched).await
+                    }
+                    Ok(MemecoinEvent::Transfer(transfer)) => {
+                        self.market_analyzer.on_transfer(&transfer);
+                        self.safety_analyzer.on_transfer(&transfer);
+                        self.trending_analyzer.on_transfer(&transfer);
+
+                        match self.developer_tracker.on_transfer(&transfer).await {
+                            Ok(Some(activity)) => {
+                                self.event_handler.on_developer_activity(activity).await
+                            }
+                            Ok(None) => (),
+                            Err(err) => {
+                                error!("Error while tracking developers {:?}\n{:?}", err, transfer)
+                            }
+                        };
+
+                        memecoins.insert(transfer.token_address);
+                    }
+                    Ok(MemecoinEvent::Locked(lock)) => {
+                        locked_tokens.insert(lock.token_address);
+                    }
+                    Ok(MemecoinEvent::Funding(_)) => (),
+                    Err(err) => {
+                        error!("Error while registering memecoin event {:?}\n{:?}", err, e);
+                        self.fail_event(
+                            &e,
+                            block_timestamp,
+                            EventFailureKind::MemecoinEvent,
+                            IndexingError::from(&err),
+                        )
+                        .await;
+                    }
+                };
+                continue;
+            }
+
+            if self.swap_manager.is_swap_event(&e).await {
+                last_block_number = Some(e.block_number);
+
+                let start = Instant::now();
+                let registered = self
+                    .swap_manager
+                    .format_and_register_event(&e, ekubo_recipients, block_timestamp)
+                    .await;
+                self.metrics.storage_write("swap", start.elapsed());
+
+                match registered {
+                    Ok((swap, pool)) => {
+                        self.metrics.event("SWAP");
+                        self.price_oracle.add_pool(&pool);
+                        self.price_oracle.invalidate(swap.block_number);
+                        if self.swap_manager.is_quote_pool(&pool) {
+                            // Only used to price the quote token.
+                            quote_tokens.insert(pool.memecoin_address);
+                            continue;
+                        }
+                        traded.insert(pool.memecoin_address.clone());
+
+                        if let Err(err) = self.candle_aggregator.add_swap(&pool, &swap).await {
+                            error!("Error while updating candles {:?}\n{:?}", err, swap);
+                        }
+                        if let Err(err) = self.trending_analyzer.on_swap(&pool, &swap).await {
+                            error!(
+                                "Error while updating trending activity {:?}\n{:?}",
+                                err, swap
+                            );
+                        }
+
+                        self.event_handler.on_swap_registered(swap).await
+                    }
+                    Err(err) => {
+                        error!("Error while registering swap {:?}\n{:?}", err, e);
+                        self.fail_event(
+                            &e,
+                            block_timestamp,
+                            EventFailureKind::SwapEvent,
+                            IndexingError::from(&err),
+                        )
+                        .await;
+                    }
+                };
+                continue;
+            }
+
             let contract_type = match self
                 .contract_manager
-                .write()
-                .await
-                .identify_contract(contract_address, block_timestamp)
+                .identify_contract(contract_address, e.block_number, block_timestamp)
                 .await
             {
                 Ok(info) => info,
-                Err(e) => {
+                Err(err) => {
                     warn!(
                         "Error while identifying contract {}: {:?}",
                         to_hex_str(&contract_address),
-                        e
+                        err
                     );
+                    self.fail_event(
+                        &e,
+                        block_timestamp,
+                        EventFailureKind::ContractIdentification,
+                        IndexingError::from(&err),
+                    )
+                    .await;
                     continue;
                 }
             };
 
-            if contract_type == ContractType::Other {
+            // ERC20 transfers are not tokens, and are not indexed as such.
+            if contract_type == ContractType::Other || contract_type == ContractType::ERC20 {
                 debug!(
-                    "Contract identified as OTHER: {}",
+                    "Contract identified as {}: {}",
+                    contract_type.to_string(),
                     to_hex_str(&contract_address),
                 );
                 continue;
             }
 
-            let (token_id, token_event) = match self
+            let start = Instant::now();
+            let registered = self
                 .event_manager
                 .format_and_register_event(&e, contract_type, block_timestamp)
-                .await
-            {
+                .await;
+            self.metrics.storage_write("event", start.elapsed());
+
+            let (token_id, token_event) = match registered {
                 Ok(te) => te,
                 Err(err) => {
                     error!("Error while registering event {:?}\n{:?}", err, e);
+                    self.fail_event(
+                        &e,
+                        block_timestamp,
+                        EventFailureKind::EventRegistration,
+                        IndexingError::from(&err),
+                    )
+                    .await;
                     continue;
                 }
             };
 
+            self.metrics.event(&token_event.event_type.to_string());
+            self.event_handler
+                .on_event_registered(token_event.clone())
+                .await;
+
+            self.register_token(&e, &token_id, &token_event, block_timestamp)
+                .await;
+        }
+
+        if let Some(block_number) = last_block_number {
+            self.update_sniper_reports(&memecoins, block_number, block_timestamp)
+                .await;
+
+            traded.extend(memecoins.iter().cloned());
+            traded.extend(locked_tokens.iter().cloned());
             match self
-                .token_manager
-                .format_and_register_token(&token_id, &token_event, block_timestamp, e.block_number)
+                .market_analyzer
+                .memecoins_quoted_in(&quote_tokens)
                 .await
             {
-                Ok(()) => (),
-                Err(err) => {
-                    error!("Can't format token {:?}\ntevent: {:?}", err, token_event);
-                    continue;
-                }
+                Ok(quoted) => traded.extend(quoted),
+                Err(e) => error!(
+                    "Can't get the memecoins quoted in {:?}: {:?}",
+                    quote_tokens, e
+                ),
             }
+            self.update_market_metrics(&traded, block_number, block_timestamp)
+                .await;
+
+            self.update_safety_reports(memecoins, locked_tokens, block_number, block_timestamp)
+                .await;
         }
 
         Ok(())
     }
+
+    async fn set_block_info(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+        status: BlockIndexingStatus,
+    ) -> IndexerResult<()> {
+        let start = Instant::now();
+        self.block_manager
+            .set_block_info(
+                block_number,
+                block_timestamp,
+                &self.config.indexer_version,
+                &self.config.indexer_identifier,
+                status,
+            )
+            .await?;
+        self.metrics.storage_write("block_info", start.elapsed());
+        Ok(())
+    }
+
+    /// Snapshots the portfolios of the day in a task, not to hold the indexing.
+    fn snapshot_portfolios(&self, day: u64) -> JoinHandle<()> {
+        let tracker = Arc::clone(&self.portfolio_tracker);
+
+        tokio::spawn(async move {
+            if let Err(e) = tracker.snapshot_day(day).await {
+                error!("Error while snapshotting portfolios of {}: {:?}", day, e);
+            }
+        })
+    }
+
+    /// Fetches the chain head and records it in the metrics and
+    /// the progress.
+    async fn refresh_chain_head(&self) {
+        match self.client.block_number().await {
+            Ok(block_number) => {
+                self.metrics.set_chain_head(block_number);
+                self.progress.set_chain_head(block_number);
+            }
+            Err(e) => {
+                error!("Error while fetching chain head: {:?}", e);
+                self.rpc_error("block_number", None, &e).await;
+            }
+        }
+    }
+
+    async fn skip_block(&self, block_number: u64, error: IndexingError) {
+        self.metrics.block_skipped();
+        self.event_handler
+            .on_block_skipped(block_number, error)
+            .await;
+    }
+
+    /// Registers the token of the registered event. A token already
+    /// registered by a previous event is not a failure.
+    async fn register_token(
+        &self,
+        event: &EmittedEvent,
+        token_id: &CairoU256,
+        token_event: &TokenEvent,
+        block_timestamp: u64,
+    ) {
+        match self
+            .token_manager
+            .format_and_register_token(token_id, token_event, block_timestamp, event.block_number)
+            .await
+        {
+            Ok(token) => self.event_handler.on_token_registered(token).await,
+            Err(err)
+                if matches!(
+                    err.downcast_ref::<StorageError>(),
+                    Some(StorageError::AlreadyExists(_))
+                ) =>
+            {
+                trace!("Token already registered: {:?}", token_event);
+            }
+            Err(err) => {
+                error!("Can't format token {:?}\ntevent: {:?}", err, token_event);
+                self.fail_event(
+                    event,
+                    block_timestamp,
+                    EventFailureKind::TokenRegistration,
+                    IndexingError::from(&err),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Records the event in the dead-letter store and notifies the failure.
+    async fn fail_event(
+        &self,
+        event: &EmittedEvent,
+        block_timestamp: u64,
+        kind: EventFailureKind,
+        error: IndexingError,
+    ) {
+        match self
+            .failure_manager
+            .record(event, block_timestamp, kind, error)
+            .await
+        {
+            Ok(failed) => self.event_handler.on_event_failed(failed).await,
+            Err(e) => error!(
+                "Can't record failed event of tx 0x{:064x}: {:?}",
+                event.transaction_hash, e
+            ),
+        }
+    }
+
+    async fn rpc_error(
+        &self,
+        method: &str,
+        block_number: Option<u64>,
+        error: &StarknetClientError,
+    ) {
+        self.event_handler
+            .on_rpc_error(RpcError {
+                method: method.to_string(),
+                block_number,
+                error: error.into(),
+            })
+            .await;
+    }
+
+    /// Detects again the snipers of the launched memecoins.
+    async fn update_sniper_reports(
+        &self,
+        memecoins: &HashSet<String>,
+        block_number: u64,
+        block_timestamp: u64,
+    ) {
+        for m in memecoins {
+            if let Err(e) = self
+                .sniper_detector
+                .analyze(m, block_number, block_timestamp)
+                .await
+            {
+                error!("Can't detect snipers of memecoin {}: {:?}", m, e);
+            }
+        }
+    }
+
+    /// Computes again the market metrics of the memecoins. Other tokens,
+    /// like liquidity tokens or quote tokens, are ignored.
+    async fn update_market_metrics(
+        &self,
+        memecoins: &HashSet<String>,
+        block_number: u64,
+        block_timestamp: u64,
+    ) {
+        for m in memecoins {
+            if let Err(e) = self
+                .market_analyzer
+                .update(m, block_number, block_timestamp)
+                .await
+            {
+                error!("Can't compute market metrics of memecoin {}: {:?}", m, e);
+            }
+        }
+    }
+
+    /// Evaluates again the safety of the memecoins, and of the memecoins
+    /// whose liquidity is one of the locked tokens.
+    async fn update_safety_reports(
+        &self,
+        mut memecoins: HashSet<String>,
+        locked_tokens: HashSet<String>,
+        block_number: u64,
+        block_timestamp: u64,
+    ) {
+        if !locked_tokens.is_empty() {
+            match self
+                .memecoin_manager
+                .get_memecoins_by_liquidity(&locked_tokens)
+                .await
+            {
+                Ok(locked) => memecoins.extend(locked),
+                Err(e) => error!("Can't get memecoins of locked tokens: {:?}", e),
+            }
+        }
+
+        for m in memecoins {
+            match self
+                .safety_analyzer
+                .evaluate(&m, block_number, block_timestamp)
+                .await
+            {
+                Ok(report) => self.event_handler.on_safety_report_updated(report).await,
+                Err(e) => error!("Can't evaluate safety of memecoin {}: {:?}", m, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::{
+        CandleInterval, DeveloperActivityKind, DeveloperRole, EventType, LiquidityType,
+        SafetyCriterionKind, SafetyStatus,
+    };
+    use crate::testing::*;
+
+    fn felt(v: u64) -> FieldElement {
+        FieldElement::from(v)
+    }
+
+    /// Block 1 mints token #1 of an ERC721, block 2 transfers it,
+    /// and block 3 only contains a transfer of a non-NFT contract.
+    fn sample_chain() -> SyntheticChain {
+        SyntheticChain::new()
+            .block(1, 1000)
+            .block(2, 1010)
+            .block(3, 1020)
+            .erc721(felt(0x100), felt(0xaaa))
+            .transfer(1, felt(0x100), FieldElement::ZERO, felt(0xaaa), 1)
+            .transfer(2, felt(0x100), felt(0xaaa), felt(0xbbb), 1)
+            .transfer(3, felt(0x200), felt(0xaaa), felt(0xbbb), 1000)
+    }
+
+    fn config() -> PontosConfig {
+        PontosConfig {
+            indexer_version: "0.0.1".to_string(),
+            indexer_identifier: "test".to_string(),
+            contract_cache: ContractCacheConfig::default(),
+            memecoin_factories: vec![],
+            lock_managers: vec![],
+            ekubo_core: felt(0xe4b0),
+            trending: TrendingConfig {
+                update_interval: 0,
+                ..Default::default()
+            },
+            sniper: SniperConfig::default(),
+            pricing: PricingConfig::default(),
+            max_replay_attempts: 3,
+            health: HealthConfig::default(),
+        }
+    }
+
+    fn pontos(
+        chain: SyntheticChain,
+    ) -> (
+        Pontos<MemoryStorage, SyntheticClient, RecordingEventHandler>,
+        Arc<SyntheticClient>,
+        Arc<MemoryStorage>,
+        Arc<RecordingEventHandler>,
+    ) {
+        pontos_with_config(chain, config())
+    }
+
+    fn pontos_with_config(
+        chain: SyntheticChain,
+        config: PontosConfig,
+    ) -> (
+        Pontos<MemoryStorage, SyntheticClient, RecordingEventHandler>,
+        Arc<SyntheticClient>,
+        Arc<MemoryStorage>,
+        Arc<RecordingEventHandler>,
+    ) {
+        let client = Arc::new(SyntheticClient::from_chain(chain));
+        let storage = Arc::new(MemoryStorage::new());
+        let handler = Arc::new(RecordingEventHandler::new());
+
+        let pontos = Pontos::new(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            Arc::clone(&handler),
+            config,
+        );
+
+        (pontos, client, storage, handler)
+    }
+
+    #[tokio::test]
+    async fn test_index_block_range() {
+        let (pontos, _, storage, handler) = pontos(sample_chain());
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+
+        assert_eq!(data.events.len(), 2);
+        assert_eq!(data.tokens.len(), 1);
+        assert_eq!(data.contracts.len(), 2);
+        assert_eq!(
+            data.contracts[&to_hex_str(&felt(0x100))].0.contract_type,
+            "ERC721"
+        );
+        assert_eq!(
+            data.contracts[&to_hex_str(&felt(0x200))].0.contract_type,
+            "OTHER"
+        );
+
+        let token = data.tokens.values().next().unwrap();
+        assert_eq!(token.info.owner, to_hex_str(&felt(0xaaa)));
+        assert_eq!(
+            token.mint.as_ref().unwrap().address,
+            to_hex_str(&felt(0xaaa))
+        );
+
+        assert_eq!(data.blocks.len(), 3);
+        assert!(data
+            .blocks
+            .values()
+            .all(|b| b.status == BlockIndexingStatus::Terminated));
+
+        let (registered, calls): (Vec<HandlerCall>, Vec<HandlerCall>) =
+            handler.calls().await.into_iter().partition(|c| {
+                matches!(
+                    c,
+                    HandlerCall::EventRegistered(_) | HandlerCall::TokenRegistered(_)
+                )
+            });
+        // The mint and the transfer of the token.
+        assert_eq!(registered.len(), 4);
+        assert!(matches!(
+            &registered[0],
+            HandlerCall::EventRegistered(e) if e.event_type == EventType::Mint
+        ));
+        assert!(matches!(
+            &registered[1],
+            HandlerCall::TokenRegistered(t) if t.owner == to_hex_str(&felt(0xaaa))
+        ));
+
+        assert_eq!(
+            calls,
+            vec![
+                HandlerCall::BlockProcessing(1000, Some(1)),
+                HandlerCall::BlockProcessed(1, 0.0),
+                HandlerCall::BlockProcessing(1010, Some(2)),
+                HandlerCall::BlockProcessed(2, 50.0),
+                HandlerCall::BlockProcessing(1020, Some(3)),
+                HandlerCall::BlockProcessed(3, 100.0),
+                HandlerCall::IndexationRangeCompleted,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let (pontos, _, _, handler) = pontos(sample_chain());
+        let mut first = pontos.subscribe();
+        let mut second = pontos.subscribe();
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let mut events = vec![];
+        while let Ok(event) = first.try_recv() {
+            events.push(event);
+        }
+
+        // Each handler call is also broadcast, in the same order.
+        assert_eq!(events.len(), handler.calls().await.len());
+        assert!(matches!(
+            events[0],
+            PontosEvent::BlockProcessing {
+                block_timestamp: 1000,
+                block_number: Some(1)
+            }
+        ));
+        assert!(matches!(
+            events.last(),
+            Some(PontosEvent::IndexationRangeCompleted)
+        ));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, PontosEvent::TokenRegistered(t) if t.contract_address == to_hex_str(&felt(0x100)))));
+
+        assert!(matches!(
+            second.try_recv(),
+            Ok(PontosEvent::BlockProcessing { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_index_block_range_skips_indexed_blocks() {
+        let (pontos, _, storage, handler) = pontos(sample_chain());
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let calls = handler.calls().await;
+        assert_eq!(calls.len(), 12);
+        assert_eq!(calls[11], HandlerCall::IndexationRangeCompleted);
+
+        // Forcing cleans the blocks and indexes them again.
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), true)
+            .await
+            .unwrap();
+
+        assert_eq!(handler.calls().await.len(), 23);
+        assert_eq!(storage.data().await.events.len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_index_block_range_recovers_from_rpc_failures() {
+        let (pontos, client, storage, _) = pontos(sample_chain());
+
+        client.inject_failure(FailurePoint::BlockTime(2), 2).await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        assert_eq!(data.events.len(), 2);
+        assert_eq!(data.blocks.len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_index_block_range_skips_block_without_timestamp() {
+        let (pontos, client, storage, handler) = pontos(sample_chain());
+
+        client.inject_failure(FailurePoint::BlockTime(2), 10).await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        assert_eq!(data.events.len(), 1);
+        assert_eq!(data.blocks.len(), 2);
+
+        let calls = handler.calls().await;
+        let rpc_errors = calls
+            .iter()
+            .filter(|c| {
+                matches!(
+                    c,
+                    HandlerCall::RpcError(e) if e.method == "block_time" && e.block_number == Some(2)
+                )
+            })
+            .count();
+        assert_eq!(rpc_errors, 6);
+        assert!(calls
+            .iter()
+            .any(|c| matches!(c, HandlerCall::BlockSkipped(2, IndexingError::Starknet(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_failed_events() {
+        let (pontos, client, _, handler) = pontos(sample_chain());
+
+        // The contract can't be identified for the mint only.
+        client
+            .inject_failure(FailurePoint::Call(felt(0x100)), 1)
+            .await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let failed = pontos.failed_events(None).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].block_number, 1);
+        assert_eq!(failed[0].block_timestamp, 1000);
+        assert_eq!(failed[0].contract_address, to_hex_str(&felt(0x100)));
+        assert_eq!(failed[0].kind, EventFailureKind::ContractIdentification);
+        assert_eq!(failed[0].status, FailedEventStatus::Pending);
+        assert_eq!(failed[0].attempts, 1);
+
+        assert!(handler
+            .calls()
+            .await
+            .contains(&HandlerCall::EventFailed(failed[0].clone())));
+
+        assert!(pontos
+            .failed_events(Some(FailedEventStatus::Resolved))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test(start_paused = true)]
+    async fn test_metrics() {
+        let metrics = Arc::new(Metrics::new());
+        let inner = Arc::new(SyntheticClient::from_chain(sample_chain()));
+        inner.inject_failure(FailurePoint::BlockTime(3), 10).await;
+        let client = Arc::new(client::MeteredClient::wrap(inner, Arc::clone(&metrics)));
+
+        let pontos = Pontos::new(
+            Arc::clone(&client),
+            Arc::new(MemoryStorage::new()),
+            Arc::new(RecordingEventHandler::new()),
+            config(),
+        )
+        .with_metrics(Arc::clone(&metrics));
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        // The chain head is fetched by the indexing loop.
+        assert_eq!(metrics.last_indexed_block(), Some(2));
+        assert_eq!(metrics.lag(), 1);
+
+        let text = pontos.metrics().render();
+        assert!(text.contains("pontos_blocks_indexed_total 2"));
+        assert!(text.contains("pontos_skipped_blocks_total 1"));
+        assert!(text.contains("pontos_events_total{type=\"MINT\"} 1"));
+        assert!(text.contains("pontos_events_total{type=\"TRANSFER\"} 1"));
+        assert!(text.contains("pontos_rpc_requests_total{method=\"block_time\",status=\"ok\"} 2"));
+        assert!(text.contains("pontos_chain_head 3"));
+        assert!(text
+            .contains("pontos_storage_write_duration_seconds_count{operation=\"block_info\"} 4"));
+        assert!(text.contains("pontos_contract_cache{stat=\"size\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let health = HealthConfig {
+            max_lag: 1,
+            ..Default::default()
+        };
+        let (pontos, client, storage, _) =
+            pontos_with_config(sample_chain(), PontosConfig { health, ..config() });
+
+        // The chain head is only fetched by the indexing loop.
+        let report = pontos.health();
+        assert!(!report.progressing);
+        assert_eq!(report.chain_head, None);
+        assert_eq!(report.last_indexed_block, None);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(1), false)
+            .await
+            .unwrap();
+
+        let report = pontos.health();
+        assert!(report.progressing);
+        assert!(!report.ready);
+        assert_eq!(report.chain_head, Some(3));
+        assert_eq!(report.lag, Some(2));
+        assert_eq!(report.last_indexed_block_timestamp, Some(1000));
+
+        pontos
+            .index_block_range(BlockId::Number(2), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let report = pontos.health();
+        assert!(report.ready);
+        assert_eq!(report.last_indexed_block, Some(3));
+        assert_eq!(report.lag, Some(0));
+        assert_eq!(pontos.metrics().lag(), 0);
+
+        // The blocks already indexed are progress of a restarted indexer.
+        let restarted = Pontos::new(
+            client,
+            storage,
+            Arc::new(RecordingEventHandler::new()),
+            config(),
+        );
+        restarted
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let report = restarted.health();
+        assert!(report.ready);
+        assert_eq!(report.last_indexed_block, Some(3));
+        assert_eq!(report.last_indexed_block_timestamp, Some(1020));
+    }
+
+    #[tokio::test]
+    async fn test_replay_failed() {
+        let (pontos, client, storage, _) = pontos(sample_chain());
+
+        client
+            .inject_failure(FailurePoint::Call(felt(0x100)), 1)
+            .await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+        assert_eq!(storage.data().await.events.len(), 1);
+
+        let report = pontos.replay_failed().await.unwrap();
+        assert_eq!(
+            report,
+            ReplayReport {
+                resolved: 1,
+                failed: 0,
+                pending: 0
+            }
+        );
+
+        // The mint is indexed once replayed.
+        let data = storage.data().await;
+        assert_eq!(data.events.len(), 2);
+        let failed = pontos.failed_events(None).await.unwrap();
+        assert_eq!(failed[0].status, FailedEventStatus::Resolved);
+
+        // Nothing is left to replay.
+        assert_eq!(
+            pontos.replay_failed().await.unwrap(),
+            ReplayReport::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_failed_token_registration() {
+        let (pontos, _, storage, handler) = pontos(sample_chain());
+
+        storage.fail_token_registrations(1).await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(1), false)
+            .await
+            .unwrap();
+
+        let failed = pontos.failed_events(None).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].kind, EventFailureKind::TokenRegistration);
+        assert!(storage.data().await.tokens.is_empty());
+
+        // The event is already registered, only the token is replayed.
+        let report = pontos.replay_failed().await.unwrap();
+        assert_eq!(
+            report,
+            ReplayReport {
+                resolved: 1,
+                failed: 0,
+                pending: 0
+            }
+        );
+
+        let data = storage.data().await;
+        assert_eq!(data.events.len(), 1);
+        assert_eq!(data.tokens.len(), 1);
+        assert!(data.tokens.values().all(|t| t.mint.is_some()));
+        assert!(handler
+            .calls()
+            .await
+            .iter()
+            .any(|c| matches!(c, HandlerCall::TokenRegistered(_))));
+    }
+
+    #[tokio::test]
+    async fn test_replay_failed_gives_up_after_max_attempts() {
+        let (pontos, client, _, _) = pontos(sample_chain());
+
+        // The contract of the mint can't be identified when it is first
+        // processed, nor when it is replayed twice.
+        client
+            .inject_failure(FailurePoint::Call(felt(0x100)), 1)
+            .await;
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(1), false)
+            .await
+            .unwrap();
+
+        client
+            .inject_failure(FailurePoint::Call(felt(0x100)), 1)
+            .await;
+        let report = pontos.replay_failed().await.unwrap();
+        assert_eq!(report.pending, 1);
+
+        client
+            .inject_failure(FailurePoint::Call(felt(0x100)), 1)
+            .await;
+        let report = pontos.replay_failed().await.unwrap();
+        assert_eq!(report.failed, 1);
+
+        let failed = pontos.failed_events(None).await.unwrap();
+        assert_eq!(failed[0].attempts, 3);
+        assert_eq!(failed[0].status, FailedEventStatus::Failed);
+        assert_eq!(
+            pontos.replay_failed().await.unwrap(),
+            ReplayReport::default()
+        );
+    }
+
+    /// Indexes the sample chain through a recording client, then indexes
+    /// it again offline from the saved fixture, expecting the same result.
+    #[tokio::test]
+    async fn test_index_block_range_from_recorded_fixture() {
+        async fn index<C: StarknetClient + Send + Sync>(client: Arc<C>) -> MemoryData {
+            let storage = Arc::new(MemoryStorage::new());
+            let pontos = Pontos::new(
+                client,
+                Arc::clone(&storage),
+                Arc::new(RecordingEventHandler::new()),
+                config(),
+            );
+            pontos
+                .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+                .await
+                .unwrap();
+            storage.data().await
+        }
+
+        let synthetic = Arc::new(SyntheticClient::from_chain(sample_chain()));
+        let recorder = Arc::new(client::RecordingClient::wrap(synthetic));
+        let recorded = index(Arc::clone(&recorder)).await;
+
+        let path = unique_temp_path("index_block_range.json");
+        recorder.save(&path).await.unwrap();
+        let replay = Arc::new(client::ReplayClient::from_fixture(
+            client::Fixture::load(&path).unwrap(),
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        let replayed = index(replay).await;
+
+        let event_ids = |data: &MemoryData| {
+            data.events
+                .values()
+                .map(|(e, _)| e.event_id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(event_ids(&replayed), event_ids(&recorded));
+        assert_eq!(event_ids(&replayed).len(), 2);
+        assert_eq!(replayed.contracts.len(), recorded.contracts.len());
+        assert_eq!(
+            replayed.tokens.values().next().unwrap().info.owner,
+            recorded.tokens.values().next().unwrap().info.owner
+        );
+    }
+
+    /// Indexes the mainnet blocks of `testing::recorded`, without network.
+    #[tokio::test]
+    #[ignore = "needs tests/fixtures/mainnet_memecoin.json, recorded by examples/record_fixture.rs"]
+    async fn test_index_recorded_mainnet_blocks() {
+        let fixture = client::Fixture::load(recorded::MAINNET_FIXTURE).unwrap();
+        let (from, to) = recorded::block_range(&fixture).unwrap();
+
+        let storage = Arc::new(MemoryStorage::new());
+        let pontos = Pontos::new(
+            Arc::new(client::ReplayClient::from_fixture(fixture)),
+            Arc::clone(&storage),
+            Arc::new(RecordingEventHandler::new()),
+            recorded::config(),
+        );
+        pontos
+            .index_block_range(BlockId::Number(from), BlockId::Number(to), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        assert_eq!(data.blocks.len() as u64, to - from + 1);
+        assert!(data.failed_events.is_empty());
+
+        let memecoins: HashSet<&String> = data
+            .memecoins
+            .iter()
+            .map(|(m, _)| &m.memecoin_address)
+            .collect();
+        assert!(!memecoins.is_empty());
+        assert!(data.launches.keys().all(|m| memecoins.contains(m)));
+        assert!(!data.launches.is_empty());
+
+        // The swaps of the launched memecoins are decoded and priced.
+        let memecoin_pools: HashSet<&String> = data
+            .pools
+            .values()
+            .filter(|p| memecoins.contains(&p.memecoin_address))
+            .map(|p| &p.pool_address)
+            .collect();
+        assert!(data
+            .swaps
+            .values()
+            .any(|s| memecoin_pools.contains(&s.pool_address)));
+        assert!(data.candles.keys().any(|(m, _, _)| memecoins.contains(m)));
+
+        assert!(data
+            .transfers
+            .values()
+            .any(|t| memecoins.contains(&t.token_address)));
+    }
+
+    #[tokio::test]
+    async fn test_index_contract_events() {
+        let (pontos, _, storage, handler) = pontos(sample_chain());
+
+        pontos
+            .index_contract_events(
+                Some(BlockId::Number(1)),
+                Some(BlockId::Number(3)),
+                felt(0x100),
+            )
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        assert_eq!(data.events.len(), 2);
+        assert_eq!(data.contracts.len(), 1);
+        assert!(data.blocks.is_empty());
+        // Only the registrations are notified, without block callbacks.
+        assert!(handler.calls().await.iter().all(|c| matches!(
+            c,
+            HandlerCall::EventRegistered(_) | HandlerCall::TokenRegistered(_)
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_index_block_range_identifies_by_class_hash() {
+        // Only the first contract answers the probes, the second one
+        // must be identified from the class they share.
+        let chain = sample_chain()
+            .class_hash(felt(0x100), felt(0xc1))
+            .class_hash(felt(0x101), felt(0xc1))
+            .transfer(3, felt(0x101), FieldElement::ZERO, felt(0xaaa), 7);
+
+        let client = Arc::new(SyntheticClient::from_chain(chain));
+        let storage = Arc::new(MemoryStorage::new());
+
+        let pontos = Pontos::new(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            Arc::new(RecordingEventHandler::new()),
+            config(),
+        )
+        .with_class_hash_provider(client);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        let contract = &data.contracts[&to_hex_str(&felt(0x101))].0;
+
+        assert_eq!(contract.contract_type, "ERC721");
+        assert_eq!(
+            contract.identification_method.as_deref(),
+            Some("CLASS_HASH")
+        );
+        assert_eq!(data.classes.len(), 1);
+        assert_eq!(data.events.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_index_block_range_skips_proxy_classes() {
+        // Contracts sharing a proxy class are each identified with calls.
+        let chain = sample_chain()
+            .class_hash(felt(0x100), felt(0xc1))
+            .class_hash(felt(0x101), felt(0xc1))
+            .call_response(felt(0x100), "get_implementation", Ok(vec![felt(0xc2)]))
+            .transfer(3, felt(0x101), FieldElement::ZERO, felt(0xaaa), 7);
+
+        let client = Arc::new(SyntheticClient::from_chain(chain));
+        let storage = Arc::new(MemoryStorage::new());
+
+        let pontos = Pontos::new(
+            Arc::clone(&client),
+            Arc::clone(&storage),
+            Arc::new(RecordingEventHandler::new()),
+            config(),
+        )
+        .with_class_hash_provider(client);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let data = storage.data().await;
+        assert!(data.classes.is_empty());
+        assert!(data
+            .contracts
+            .values()
+            .all(|(c, _)| c.identification_method.as_deref() != Some("CLASS_HASH")));
+    }
+
+    #[tokio::test]
+    async fn test_index_memecoin_launch() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(3, factory, memecoin, felt(0xe7), "Jediswap")
+            .call_response(
+                memecoin,
+                "get_team_allocation",
+                Ok(vec![felt(50_000), felt(0)]),
+            );
+
+        let (pontos, _, storage, handler) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let memecoins = storage.get_memecoins().await.unwrap();
+        assert_eq!(memecoins.len(), 1);
+        assert_eq!(memecoins[0].name, "Doge");
+        assert_eq!(memecoins[0].initial_supply, "1000000");
+
+        let launch = memecoins[0].launch.as_ref().unwrap();
+        assert_eq!(launch.quote_token, to_hex_str(&felt(0xe7)));
+        assert_eq!(launch.liquidity_type, LiquidityType::JediSwap);
+        assert_eq!(launch.team_allocation.as_deref(), Some("50000"));
+        assert_eq!(launch.block_number, 3);
+        assert_eq!(launch.block_timestamp, 1020);
+
+        let calls = handler.calls().await;
+        assert!(calls.contains(&HandlerCall::MemecoinCreated(to_hex_str(&memecoin))));
+        assert!(calls.contains(&HandlerCall::MemecoinLaunched(launch.clone())));
+
+        // The factory is never identified as a contract.
+        assert!(!storage
+            .data()
+            .await
+            .contracts
+            .contains_key(&to_hex_str(&factory)));
+    }
+
+    #[tokio::test]
+    async fn test_memecoin_safety_report() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .transfer(2, memecoin, FieldElement::ZERO, felt(0xaaa), 1_000_000)
+            .transfer(2, memecoin, felt(0xaaa), felt(0xbbb), 900_000)
+            .memecoin_launched(3, factory, memecoin, felt(0xe7), "Ekubo")
+            .call_response(
+                memecoin,
+                "liquidity_type",
+                Ok(vec![felt(0), felt(2), felt(42)]),
+            )
+            .call_response(
+                memecoin,
+                "get_team_allocation",
+                Ok(vec![felt(20_000), felt(0)]),
+            )
+            .call_response(memecoin, "owner", Ok(vec![FieldElement::ZERO]));
+
+        let (pontos, _, storage, handler) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage
+                .get_token_transfers(&to_hex_str(&memecoin), None)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let report = storage
+            .get_safety_report(&to_hex_str(&memecoin))
+            .await
+            .unwrap();
+
+        let statuses: Vec<SafetyStatus> = report.criteria.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                SafetyStatus::Pass,
+                SafetyStatus::Pass,
+                SafetyStatus::Fail,
+                SafetyStatus::Pass
+            ]
+        );
+        assert_eq!(report.score, 75);
+        assert_eq!(report.block_number, 3);
+
+        let calls = handler.calls().await;
+        assert!(calls.contains(&HandlerCall::SafetyReportUpdated(report)));
+        // Memecoin transfers are not NFT events.
+        assert_eq!(storage.data().await.events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_holder_concentration_excludes_ekubo_core() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+
+        // The liquidity is provided to Ekubo, the core holding 98% of the supply.
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .transfer(2, memecoin, FieldElement::ZERO, felt(0xaaa), 1_000_000)
+            .transfer(2, memecoin, felt(0xaaa), felt(0xe4b0), 980_000)
+            .memecoin_launched(3, factory, memecoin, felt(0xe7), "Ekubo")
+            .call_response(
+                memecoin,
+                "liquidity_type",
+                Ok(vec![felt(0), felt(2), felt(42)]),
+            )
+            .call_response(memecoin, "owner", Ok(vec![FieldElement::ZERO]));
+
+        let (pontos, _, storage, _) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let report = storage
+            .get_safety_report(&to_hex_str(&memecoin))
+            .await
+            .unwrap();
+        let concentration = report
+            .criteria
+            .iter()
+            .find(|c| c.kind == SafetyCriterionKind::HolderConcentration)
+            .unwrap();
+
+        assert_eq!(concentration.status, SafetyStatus::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_index_memecoin_swaps() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let quote = felt(0xe7);
+        let pair = felt(0x9a1);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(2, factory, memecoin, quote, "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            // The quote token being token0, buys 1000 DOGE for 5 ETH.
+            .jediswap_swap(3, pair, (5, 0), (0, 1000), felt(0xbbb))
+            // Unknown pair, ignored.
+            .jediswap_swap(3, felt(0x9a2), (5, 0), (0, 1000), felt(0xbbb));
+
+        let (pontos, _, storage, handler) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let pools = storage.get_pools().await.unwrap();
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].pool_address, to_hex_str(&pair));
+        assert_eq!(pools[0].token0, to_hex_str(&quote));
+
+        let swaps = storage
+            .get_swaps(&to_hex_str(&pair), 0, u64::MAX)
+            .await
+            .unwrap();
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].token_in, to_hex_str(&quote));
+        assert_eq!(swaps[0].token_out, to_hex_str(&memecoin));
+        assert_eq!(swaps[0].amount_in, "5");
+        assert_eq!(swaps[0].amount_out, "1000");
+        assert_eq!(swaps[0].block_timestamp, 1020);
+
+        let calls = handler.calls().await;
+        assert!(calls.contains(&HandlerCall::SwapRegistered(swaps[0].clone())));
+
+        let trending = pontos.trending_scores(3_600, 10).await.unwrap();
+        assert_eq!(trending.len(), 1);
+        assert_eq!(trending[0].memecoin_address, to_hex_str(&memecoin));
+        assert_eq!(trending[0].volume_quote, "5");
+        assert_eq!(trending[0].unique_buyers, 1);
+        assert!(trending[0].score > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_candles_are_rebuilt_on_forced_indexing() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(1, factory, memecoin, felt(0xe7), "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            .jediswap_swap(2, pair, (10, 0), (0, 1000), felt(0xbbb))
+            .jediswap_swap(3, pair, (0, 500), (20, 0), felt(0xbbb));
+
+        let (pontos, _, storage, _) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        // Blocks 2 and 3 (1010 and 1020) are in the same minute.
+        let memecoin = to_hex_str(&memecoin);
+        let candles = storage
+            .get_candles(&memecoin, CandleInterval::OneMinute, 0, u64::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].open_time, 960);
+        assert_eq!(candles[0].open, 0.01);
+        assert_eq!(candles[0].close, 0.04);
+        assert_eq!(candles[0].volume_token, "1500");
+        assert_eq!(candles[0].volume_quote, "30");
+        assert_eq!(candles[0].trades, 2);
+
+        pontos
+            .index_block_range(BlockId::Number(3), BlockId::Number(3), true)
+            .await
+            .unwrap();
+
+        let candle = storage
+            .get_candle(&memecoin, CandleInterval::OneDay, 0)
+            .await
+            .unwrap();
+
+        // The swap of block 3 is not counted twice.
+        assert_eq!(candle.trades, 2);
+        assert_eq!(candle.volume_token, "1500");
+    }
+
+    #[tokio::test]
+    async fn test_wallet_portfolio() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+        let wallet = felt(0xbbb);
+
+        let chain = sample_chain()
+            .block(4, 90_000)
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(1, factory, memecoin, felt(0xe7), "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            // Buys 1000 DOGE for 10, then sells 500 for 20.
+            .jediswap_swap(2, pair, (10, 0), (0, 1000), wallet)
+            .transfer(2, memecoin, pair, wallet, 1000)
+            .jediswap_swap(3, pair, (0, 500), (20, 0), wallet)
+            .transfer(3, memecoin, wallet, pair, 500);
+
+        let (pontos, _, storage, _) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let wallet = to_hex_str(&wallet);
+        let positions = pontos.wallet_positions(&wallet).await.unwrap();
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].memecoin_address, to_hex_str(&memecoin));
+        assert_eq!(positions[0].balance, "500");
+        assert_eq!(positions[0].average_cost, 0.01);
+        assert_eq!(positions[0].last_price, 0.04);
+        assert_eq!(positions[0].realized_pnl, 15.0);
+        assert!((positions[0].unrealized_pnl - 15.0).abs() < 1e-9);
+
+        // No snapshot until the day is over.
+        assert!(storage.data().await.portfolio_snapshots.is_empty());
+
+        pontos
+            .index_block_range(BlockId::Number(4), BlockId::Number(4), false)
+            .await
+            .unwrap();
+
+        let history = pontos.wallet_history(&wallet, 0, 86_400).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].day, 0);
+        assert_eq!(history[0].positions, positions);
+    }
+
+    /// Prices 0xe7 in USD from the swaps of its pair 0x9a2
+    /// against 0x5dc, a stablecoin with 6 decimals.
+    fn usd_config() -> PontosConfig {
+        let mut config = config();
+        config.pricing = PricingConfig {
+            stablecoins: vec![analysis::pricing::Stablecoin {
+                address: felt(0x5dc),
+                decimals: 6,
+            }],
+            quote_pools: vec![analysis::pricing::QuotePool {
+                exchange: LiquidityType::JediSwap,
+                token: felt(0xe7),
+                stablecoin: felt(0x5dc),
+                jediswap_pair: Some(felt(0x9a2)),
+                ekubo_pool: None,
+            }],
+            pragma: None,
+        };
+        config
+    }
+
+    #[tokio::test]
+    async fn test_usd_prices() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+        let eth = felt(0xe7);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(1, factory, memecoin, eth, "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            // Sells 1 ETH for 2000 USDC.
+            .jediswap_swap(
+                1,
+                felt(0x9a2),
+                (1_000_000_000_000_000_000, 0),
+                (0, 2_000_000_000),
+                felt(0xbbb),
+            )
+            // Buys 1000 DOGE for 0.001 ETH.
+            .jediswap_swap(
+                2,
+                pair,
+                (1_000_000_000_000_000, 0),
+                (0, 1_000_000_000_000_000_000_000),
+                felt(0xbbb),
+            );
+
+        let (pontos, _, storage, _) = pontos_with_config(chain, usd_config());
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let memecoin = to_hex_str(&memecoin);
+        let eth_price = pontos
+            .price_usd(&to_hex_str(&eth), 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((eth_price - 2000.0).abs() < 1e-9);
+
+        assert_eq!(pontos.price_usd(&memecoin, 1).await.unwrap(), None);
+        let price = pontos.price_usd(&memecoin, 3).await.unwrap().unwrap();
+        assert!((price - 0.002).abs() < 1e-12);
+
+        let data = storage.data().await;
+        let candle = data
+            .candles
+            .values()
+            .find(|c| c.memecoin_address == memecoin && c.interval == CandleInterval::OneDay)
+            .unwrap();
+        assert!((candle.close_usd - 0.002).abs() < 1e-12);
+        assert!((candle.volume_usd - 2.0).abs() < 1e-9);
+
+        // The quote pool is not analyzed as a memecoin pool.
+        assert_eq!(data.pools.len(), 1);
+        assert!(data
+            .candles
+            .values()
+            .all(|c| c.memecoin_address == memecoin));
+    }
+
+    #[tokio::test]
+    async fn test_market_metrics() {
+        const UNIT: u128 = 1_000_000_000_000_000_000;
+
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+        let eth = felt(0xe7);
+
+        let chain = sample_chain()
+            .memecoin_created(
+                1,
+                factory,
+                felt(0xaaa),
+                "Doge",
+                "DOGE",
+                1000 * UNIT,
+                memecoin,
+            )
+            .transfer(1, memecoin, FieldElement::ZERO, memecoin, 900 * UNIT)
+            .transfer(1, memecoin, FieldElement::ZERO, felt(0xaaa), 100 * UNIT)
+            .memecoin_launched(1, factory, memecoin, eth, "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            .transfer(1, memecoin, memecoin, pair, 800 * UNIT)
+            // 1 ETH = 2000 USDC.
+            .jediswap_swap(1, felt(0x9a2), (UNIT, 0), (0, 2_000_000_000), felt(0xbbb))
+            // Buys 100 DOGE for 0.1 ETH, 1 DOGE = 2 USD.
+            .jediswap_swap(2, pair, (UNIT / 10, 0), (0, 100 * UNIT), felt(0xbbb))
+            .transfer(2, memecoin, pair, felt(0xbbb), 100 * UNIT)
+            // 1 ETH = 3000 USDC.
+            .jediswap_swap(3, felt(0x9a2), (UNIT, 0), (0, 3_000_000_000), felt(0xbbb));
+
+        let (pontos, _, _, _) = pontos_with_config(chain, usd_config());
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(2), false)
+            .await
+            .unwrap();
+
+        let metrics = pontos.market_metrics(&to_hex_str(&memecoin)).await.unwrap();
+
+        assert_eq!(metrics.block_number, 2);
+        assert_eq!(metrics.total_supply, (1000 * UNIT).to_string());
+        // Neither the supply kept by the memecoin nor the team one.
+        assert_eq!(metrics.circulating_supply, (800 * UNIT).to_string());
+        assert!((metrics.price_usd - 2.0).abs() < 1e-9);
+        assert!((metrics.market_cap_usd - 1600.0).abs() < 1e-6);
+        assert!((metrics.fdv_usd - 2000.0).abs() < 1e-6);
+        assert!((metrics.liquidity_usd - 2800.0).abs() < 1e-6);
+
+        // The quote token is not a memecoin.
+        let top = pontos.top_market_metrics(10).await.unwrap();
+        assert_eq!(top, vec![metrics]);
+
+        // Updated with the price of the quote token.
+        pontos
+            .index_block_range(BlockId::Number(3), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let metrics = pontos.market_metrics(&to_hex_str(&memecoin)).await.unwrap();
+        assert_eq!(metrics.block_number, 3);
+        assert_eq!(metrics.circulating_supply, (800 * UNIT).to_string());
+        assert!((metrics.price_usd - 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_sniper_detection() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+        let eth = analysis::snipers::ETH_ADDRESS;
+
+        let chain = sample_chain()
+            .block(4, 1030)
+            .transfer(1, eth, felt(0xf00), felt(0xbbb), 1)
+            .transfer(1, eth, felt(0xf00), felt(0xccc), 1)
+            .transfer(1, eth, felt(0xf11), felt(0xddd), 1)
+            // Not the first funding of 0xbbb.
+            .transfer(2, eth, felt(0xf11), felt(0xbbb), 1)
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .memecoin_launched(1, factory, memecoin, felt(0xe7), "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            .transfer(1, memecoin, FieldElement::ZERO, pair, 950_000)
+            .transfer(1, memecoin, FieldElement::ZERO, felt(0xaaa), 50_000)
+            .transfer(2, memecoin, pair, felt(0xbbb), 100_000)
+            .transfer(2, memecoin, pair, felt(0xccc), 100_000)
+            .transfer(3, memecoin, pair, felt(0xddd), 50_000)
+            // Out of the launch window.
+            .transfer(4, memecoin, pair, felt(0xeee), 50_000);
+
+        let (pontos, _, storage, _) = pontos_with_config(
+            chain,
+            PontosConfig {
+                sniper: SniperConfig {
+                    funding_tokens: vec![eth],
+                    ..Default::default()
+                },
+                ..config()
+            },
+        );
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(4), false)
+            .await
+            .unwrap();
+
+        let report = storage
+            .get_sniper_report(&to_hex_str(&memecoin))
+            .await
+            .unwrap();
+
+        let snipers: Vec<&str> = report
+            .snipers
+            .iter()
+            .map(|s| s.wallet_address.as_str())
+            .collect();
+        assert_eq!(
+            snipers,
+            vec![
+                to_hex_str(&felt(0xbbb)),
+                to_hex_str(&felt(0xccc)),
+                to_hex_str(&felt(0xddd))
+            ]
+        );
+        assert_eq!(
+            report.snipers[0].funder_address,
+            Some(to_hex_str(&felt(0xf00)))
+        );
+        assert_eq!(
+            report.clusters,
+            vec![vec![to_hex_str(&felt(0xbbb)), to_hex_str(&felt(0xccc))]]
+        );
+        assert_eq!(report.launch_block_number, 1);
+        assert_eq!(report.snipers_supply_percent, 25.0);
+        assert_eq!(report.bundled_supply_percent, 20.0);
+        assert_eq!(report.block_number, 4);
+    }
+
+    #[tokio::test]
+    async fn test_developer_activity() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+        let pair = felt(0x9a1);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .transfer(1, memecoin, FieldElement::ZERO, felt(0xaaa), 50_000)
+            .transfer(1, memecoin, FieldElement::ZERO, felt(0xddd), 20_000)
+            .transfer(1, memecoin, FieldElement::ZERO, memecoin, 930_000)
+            .memecoin_launched(1, factory, memecoin, felt(0xe7), "Jediswap")
+            .call_response(memecoin, "liquidity_type", Ok(vec![felt(0), felt(0), pair]))
+            .transfer(2, memecoin, felt(0xaaa), felt(0xbbb), 1_000)
+            .transfer(3, memecoin, felt(0xddd), pair, 5_000)
+            // Not a developer wallet.
+            .transfer(3, memecoin, felt(0xbbb), felt(0xccc), 1_000);
+
+        let (pontos, _, _, handler) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let memecoin = to_hex_str(&memecoin);
+
+        let mut wallets: Vec<(String, DeveloperRole)> = pontos
+            .developer_wallets(&memecoin)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|w| (w.wallet_address, w.role))
+            .collect();
+        wallets.sort_by_key(|(w, r)| (w.clone(), r.to_string()));
+
+        assert_eq!(
+            wallets,
+            vec![
+                (to_hex_str(&felt(0xaaa)), DeveloperRole::Deployer),
+                (to_hex_str(&felt(0xaaa)), DeveloperRole::TeamAllocation),
+                (to_hex_str(&felt(0xddd)), DeveloperRole::TeamAllocation),
+            ]
+        );
+
+        let history = pontos.developer_history(&memecoin).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, DeveloperRole::Deployer);
+        assert_eq!(history[0].kind, DeveloperActivityKind::Transfer);
+        assert_eq!(history[0].to_address, to_hex_str(&felt(0xbbb)));
+        assert_eq!(history[1].role, DeveloperRole::TeamAllocation);
+        assert_eq!(history[1].kind, DeveloperActivityKind::Sell);
+        assert_eq!(history[1].amount, "5000");
+
+        let calls = handler.calls().await;
+        for activity in history {
+            assert!(calls.contains(&HandlerCall::DeveloperActivity(activity)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_holder_snapshot() {
+        let factory = felt(0xfac);
+        let memecoin = felt(0x3e3e);
+
+        let chain = sample_chain()
+            .memecoin_created(1, factory, felt(0xaaa), "Doge", "DOGE", 1_000_000, memecoin)
+            .transfer(1, memecoin, FieldElement::ZERO, felt(0xaaa), 1000)
+            .transfer(2, memecoin, felt(0xaaa), felt(0xbbb), 300)
+            .transfer(3, memecoin, felt(0xaaa), felt(0xccc), 700)
+            // Every holder is answered 300 on-chain.
+            .call_response(memecoin, "balance_of", Ok(vec![felt(300), felt(0)]));
+
+        let (pontos, _, _, _) = pontos(chain);
+
+        pontos
+            .index_block_range(BlockId::Number(1), BlockId::Number(3), false)
+            .await
+            .unwrap();
+
+        let memecoin = to_hex_str(&memecoin);
+        let snapshot = pontos.holder_snapshot(&memecoin, 2).await.unwrap();
+
+        // The transfer of block 3 is not included.
+        assert_eq!(snapshot.block_number, 2);
+        // Other tokens and blocks not indexed yet have no snapshot.
+        assert!(matches!(
+            pontos.holder_snapshot(&to_hex_str(&felt(0x200)), 2).await,
+            Err(IndexerError::StorageError(StorageError::NotFound(_)))
+        ));
+        assert!(matches!(
+            pontos.holder_snapshot(&memecoin, 4).await,
+            Err(IndexerError::StorageError(StorageError::NotFound(_)))
+        ));
+        assert_eq!(
+            snapshot.to_csv(),
+            format!(
+                "address,balance\n{},700\n{},300\n",
+                to_hex_str(&felt(0xaaa)),
+                to_hex_str(&felt(0xbbb))
+            )
+        );
+
+        let verification = pontos.verify_holder_snapshot(&snapshot, 10).await.unwrap();
+        assert_eq!(verification.checked, 2);
+        assert_eq!(verification.mismatches.len(), 1);
+        assert_eq!(verification.mismatches[0].indexed_balance, "700");
+        assert_eq!(
+            verification.mismatches[0].onchain_balance,
+            Some("300".to_string())
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_index_pending_detects_new_latest_block() {
+        let chain = sample_chain()
+            .pending(1030, vec![])
+            .pending(1030, vec![felt(1)])
+            .pending(1040, vec![]);
+
+        let (pontos, _, _, handler) = pontos(chain);
+
+        let r = tokio::time::timeout(tokio::time::Duration::from_secs(10), pontos.index_pending())
+            .await;
+
+        assert!(r.is_err());
+        assert_eq!(handler.calls().await, vec![HandlerCall::NewLatestBlock(3)]);
+    }
 }
@@ -5,6 +5,18 @@ use std::sync::Arc;
 use tracing::{debug, trace};
 use version_compare::{compare, Cmp};
 
+/// Indexing to be done for a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockIndexing {
+    /// The block is not indexed yet.
+    Index,
+    /// The block is already indexed by this indexer version.
+    Skip,
+    /// The block must be cleaned and indexed again, as forced
+    /// or indexed by a previous indexer version.
+    Reindex { forced: bool },
+}
+
 #[derive(Debug)]
 pub struct BlockManager<S: Storage> {
     storage: Arc<S>,
@@ -36,40 +48,67 @@ impl<S: Storage> BlockManager<S> {
         indexer_version: &str,
         do_force: bool,
     ) -> Result<bool, StorageError> {
+        match self
+            .indexing(block_number, indexer_version, do_force)
+            .await?
+        {
+            BlockIndexing::Index => Ok(false),
+            BlockIndexing::Skip => Ok(true),
+            BlockIndexing::Reindex { forced } => Ok(!self
+                .clean_for_reindex(block_number, block_timestamp, forced)
+                .await?),
+        }
+    }
+
+    /// Returns the indexing to be done for the block, without cleaning it.
+    pub async fn indexing(
+        &self,
+        block_number: u64,
+        indexer_version: &str,
+        do_force: bool,
+    ) -> Result<BlockIndexing, StorageError> {
         if do_force {
-            // Force indexing by cleaning the block, and return true.
-            match self
-                .storage
-                .clean_block(block_timestamp, Some(block_number))
-                .await
-            {
-                Ok(()) => Ok(false),
-                Err(_) => Ok(true),
-            }
-        } else {
-            match self.storage.get_block_info(block_number).await {
-                Ok(info) => {
-                    trace!("Block {} already indexed", block_number);
-                    debug!(
-                        "Checking indexation version: current={:?}, last={:?}",
-                        indexer_version, info.indexer_version
-                    );
-
-                    // Compare the indexer versions.
-                    match compare(indexer_version, info.indexer_version) {
-                        // if the current version is greater, clean the block & return false we index the block
-                        Ok(Cmp::Gt) => self
-                            .storage
-                            .clean_block(block_timestamp, Some(block_number))
-                            .await
-                            .map(|_| false),
-                        // if the current version is equal, return false we skip the block indexation
-                        _ => Ok(true),
-                    }
+            return Ok(BlockIndexing::Reindex { forced: true });
+        }
+
+        match self.storage.get_block_info(block_number).await {
+            Ok(info) => {
+                trace!("Block {} already indexed", block_number);
+                debug!(
+                    "Checking indexation version: current={:?}, last={:?}",
+                    indexer_version, info.indexer_version
+                );
+
+                // Compare the indexer versions.
+                match compare(indexer_version, info.indexer_version) {
+                    // if the current version is greater, the block is cleaned & indexed again
+                    Ok(Cmp::Gt) => Ok(BlockIndexing::Reindex { forced: false }),
+                    // if the current version is equal, we skip the block indexation
+                    _ => Ok(BlockIndexing::Skip),
                 }
-                Err(StorageError::NotFound(_s)) => Ok(false),
-                Err(e) => Err(e),
             }
+            Err(StorageError::NotFound(_s)) => Ok(BlockIndexing::Index),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cleans the block to index it again. Returns false if the block
+    /// must be skipped instead, a forced indexing being skipped if the
+    /// block can't be cleaned.
+    pub async fn clean_for_reindex(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+        forced: bool,
+    ) -> Result<bool, StorageError> {
+        match self
+            .storage
+            .clean_block(block_timestamp, Some(block_number))
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(_) if forced => Ok(false),
+            Err(e) => Err(e),
         }
     }
 
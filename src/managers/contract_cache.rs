@@ -0,0 +1,263 @@
+//! Bounded cache of contract types.
+//!
+//! Lookups only take a read lock, the recency being tracked with atomics,
+//! so concurrent event processing is not serialized by the cache.
+//! The write lock is only taken when a new contract is inserted.
+//!
+//! Entries are ordered by the tick at which they were queued. The oldest
+//! one is evicted, unless it was accessed since it was queued, in which
+//! case it is queued again with its last access tick. Each access causes
+//! at most one requeue, keeping the eviction in amortized `O(log n)`.
+use crate::storage::types::ContractType;
+use starknet::core::types::FieldElement;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+/// Argent and Braavos account proxies.
+pub const KNOWN_PROXY_CLASSES: [&str; 2] = [
+    "0x25ec026985a3bf9d0cc1fe17326b245dfdc3ff89b8fde106542a3ea56c5a918",
+    "0x3131fa018d520a037686ce3efddeab8f28895662f019ca3ca18a626650f7d1e",
+];
+
+#[derive(Debug, Clone)]
+pub struct ContractCacheConfig {
+    /// Maximum number of contracts kept in memory.
+    pub capacity: usize,
+    /// Duration after which an entry must be fetched again, if any.
+    pub ttl: Option<Duration>,
+    /// Number of contracts loaded from the storage during the warm-up.
+    pub warm_up_size: u64,
+    /// Classes shared by contracts of different types, like proxies,
+    /// which must not be used to identify their contracts. Proxies not
+    /// listed are detected by their `get_implementation` entrypoints.
+    pub proxy_classes: Vec<FieldElement>,
+}
+
+impl Default for ContractCacheConfig {
+    fn default() -> Self {
+        ContractCacheConfig {
+            capacity: 50_000,
+            ttl: None,
+            warm_up_size: 10_000,
+            proxy_classes: KNOWN_PROXY_CLASSES
+                .iter()
+                .map(|c| FieldElement::from_hex_be(c).expect("Valid class hash"))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub size: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    contract_type: ContractType,
+    inserted_at: Instant,
+    /// Key of the entry in the recency queue.
+    queued_at: u64,
+    last_access: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    by_address: HashMap<FieldElement, CacheEntry>,
+    /// Addresses by the tick at which they were queued, oldest first.
+    recency: BTreeMap<u64, FieldElement>,
+}
+
+impl Entries {
+    /// Removes the least recently used entry, returning its address.
+    fn evict_lru(&mut self) -> Option<FieldElement> {
+        loop {
+            let (tick, address) = self.recency.iter().next().map(|(t, a)| (*t, *a))?;
+            self.recency.remove(&tick);
+
+            let entry = match self.by_address.get_mut(&address) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let last_access = entry.last_access.load(Ordering::Relaxed);
+            if last_access > tick {
+                entry.queued_at = last_access;
+                self.recency.insert(last_access, address);
+                continue;
+            }
+
+            self.by_address.remove(&address);
+            return Some(address);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ContractCache {
+    config: ContractCacheConfig,
+    entries: RwLock<Entries>,
+    /// Logical clock used to track entries recency.
+    tick: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ContractCache {
+    pub fn new(config: ContractCacheConfig) -> Self {
+        ContractCache {
+            config,
+            entries: RwLock::new(Entries::default()),
+            tick: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &ContractCacheConfig {
+        &self.config
+    }
+
+    /// Returns the cached type of the contract, if present and not expired.
+    pub fn get(&self, address: &FieldElement) -> Option<ContractType> {
+        let entries = self.entries.read().unwrap();
+
+        match entries.by_address.get(address) {
+            Some(entry) if !self.is_expired(entry) => {
+                entry
+                    .last_access
+                    .store(self.tick.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.contract_type.clone())
+            }
+            _ => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Inserts the contract type, evicting the least recently used
+    /// entry if the capacity is reached.
+    pub fn insert(&self, address: FieldElement, contract_type: ContractType) {
+        if self.config.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.write().unwrap();
+
+        if let Some(previous) = entries.by_address.remove(&address) {
+            entries.recency.remove(&previous.queued_at);
+        } else if entries.by_address.len() >= self.config.capacity {
+            if let Some(a) = entries.evict_lru() {
+                trace!("Evicting contract {:#064x} from cache", a);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed);
+        entries.recency.insert(tick, address);
+        entries.by_address.insert(
+            address,
+            CacheEntry {
+                contract_type,
+                inserted_at: Instant::now(),
+                queued_at: tick,
+                last_access: AtomicU64::new(tick),
+            },
+        );
+    }
+
+    pub fn stats(&self) -> ContractCacheStats {
+        ContractCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            size: self.entries.read().unwrap().by_address.len(),
+        }
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        self.config
+            .ttl
+            .map_or(false, |ttl| entry.inserted_at.elapsed() > ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize, ttl: Option<Duration>) -> ContractCache {
+        ContractCache::new(ContractCacheConfig {
+            capacity,
+            ttl,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = cache(2, None);
+
+        cache.insert(FieldElement::ONE, ContractType::ERC721);
+        cache.insert(FieldElement::TWO, ContractType::ERC1155);
+
+        // Access the first one, so the second becomes the LRU.
+        assert_eq!(cache.get(&FieldElement::ONE), Some(ContractType::ERC721));
+
+        cache.insert(FieldElement::THREE, ContractType::Other);
+
+        assert_eq!(cache.get(&FieldElement::TWO), None);
+        assert_eq!(cache.get(&FieldElement::ONE), Some(ContractType::ERC721));
+        assert_eq!(cache.get(&FieldElement::THREE), Some(ContractType::Other));
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.size, 2);
+    }
+
+    #[test]
+    fn test_evicts_in_access_order() {
+        let cache = cache(3, None);
+        let address = |v: u64| FieldElement::from(v);
+
+        for v in 1..=3 {
+            cache.insert(address(v), ContractType::ERC721);
+        }
+        // Recency from the least recent: 2, 3, 1.
+        cache.get(&address(1));
+        // Replacing an entry makes it the most recent: 3, 1, 2.
+        cache.insert(address(2), ContractType::ERC1155);
+
+        cache.insert(address(4), ContractType::Other);
+        assert_eq!(cache.get(&address(3)), None);
+        cache.insert(address(5), ContractType::Other);
+        assert_eq!(cache.get(&address(1)), None);
+
+        assert_eq!(cache.get(&address(2)), Some(ContractType::ERC1155));
+        assert_eq!(cache.stats().size, 3);
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn test_expired_entries_are_missed() {
+        let cache = cache(10, Some(Duration::ZERO));
+
+        cache.insert(FieldElement::ONE, ContractType::ERC721);
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(cache.get(&FieldElement::ONE), None);
+        assert_eq!(cache.stats().misses, 1);
+    }
+}
@@ -1,40 +1,103 @@
+use crate::client::ClassHashProvider;
+use crate::managers::contract_cache::{ContractCache, ContractCacheConfig, ContractCacheStats};
 use crate::storage::{
-    types::{ContractInfo, ContractType, StorageError},
+    types::{
+        ClassInfo, ContractInfo, ContractType, IdentificationMethod, Pagination, StorageError,
+    },
     Storage,
 };
 use anyhow::Result;
 use ark_starknet::client::{StarknetClient, StarknetClientError};
 use ark_starknet::format::to_hex_str;
-use starknet::core::types::{BlockId, BlockTag, FieldElement};
+use starknet::core::types::{BlockId, FieldElement};
 use starknet::core::utils::{get_selector_from_name, parse_cairo_short_string};
-use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::trace;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, trace, warn};
+
+/// SRC5 interface ids, as defined by the SNIP-5 standard.
+pub const ISRC5_ID: &str = "0x3f918d17e5ee77373b56385708f855659a07f75997f365cf87748628532a055";
+pub const IERC721_ID: &str = "0x33eb2f84c309543403fd69f0d0f363781ef06ef6faeb0131ff16ea3175bd943";
+pub const IERC1155_ID: &str = "0x6114a8f75559e1b39fcba08ce02961a1aa082d9256a158dd3e64964e4b1b52";
+
+/// Legacy ERC165 interface ids, still registered by
+/// contracts written before SNIP-5 was finalized.
+pub const IERC721_LEGACY_ID: &str = "0x80ac58cd";
+pub const IERC1155_LEGACY_ID: &str = "0xd9b67a26";
+pub const IERC20_LEGACY_ID: &str = "0x36372b07";
 
 pub struct ContractManager<S: Storage, C: StarknetClient> {
     storage: Arc<S>,
     client: Arc<C>,
     /// A cache with contract address mapped to its type.
-    cache: HashMap<FieldElement, ContractType>,
+    cache: ContractCache,
+    /// Optional provider of the class hashes, to share the
+    /// identification of contracts deployed with the same class.
+    class_hash_provider: Option<Arc<dyn ClassHashProvider + Send + Sync>>,
+    /// A cache with class hash mapped to its type.
+    class_cache: ContractCache,
+    /// Proxy classes, configured or detected, never cached.
+    proxy_classes: RwLock<HashSet<FieldElement>>,
 }
 
 impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
     /// Initializes a new instance.
-    pub fn new(storage: Arc<S>, client: Arc<C>) -> Self {
+    pub fn new(storage: Arc<S>, client: Arc<C>, cache_config: ContractCacheConfig) -> Self {
         Self {
             storage,
             client,
-            cache: HashMap::new(),
+            cache: ContractCache::new(cache_config.clone()),
+            class_hash_provider: None,
+            proxy_classes: RwLock::new(cache_config.proxy_classes.iter().copied().collect()),
+            class_cache: ContractCache::new(cache_config),
         }
     }
 
+    /// Enables the identification of the contracts by their class hash.
+    pub fn set_class_hash_provider(&mut self, provider: Arc<dyn ClassHashProvider + Send + Sync>) {
+        self.class_hash_provider = Some(provider);
+    }
+
+    /// Returns the hit/miss statistics of the contract cache.
+    pub fn cache_stats(&self) -> ContractCacheStats {
+        self.cache.stats()
+    }
+
+    /// Loads the most recently registered contracts from the storage
+    /// into the cache. Returns the count of contracts loaded.
+    pub async fn warm_up_cache(&self) -> Result<usize, StorageError> {
+        let infos = self
+            .storage
+            .get_contract_infos(&Pagination {
+                offset: 0,
+                limit: self.cache.config().warm_up_size,
+                after: None,
+            })
+            .await?;
+
+        let mut count = 0;
+        for info in infos {
+            if let (Ok(address), Ok(contract_type)) = (
+                FieldElement::from_hex_be(&info.contract_address),
+                ContractType::from_str(&info.contract_type),
+            ) {
+                self.cache.insert(address, contract_type);
+                count += 1;
+            }
+        }
+
+        debug!("Contract cache warmed up with {} contracts", count);
+        Ok(count)
+    }
+
     /// Gets the contract info from local cache, or fetch is from the DB.
     async fn get_cached_or_fetch_info(
-        &mut self,
+        &self,
         address: FieldElement,
     ) -> Result<ContractType, StorageError> {
         if let Some(contract_type) = self.cache.get(&address) {
-            return Ok(contract_type.clone());
+            return Ok(contract_type);
         }
 
         trace!("Cache miss for contract {:#064x}", address);
@@ -51,20 +114,30 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
 
     /// Identifies a contract from its address only.
     pub async fn identify_contract(
-        &mut self,
+        &self,
         address: FieldElement,
+        block_number: u64,
         block_timestamp: u64,
     ) -> Result<ContractType> {
+        let block = BlockId::Number(block_number);
+
         match self.get_cached_or_fetch_info(address).await {
             Ok(contract_type) => Ok(contract_type),
             Err(_) => {
-                // Can't find info, try to identify with calls.
-                let contract_type = self.get_contract_type(address).await?;
+                // Can't find info, try to identify from the class, or with calls.
+                let (contract_type, method) = match self.get_class_hash(address, block).await {
+                    Some(class_hash) => {
+                        self.identify_from_class(address, class_hash, block, block_timestamp)
+                            .await?
+                    }
+                    None => self.identify_contract_type(address, block).await?,
+                };
 
                 trace!(
-                    "New contract identified [0x{:064x}] : {}",
+                    "New contract identified [0x{:064x}] : {} ({})",
                     address,
-                    contract_type.to_string()
+                    contract_type.to_string(),
+                    method.to_string()
                 );
 
                 self.cache.insert(address, contract_type.clone());
@@ -75,26 +148,219 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
                     name: None,
                     symbol: None,
                     image: None,
+                    identification_method: Some(method.to_string()),
+                    block_timestamp,
                 };
 
-                self.storage
+                // An other task may have identified the same contract concurrently.
+                match self
+                    .storage
                     .register_contract_info(&info, block_timestamp)
-                    .await?;
+                    .await
+                {
+                    Ok(()) | Err(StorageError::AlreadyExists(_)) => (),
+                    Err(e) => return Err(e.into()),
+                }
 
                 Ok(contract_type)
             }
         }
     }
 
+    /// Returns the class hash of the contract, if a provider is set and succeeds.
+    async fn get_class_hash(&self, address: FieldElement, block: BlockId) -> Option<FieldElement> {
+        let provider = self.class_hash_provider.as_ref()?;
+
+        match provider.class_hash_at(address, block).await {
+            Ok(class_hash) => Some(class_hash),
+            Err(e) => {
+                warn!(
+                    "Can't get class hash of contract {}: {:?}",
+                    to_hex_str(&address),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Identifies the contract from the type of its class. If the class is
+    /// unknown, the contract is identified with calls and the class registered.
+    /// Contracts of proxy classes are always identified with calls.
+    async fn identify_from_class(
+        &self,
+        address: FieldElement,
+        class_hash: FieldElement,
+        block: BlockId,
+        block_timestamp: u64,
+    ) -> Result<(ContractType, IdentificationMethod)> {
+        if self.proxy_classes.read().unwrap().contains(&class_hash) {
+            return self.identify_contract_type(address, block).await;
+        }
+
+        if let Some(contract_type) = self.class_cache.get(&class_hash) {
+            return Ok((contract_type, IdentificationMethod::ClassHash));
+        }
+
+        match self.storage.get_class_info(&to_hex_str(&class_hash)).await {
+            Ok(info) => {
+                if let Ok(contract_type) = ContractType::from_str(&info.contract_type) {
+                    self.class_cache.insert(class_hash, contract_type.clone());
+                    return Ok((contract_type, IdentificationMethod::ClassHash));
+                }
+            }
+            Err(StorageError::NotFound(_)) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        if self.is_proxy(address, block).await? {
+            debug!("Proxy class {:#064x}, not cached", class_hash);
+            self.proxy_classes.write().unwrap().insert(class_hash);
+            return self.identify_contract_type(address, block).await;
+        }
+
+        trace!("Unknown class {:#064x}, identifying with calls", class_hash);
+
+        let (contract_type, method) = self.identify_contract_type(address, block).await?;
+        self.class_cache.insert(class_hash, contract_type.clone());
+
+        let info = ClassInfo {
+            class_hash: to_hex_str(&class_hash),
+            contract_type: contract_type.to_string(),
+            identification_method: method.to_string(),
+        };
+
+        match self
+            .storage
+            .register_class_info(&info, block_timestamp)
+            .await
+        {
+            Ok(()) | Err(StorageError::AlreadyExists(_)) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok((contract_type, method))
+    }
+
+    /// Identifies the contract type using SRC5 `supports_interface` if the
+    /// contract implements it, or by probing the entrypoints otherwise.
+    pub async fn identify_contract_type(
+        &self,
+        contract_address: FieldElement,
+        block: BlockId,
+    ) -> Result<(ContractType, IdentificationMethod)> {
+        match self
+            .get_contract_type_from_src5(contract_address, block)
+            .await?
+        {
+            Some(contract_type) => Ok((contract_type, IdentificationMethod::Src5)),
+            None => Ok((
+                self.get_contract_type(contract_address, block).await?,
+                IdentificationMethod::Probing,
+            )),
+        }
+    }
+
+    /// Returns the contract type declared through SRC5, or `None`
+    /// if the contract doesn't expose `supports_interface` or reverts.
+    pub async fn get_contract_type_from_src5(
+        &self,
+        contract_address: FieldElement,
+        block: BlockId,
+    ) -> Result<Option<ContractType>> {
+        let candidates = [
+            (ContractType::ERC721, [IERC721_ID, IERC721_LEGACY_ID]),
+            (ContractType::ERC1155, [IERC1155_ID, IERC1155_LEGACY_ID]),
+        ];
+
+        // Some contracts only register the legacy ids, the SRC5 id itself
+        // is then only used to detect the presence of the entrypoint.
+        if self
+            .supports_interface(contract_address, ISRC5_ID, block)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        for (contract_type, ids) in candidates {
+            for id in ids {
+                if self.supports_interface(contract_address, id, block).await? == Some(true) {
+                    return Ok(Some(contract_type));
+                }
+            }
+        }
+
+        if self
+            .supports_interface(contract_address, IERC20_LEGACY_ID, block)
+            .await?
+            == Some(true)
+        {
+            return Ok(Some(ContractType::ERC20));
+        }
+
+        Ok(Some(ContractType::Other))
+    }
+
+    /// Calls `supports_interface` (or its camel case version) on the contract.
+    /// Returns `None` if none of the entrypoints exists or answers, a revert
+    /// meaning that SRC5 isn't supported. Only the transport errors fail.
+    pub async fn supports_interface(
+        &self,
+        contract_address: FieldElement,
+        interface_id: &str,
+        block: BlockId,
+    ) -> Result<Option<bool>> {
+        let calldata = vec![FieldElement::from_hex_be(interface_id)?];
+
+        for selector_name in ["supports_interface", "supportsInterface"] {
+            match self
+                .get_contract_response(contract_address, selector_name, calldata.clone(), block)
+                .await
+            {
+                Ok(r) => return Ok(Some(r.first() == Some(&FieldElement::ONE))),
+                Err(StarknetClientError::EntrypointNotFound(_))
+                | Err(StarknetClientError::Contract(_))
+                | Err(StarknetClientError::InputTooLong) => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns true if the contract exposes one of the usual entrypoints
+    /// of the proxies to get their implementation.
+    pub async fn is_proxy(&self, contract_address: FieldElement, block: BlockId) -> Result<bool> {
+        for selector_name in [
+            "get_implementation_hash",
+            "get_implementation",
+            "implementation",
+        ] {
+            match self
+                .get_contract_response(contract_address, selector_name, vec![], block)
+                .await
+            {
+                Ok(_) | Err(StarknetClientError::Contract(_)) => return Ok(true),
+                Err(StarknetClientError::EntrypointNotFound(_)) => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(false)
+    }
+
     /// Verifies if the contract is an ERC721, ERC1155 or an other type.
     /// `owner_of` is specific to ERC721.
     /// `balance_of` is specific to ERC1155 and different from ERC20 as 2 arguments are expected.
-    pub async fn get_contract_type(&self, contract_address: FieldElement) -> Result<ContractType> {
-        let _block = BlockId::Tag(BlockTag::Pending);
-
-        if self.is_erc721(contract_address).await? {
+    pub async fn get_contract_type(
+        &self,
+        contract_address: FieldElement,
+        block: BlockId,
+    ) -> Result<ContractType> {
+        if self.is_erc721(contract_address, block).await? {
             Ok(ContractType::ERC721)
-        } else if self.is_erc1155(contract_address).await? {
+        } else if self.is_erc1155(contract_address, block).await? {
             Ok(ContractType::ERC1155)
         } else {
             Ok(ContractType::Other)
@@ -102,8 +368,7 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
     }
 
     /// Returns true if the contract is ERC721, false otherwise.
-    pub async fn is_erc721(&self, contract_address: FieldElement) -> Result<bool> {
-        let block = BlockId::Tag(BlockTag::Pending);
+    pub async fn is_erc721(&self, contract_address: FieldElement, block: BlockId) -> Result<bool> {
         let token_id = vec![FieldElement::ONE, FieldElement::ZERO]; // u256.
 
         match self
@@ -111,18 +376,10 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
             .await
         {
             Ok(_) => return Ok(true),
-            Err(e) => match e {
-                StarknetClientError::Contract(s) => {
-                    // Token ID may not exist, but the entrypoint was hit.
-                    if s.contains("not found in contract") {
-                        // do nothing and go to the next selector.
-                    } else {
-                        return Ok(true);
-                    }
-                }
-                StarknetClientError::EntrypointNotFound(_) => (),
-                _ => return Ok(false),
-            },
+            // Token ID may not exist, but the entrypoint was hit.
+            Err(StarknetClientError::Contract(_)) => return Ok(true),
+            Err(StarknetClientError::EntrypointNotFound(_)) => (),
+            Err(_) => return Ok(false),
         };
 
         match self
@@ -130,24 +387,14 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
             .await
         {
             Ok(_) => Ok(true),
-            Err(e) => match e {
-                StarknetClientError::Contract(s) => {
-                    // Token ID may not exist, but the entrypoint was hit.
-                    if s.contains("not found in contract") {
-                        Ok(false)
-                    } else {
-                        Ok(true)
-                    }
-                }
-                StarknetClientError::EntrypointNotFound(_) => Ok(false),
-                _ => Ok(false),
-            },
+            // Token ID may not exist, but the entrypoint was hit.
+            Err(StarknetClientError::Contract(_)) => Ok(true),
+            Err(_) => Ok(false),
         }
     }
 
     /// Returns true if the contract is ERC1155, false otherwise.
-    pub async fn is_erc1155(&self, contract_address: FieldElement) -> Result<bool> {
-        let block = BlockId::Tag(BlockTag::Pending);
+    pub async fn is_erc1155(&self, contract_address: FieldElement, block: BlockId) -> Result<bool> {
         // felt and u256 expected.
         let address_and_token_id = vec![FieldElement::ZERO, FieldElement::ONE, FieldElement::ZERO];
 
@@ -198,6 +445,7 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
                 block,
             )
             .await
+            .map_err(entrypoint_error)
     }
 
     pub async fn get_contract_property_string(
@@ -225,6 +473,17 @@ impl<S: Storage, C: StarknetClient> ContractManager<S, C> {
     }
 }
 
+/// Nodes report a missing entrypoint as a contract execution error.
+/// Converts it to `EntrypointNotFound`, so callers only match on variants.
+fn entrypoint_error(e: StarknetClientError) -> StarknetClientError {
+    match e {
+        StarknetClientError::Contract(s) if s.contains("not found in contract") => {
+            StarknetClientError::EntrypointNotFound(s)
+        }
+        e => e,
+    }
+}
+
 pub fn decode_string_array(string_array: &[FieldElement]) -> Result<String> {
     match string_array.len() {
         0 => Ok("".to_string()),
@@ -247,3 +506,146 @@ pub fn decode_string_array(string_array: &[FieldElement]) -> Result<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockStorage;
+    use ark_starknet::client::MockStarknetClient;
+    use starknet::macros::selector;
+
+    fn manager(client: MockStarknetClient) -> ContractManager<MockStorage, MockStarknetClient> {
+        ContractManager::new(
+            Arc::new(MockStorage::default()),
+            Arc::new(client),
+            ContractCacheConfig::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identify_with_src5() {
+        let mut client = MockStarknetClient::default();
+
+        client
+            .expect_call_contract()
+            .returning(|_, selector, calldata, _| {
+                if selector != selector!("supports_interface") {
+                    return Err(StarknetClientError::EntrypointNotFound("".to_string()));
+                }
+
+                let supported = calldata[0] == FieldElement::from_hex_be(ISRC5_ID).unwrap()
+                    || calldata[0] == FieldElement::from_hex_be(IERC1155_ID).unwrap();
+
+                Ok(vec![if supported {
+                    FieldElement::ONE
+                } else {
+                    FieldElement::ZERO
+                }])
+            });
+
+        let result = manager(client)
+            .identify_contract_type(FieldElement::ONE, BlockId::Number(1))
+            .await
+            .unwrap();
+
+        assert_eq!(result, (ContractType::ERC1155, IdentificationMethod::Src5));
+    }
+
+    #[tokio::test]
+    async fn test_identify_fallbacks_to_probing() {
+        let mut client = MockStarknetClient::default();
+
+        client
+            .expect_call_contract()
+            .returning(|_, selector, _, _| {
+                if selector == selector!("owner_of") {
+                    Ok(vec![FieldElement::TWO])
+                } else {
+                    Err(StarknetClientError::EntrypointNotFound("".to_string()))
+                }
+            });
+
+        let result = manager(client)
+            .identify_contract_type(FieldElement::ONE, BlockId::Number(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result,
+            (ContractType::ERC721, IdentificationMethod::Probing)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_src5_revert_fallbacks_to_probing() {
+        let mut client = MockStarknetClient::default();
+
+        client
+            .expect_call_contract()
+            .returning(|_, selector, _, _| {
+                if selector == selector!("supports_interface")
+                    || selector == selector!("supportsInterface")
+                {
+                    Err(StarknetClientError::Contract(
+                        "Execution reverted".to_string(),
+                    ))
+                } else if selector == selector!("owner_of") {
+                    Ok(vec![FieldElement::TWO])
+                } else {
+                    Err(StarknetClientError::EntrypointNotFound("".to_string()))
+                }
+            });
+
+        let result = manager(client)
+            .identify_contract_type(FieldElement::ONE, BlockId::Number(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result,
+            (ContractType::ERC721, IdentificationMethod::Probing)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_src5_transport_error() {
+        let mut client = MockStarknetClient::default();
+
+        client
+            .expect_call_contract()
+            .returning(|_, _, _, _| Err(StarknetClientError::Other("Timeout".to_string())));
+
+        assert!(manager(client)
+            .identify_contract_type(FieldElement::ONE, BlockId::Number(1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_missing_entrypoint_contract_error() {
+        let mut client = MockStarknetClient::default();
+
+        client
+            .expect_call_contract()
+            .withf(|_, _, _, block| *block == BlockId::Number(7))
+            .returning(|_, selector, _, _| {
+                if selector == selector!("balance_of") {
+                    Ok(vec![FieldElement::ZERO])
+                } else {
+                    Err(StarknetClientError::Contract(
+                        "Entry point not found in contract".to_string(),
+                    ))
+                }
+            });
+
+        let result = manager(client)
+            .identify_contract_type(FieldElement::ONE, BlockId::Number(7))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result,
+            (ContractType::ERC1155, IdentificationMethod::Probing)
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use crate::storage::types::{EventType, TokenEvent, MemecoinCreatedEvent};
+use crate::managers::memecoin_manager::{
+    MEMECOIN_CREATED_SELECTOR, MEMECOIN_LAUNCHED_SELECTOR, TOKEN_LOCKED_SELECTOR,
+};
+use crate::managers::swap_manager::{EKUBO_SWAPPED_SELECTOR, JEDISWAP_SWAP_SELECTOR};
+use crate::storage::types::{EventType, TokenEvent};
 use crate::storage::Storage;
 use crate::ContractType;
 use anyhow::{anyhow, Result};
@@ -11,7 +15,6 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, trace};
 
 const TRANSFER_SELECTOR: FieldElement = selector!("Transfer");
-const MEMECOINCREATED_SELECTOR: FieldElement = selector!("MemecoinCreated");
 
 #[derive(Debug)]
 pub struct EventManager<S: Storage> {
@@ -28,7 +31,14 @@ impl<S: Storage> EventManager<S> {
 
     /// Returns the selectors used to filter events.
     pub fn keys_selector(&self) -> Option<Vec<Vec<FieldElement>>> {
-        Some(vec![vec![TRANSFER_SELECTOR, MEMECOINCREATED_SELECTOR]])
+        Some(vec![vec![
+            TRANSFER_SELECTOR,
+            MEMECOIN_CREATED_SELECTOR,
+            MEMECOIN_LAUNCHED_SELECTOR,
+            TOKEN_LOCKED_SELECTOR,
+            JEDISWAP_SWAP_SELECTOR,
+            EKUBO_SWAPPED_SELECTOR,
+        ]])
     }
 
     /// Formats & register a token event based on the event content.
@@ -38,6 +48,25 @@ impl<S: Storage> EventManager<S> {
         event: &EmittedEvent,
         contract_type: ContractType,
         block_timestamp: u64,
+    ) -> Result<(CairoU256, TokenEvent)> {
+        let (token_id, token_event) = self.format_event(event, contract_type, block_timestamp)?;
+
+        trace!("Registering event: {:?}", token_event);
+
+        self.storage
+            .register_event(&token_event, block_timestamp)
+            .await?;
+
+        Ok((token_id, token_event))
+    }
+
+    /// Formats a token event based on the event content, without
+    /// registering it.
+    pub fn format_event(
+        &self,
+        event: &EmittedEvent,
+        contract_type: ContractType,
+        block_timestamp: u64,
     ) -> Result<(CairoU256, TokenEvent)> {
         let mut token_event = TokenEvent::default();
 
@@ -46,27 +75,6 @@ impl<S: Storage> EventManager<S> {
             event, contract_type, block_timestamp
         );
 
-        // Check if the event is a MemecoinCreated event
-        if event.keys[0] == MEMECOINCREATED_SELECTOR {
-            let event_info = Self::get_memecoin_created_info_from_felts(&event.data)
-                .ok_or_else(|| anyhow!("Invalid data for MemecoinCreated event"))?;
-            let (owner, name, symbol, initial_supply, memecoin_address) = event_info;
-
-            let memecoin_event = MemecoinCreatedEvent {
-                owner: to_hex_str(&owner),
-                name: String::from_utf8_lossy(&name.to_bytes_be()).into_owned(),
-                symbol: String::from_utf8_lossy(&symbol.to_bytes_be()).into_owned(),
-                initial_supply,
-                memecoin_address: to_hex_str(&memecoin_address),
-            };
-
-            debug!("Processing MemecoinCreated event: {:?}", memecoin_event);
-
-            // Implement logic to store or handle the MemecoinCreated event as needed
-            // self.storage.register_memecoin_created_event(&memecoin_event, block_timestamp).await?;
-            return Err(anyhow!("MemecoinCreated event processed"));
-        }
-
         // As cairo didn't have keys before, we first check if the data
         // contains the info. If not, we check into the keys, skipping the first
         // element which is the selector.
@@ -101,13 +109,7 @@ impl<S: Storage> EventManager<S> {
                 .as_secs(),
         );
 
-        trace!("Registering event: {:?}", token_event);
-
-        self.storage
-            .register_event(&token_event, block_timestamp)
-            .await?;
-
-        Ok((token_id, token_event.clone()))
+        Ok((token_id, token_event))
     }
 
     pub fn get_event_type(from: FieldElement, to: FieldElement) -> EventType {
@@ -164,23 +166,6 @@ impl<S: Storage> EventManager<S> {
 
         Some((from, to, token_id))
     }
-
-    /// helper method to parse the event details
-    fn get_memecoin_created_info_from_felts(felts: &[FieldElement]) -> Option<(FieldElement, FieldElement, FieldElement, CairoU256, FieldElement)> {
-        if felts.len() < 5 {
-            return None;
-        }
-        let owner = felts[0];
-        let name = felts[1];
-        let symbol = felts[2];
-        let initial_supply = CairoU256 {
-            low: felts[3].try_into().unwrap(),
-            high: felts[4].try_into().unwrap(),
-        };
-        let memecoin_address = felts[5];
-        Some((owner, name, symbol, initial_supply, memecoin_address))
-    }
-    
 }
 
 #[cfg(test)]
@@ -300,7 +285,14 @@ mod tests {
         let result = manager.keys_selector().unwrap();
 
         // Define expected result
-        let expected = vec![vec![selector!("Transfer")]];
+        let expected = vec![vec![
+            selector!("Transfer"),
+            selector!("MemecoinCreated"),
+            selector!("MemecoinLaunched"),
+            selector!("TokenLocked"),
+            selector!("Swap"),
+            selector!("Swapped"),
+        ]];
 
         // Assert the output
         assert_eq!(result, expected);
@@ -0,0 +1,232 @@
+//! Dead-letter store of the events which couldn't be indexed.
+use crate::storage::types::{
+    EventFailureKind, FailedEvent, FailedEventStatus, IndexingError, StorageError,
+};
+use crate::storage::Storage;
+use ark_starknet::format::to_hex_str;
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::{EmittedEvent, FieldElement};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default number of attempts before an event is permanently failed.
+pub const DEFAULT_MAX_REPLAY_ATTEMPTS: u32 = 5;
+
+/// Outcome of a replay of the pending failed events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub resolved: usize,
+    /// Events permanently failed, not replayed anymore.
+    pub failed: usize,
+    /// Events still to be replayed.
+    pub pending: usize,
+}
+
+#[derive(Debug)]
+pub struct FailureManager<S: Storage> {
+    storage: Arc<S>,
+    max_attempts: u32,
+}
+
+impl<S: Storage> FailureManager<S> {
+    /// Initializes a new instance, permanently failing the events
+    /// after `max_attempts` attempts.
+    pub fn new(storage: Arc<S>, max_attempts: u32) -> Self {
+        Self {
+            storage,
+            max_attempts,
+        }
+    }
+
+    /// Returns the id of the event, hash of its transaction, emitter,
+    /// keys and data, to find it again in its block.
+    pub fn failure_id(event: &EmittedEvent) -> String {
+        let mut elements: Vec<FieldElement> = vec![event.transaction_hash, event.from_address];
+        elements.extend(&event.keys);
+        elements.extend(&event.data);
+
+        to_hex_str(&compute_hash_on_elements(&elements))
+    }
+
+    /// Records a failure of the event, counting the attempts of the
+    /// previous failures of the same event. The event is permanently
+    /// failed once the maximum number of attempts is reached.
+    pub async fn record(
+        &self,
+        event: &EmittedEvent,
+        block_timestamp: u64,
+        kind: EventFailureKind,
+        error: IndexingError,
+    ) -> Result<FailedEvent, StorageError> {
+        let failure_id = Self::failure_id(event);
+
+        let attempts = match self.storage.get_failed_event(&failure_id).await {
+            Ok(previous) => previous.attempts + 1,
+            Err(StorageError::NotFound(_)) => 1,
+            Err(e) => return Err(e),
+        };
+
+        let failed = FailedEvent {
+            failure_id,
+            block_number: event.block_number,
+            block_timestamp,
+            transaction_hash: to_hex_str(&event.transaction_hash),
+            contract_address: to_hex_str(&event.from_address),
+            kind,
+            error,
+            attempts,
+            status: if attempts >= self.max_attempts {
+                FailedEventStatus::Failed
+            } else {
+                FailedEventStatus::Pending
+            },
+        };
+
+        warn!("Event failed: {:?}", failed);
+
+        self.storage.set_failed_event(&failed).await?;
+        Ok(failed)
+    }
+
+    /// Updates the failure once its event has been processed again,
+    /// returning its new status. The event is resolved if no new failure
+    /// has been recorded since `failed`.
+    pub async fn replayed(&self, failed: &FailedEvent) -> Result<FailedEventStatus, StorageError> {
+        let current = self.storage.get_failed_event(&failed.failure_id).await?;
+        if current.attempts > failed.attempts {
+            return Ok(current.status);
+        }
+
+        self.storage
+            .set_failed_event(&FailedEvent {
+                status: FailedEventStatus::Resolved,
+                ..current
+            })
+            .await?;
+        Ok(FailedEventStatus::Resolved)
+    }
+
+    /// Permanently fails the event, which can't be replayed.
+    pub async fn give_up(
+        &self,
+        failed: &FailedEvent,
+        error: IndexingError,
+    ) -> Result<(), StorageError> {
+        warn!("Giving up event {}: {}", failed.failure_id, error);
+
+        self.storage
+            .set_failed_event(&FailedEvent {
+                error,
+                status: FailedEventStatus::Failed,
+                ..failed.clone()
+            })
+            .await
+    }
+
+    /// Returns the failed events with the status, or all of them if `None`.
+    pub async fn failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> Result<Vec<FailedEvent>, StorageError> {
+        self.storage.get_failed_events(status).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockStorage;
+
+    fn event(data: u64) -> EmittedEvent {
+        EmittedEvent {
+            from_address: FieldElement::from(0x100_u64),
+            keys: vec![FieldElement::from(0x1_u64)],
+            data: vec![FieldElement::from(data)],
+            block_hash: FieldElement::ONE,
+            block_number: 1,
+            transaction_hash: FieldElement::from(0x10_u64),
+        }
+    }
+
+    #[test]
+    fn test_failure_id() {
+        assert_eq!(
+            FailureManager::<MockStorage>::failure_id(&event(1)),
+            FailureManager::<MockStorage>::failure_id(&event(1))
+        );
+        // Events of a same transaction differ by their content.
+        assert_ne!(
+            FailureManager::<MockStorage>::failure_id(&event(1)),
+            FailureManager::<MockStorage>::failure_id(&event(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_counts_attempts() {
+        let mut storage = MockStorage::default();
+
+        storage.expect_get_failed_event().returning(|id| {
+            Box::pin(futures::future::ready(Ok(FailedEvent {
+                failure_id: id.to_string(),
+                block_number: 1,
+                block_timestamp: 1000,
+                transaction_hash: String::new(),
+                contract_address: String::new(),
+                kind: EventFailureKind::ContractIdentification,
+                error: IndexingError::InputTooLong,
+                attempts: 2,
+                status: FailedEventStatus::Pending,
+            })))
+        });
+        storage
+            .expect_set_failed_event()
+            .withf(|e| e.attempts == 3 && e.kind == EventFailureKind::TokenRegistration)
+            .times(1)
+            .returning(|_| Box::pin(futures::future::ready(Ok(()))));
+
+        let manager = FailureManager::new(Arc::new(storage), DEFAULT_MAX_REPLAY_ATTEMPTS);
+        let failed = manager
+            .record(
+                &event(1),
+                1000,
+                EventFailureKind::TokenRegistration,
+                IndexingError::Storage("error".to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            failed.transaction_hash,
+            to_hex_str(&FieldElement::from(0x10_u64))
+        );
+        assert_eq!(failed.status, FailedEventStatus::Pending);
+        assert_eq!(failed.error, IndexingError::Storage("error".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_record_fails_after_max_attempts() {
+        let mut storage = MockStorage::default();
+
+        storage.expect_get_failed_event().returning(|id| {
+            Box::pin(futures::future::ready(Err(StorageError::NotFound(
+                format!("failed event: {}", id),
+            ))))
+        });
+        storage
+            .expect_set_failed_event()
+            .withf(|e| e.attempts == 1 && e.status == FailedEventStatus::Failed)
+            .times(1)
+            .returning(|_| Box::pin(futures::future::ready(Ok(()))));
+
+        let manager = FailureManager::new(Arc::new(storage), 1);
+        manager
+            .record(
+                &event(1),
+                1000,
+                EventFailureKind::SwapEvent,
+                IndexingError::Other("error".to_string()),
+            )
+            .await
+            .unwrap();
+    }
+}
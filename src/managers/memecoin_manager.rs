@@ -0,0 +1,654 @@
+use crate::managers::EventManager;
+use crate::storage::types::{
+    LiquidityLock, LiquidityType, MemecoinCreatedEvent, MemecoinLaunchedEvent, StorageError,
+    TokenTransfer,
+};
+use crate::storage::Storage;
+use anyhow::{anyhow, Result};
+use ark_starknet::client::StarknetClient;
+use ark_starknet::{format::to_hex_str, CairoU256};
+use starknet::core::types::{BlockId, EmittedEvent, FieldElement};
+use starknet::core::utils::parse_cairo_short_string;
+use starknet::macros::selector;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+pub const MEMECOIN_CREATED_SELECTOR: FieldElement = selector!("MemecoinCreated");
+pub const MEMECOIN_LAUNCHED_SELECTOR: FieldElement = selector!("MemecoinLaunched");
+pub const TOKEN_LOCKED_SELECTOR: FieldElement = selector!("TokenLocked");
+const TRANSFER_SELECTOR: FieldElement = selector!("Transfer");
+
+/// Events related to the memecoins: emitted by the Unruggable factory,
+/// the lock manager, the memecoins themselves or the funding tokens.
+#[derive(Debug, Clone)]
+pub enum MemecoinEvent {
+    Created(MemecoinCreatedEvent),
+    Launched(MemecoinLaunchedEvent),
+    Transfer(TokenTransfer),
+    Locked(LiquidityLock),
+    /// Transfer of a funding token, only the first one received by a
+    /// wallet since the indexing start block being registered.
+    Funding(TokenTransfer),
+}
+
+impl MemecoinEvent {
+    /// Name of the event type, as used in the metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemecoinEvent::Created(_) => "MEMECOIN_CREATED",
+            MemecoinEvent::Launched(_) => "MEMECOIN_LAUNCHED",
+            MemecoinEvent::Transfer(_) => "MEMECOIN_TRANSFER",
+            MemecoinEvent::Locked(_) => "LIQUIDITY_LOCKED",
+            MemecoinEvent::Funding(_) => "FUNDING",
+        }
+    }
+}
+
+pub struct MemecoinManager<S: Storage, C: StarknetClient> {
+    storage: Arc<S>,
+    client: Arc<C>,
+    /// Addresses of the factories allowed to emit memecoin events.
+    /// Any emitter is accepted if empty.
+    factories: Vec<FieldElement>,
+    /// Addresses of the lock managers allowed to emit lock events.
+    /// Any emitter is accepted if empty.
+    lock_managers: Vec<FieldElement>,
+    /// Tokens (usually ETH and STRK) whose transfers fund the wallets,
+    /// the first one indexed being the funding. None if not configured.
+    funding_tokens: Vec<FieldElement>,
+    /// Addresses of the memecoins created by the factories.
+    memecoins: RwLock<HashSet<FieldElement>>,
+    /// True once the known memecoins were loaded from the storage.
+    memecoins_loaded: AtomicBool,
+}
+
+impl<S: Storage, C: StarknetClient> MemecoinManager<S, C> {
+    /// Initializes a new instance.
+    pub fn new(
+        storage: Arc<S>,
+        client: Arc<C>,
+        factories: Vec<FieldElement>,
+        lock_managers: Vec<FieldElement>,
+        funding_tokens: Vec<FieldElement>,
+    ) -> Self {
+        Self {
+            storage,
+            client,
+            factories,
+            lock_managers,
+            funding_tokens,
+            memecoins: RwLock::new(HashSet::new()),
+            memecoins_loaded: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true if the address is a memecoin created by a factory.
+    pub async fn is_memecoin(&self, address: &FieldElement) -> bool {
+        if !self.memecoins_loaded.load(Ordering::SeqCst) {
+            // Loaded again on the next call if the storage fails.
+            match self.storage.get_memecoins().await {
+                Ok(memecoins) => {
+                    let mut known = self.memecoins.write().unwrap();
+                    for m in memecoins {
+                        if let Ok(a) = FieldElement::from_hex_be(&m.memecoin_address) {
+                            known.insert(a);
+                        }
+                    }
+                    self.memecoins_loaded.store(true, Ordering::SeqCst);
+                }
+                Err(e) => warn!("Can't load known memecoins: {:?}", e),
+            }
+        }
+
+        self.memecoins.read().unwrap().contains(address)
+    }
+
+    /// Returns the address of the memecoins whose locked liquidity
+    /// (pair address) is one of the given tokens.
+    pub async fn get_memecoins_by_liquidity(
+        &self,
+        tokens: &HashSet<String>,
+    ) -> Result<Vec<String>, StorageError> {
+        Ok(self
+            .storage
+            .get_memecoins()
+            .await?
+            .into_iter()
+            .filter(|m| {
+                m.launch
+                    .as_ref()
+                    .and_then(|l| l.locked_liquidity.as_ref())
+                    .map_or(false, |p| tokens.contains(p))
+            })
+            .map(|m| m.memecoin_address)
+            .collect())
+    }
+
+    /// Returns true if the event must be handled by this manager.
+    pub async fn is_memecoin_event(&self, event: &EmittedEvent) -> bool {
+        let selector = match event.keys.first() {
+            Some(s) => *s,
+            None => return false,
+        };
+
+        if selector == MEMECOIN_CREATED_SELECTOR || selector == MEMECOIN_LAUNCHED_SELECTOR {
+            self.factories.is_empty() || self.factories.contains(&event.from_address)
+        } else if selector == TOKEN_LOCKED_SELECTOR {
+            self.lock_managers.is_empty() || self.lock_managers.contains(&event.from_address)
+        } else if selector == TRANSFER_SELECTOR {
+            self.funding_tokens.contains(&event.from_address)
+                || self.is_memecoin(&event.from_address).await
+        } else {
+            false
+        }
+    }
+
+    /// Decodes & registers an event related to the memecoins.
+    pub async fn format_and_register_event(
+        &self,
+        event: &EmittedEvent,
+        block_timestamp: u64,
+    ) -> Result<MemecoinEvent> {
+        if event.keys[0] == MEMECOIN_CREATED_SELECTOR {
+            let created = Self::get_memecoin_created_from_felts(&event.data)
+                .ok_or_else(|| anyhow!("Invalid data for MemecoinCreated event"))?;
+
+            debug!("Processing MemecoinCreated event: {:?}", created);
+
+            self.storage
+                .register_memecoin_created_event(&created, block_timestamp)
+                .await?;
+
+            if let Ok(a) = FieldElement::from_hex_be(&created.memecoin_address) {
+                self.memecoins.write().unwrap().insert(a);
+            }
+
+            Ok(MemecoinEvent::Created(created))
+        } else if event.keys[0] == TRANSFER_SELECTOR
+            && self.funding_tokens.contains(&event.from_address)
+        {
+            let transfer = Self::get_transfer_from_event(event, block_timestamp)
+                .ok_or_else(|| anyhow!("Invalid data for funding Transfer event"))?;
+
+            match self.storage.register_wallet_funding(&transfer).await {
+                Ok(()) | Err(StorageError::AlreadyExists(_)) => (),
+                Err(e) => return Err(e.into()),
+            };
+
+            Ok(MemecoinEvent::Funding(transfer))
+        } else if event.keys[0] == TRANSFER_SELECTOR {
+            let transfer = Self::get_transfer_from_event(event, block_timestamp)
+                .ok_or_else(|| anyhow!("Invalid data for memecoin Transfer event"))?;
+
+            self.storage
+                .register_token_transfer(&transfer, block_timestamp)
+                .await?;
+
+            Ok(MemecoinEvent::Transfer(transfer))
+        } else if event.keys[0] == TOKEN_LOCKED_SELECTOR {
+            let lock = Self::get_lock_from_event(event, block_timestamp)
+                .ok_or_else(|| anyhow!("Invalid data for TokenLocked event"))?;
+
+            self.storage
+                .register_liquidity_lock(&lock, block_timestamp)
+                .await?;
+
+            Ok(MemecoinEvent::Locked(lock))
+        } else {
+            let (memecoin, quote_token, exchange_name) =
+                Self::get_memecoin_launched_info_from_felts(&event.data)
+                    .ok_or_else(|| anyhow!("Invalid data for MemecoinLaunched event"))?;
+
+            let block = BlockId::Number(event.block_number);
+
+            let (liquidity_type, locked_liquidity) =
+                match self.get_liquidity_type(memecoin, block).await {
+                    Some((t, locked)) => (t, Some(to_hex_str(&locked))),
+                    None => (Self::liquidity_type_from_exchange(&exchange_name), None),
+                };
+
+            let launched = MemecoinLaunchedEvent {
+                memecoin_address: to_hex_str(&memecoin),
+                quote_token: to_hex_str(&quote_token),
+                liquidity_type,
+                locked_liquidity,
+                team_allocation: self
+                    .get_team_allocation(memecoin, block)
+                    .await
+                    .map(|a| a.to_decimal(false)),
+                transaction_hash: to_hex_str(&event.transaction_hash),
+                block_number: event.block_number,
+                block_timestamp,
+            };
+
+            debug!("Processing MemecoinLaunched event: {:?}", launched);
+
+            self.storage
+                .register_memecoin_launched_event(&launched, block_timestamp)
+                .await?;
+
+            Ok(MemecoinEvent::Launched(launched))
+        }
+    }
+
+    /// Returns the liquidity type of the memecoin with the locked liquidity
+    /// (pair address or position id), `None` if the memecoin is not launched
+    /// or doesn't expose it.
+    async fn get_liquidity_type(
+        &self,
+        memecoin: FieldElement,
+        block: BlockId,
+    ) -> Option<(LiquidityType, FieldElement)> {
+        let r = match self
+            .client
+            .call_contract(memecoin, selector!("liquidity_type"), vec![], block)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(
+                    "Can't get liquidity type of {}: {:?}",
+                    to_hex_str(&memecoin),
+                    e
+                );
+                return None;
+            }
+        };
+
+        // Option::Some is the variant 0, then the LiquidityType variant
+        // (JediERC20, StarkDeFiERC20 or EkuboNFT) and its value.
+        if r.len() < 3 || r[0] != FieldElement::ZERO {
+            return None;
+        }
+
+        match r[1] {
+            v if v == FieldElement::ZERO => Some((LiquidityType::JediSwap, r[2])),
+            v if v == FieldElement::ONE => Some((LiquidityType::StarkDeFi, r[2])),
+            v if v == FieldElement::TWO => Some((LiquidityType::Ekubo, r[2])),
+            _ => Some((LiquidityType::Unknown, r[2])),
+        }
+    }
+
+    async fn get_team_allocation(
+        &self,
+        memecoin: FieldElement,
+        block: BlockId,
+    ) -> Option<CairoU256> {
+        match self
+            .client
+            .call_contract(memecoin, selector!("get_team_allocation"), vec![], block)
+            .await
+        {
+            Ok(r) if r.len() >= 2 => Some(CairoU256 {
+                low: r[0].try_into().ok()?,
+                high: r[1].try_into().ok()?,
+            }),
+            Ok(_) => None,
+            Err(e) => {
+                warn!(
+                    "Can't get team allocation of {}: {:?}",
+                    to_hex_str(&memecoin),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn liquidity_type_from_exchange(exchange_name: &str) -> LiquidityType {
+        match exchange_name.to_lowercase().as_str() {
+            "jediswap" => LiquidityType::JediSwap,
+            "starkdefi" => LiquidityType::StarkDeFi,
+            "ekubo" => LiquidityType::Ekubo,
+            _ => LiquidityType::Unknown,
+        }
+    }
+
+    /// Returns the transfer from the event. Depending on the ERC20
+    /// implementation, `from` and `to` are in the keys or in the data.
+    fn get_transfer_from_event(
+        event: &EmittedEvent,
+        block_timestamp: u64,
+    ) -> Option<TokenTransfer> {
+        let felts: Vec<FieldElement> = event.keys[1..]
+            .iter()
+            .chain(event.data.iter())
+            .copied()
+            .collect();
+
+        if felts.len() < 4 {
+            return None;
+        }
+
+        let amount = CairoU256 {
+            low: felts[2].try_into().ok()?,
+            high: felts[3].try_into().ok()?,
+        };
+
+        let event_id =
+            EventManager::<S>::get_event_id(&amount, &felts[0], &felts[1], block_timestamp, event);
+
+        Some(TokenTransfer {
+            token_address: to_hex_str(&event.from_address),
+            from_address: to_hex_str(&felts[0]),
+            to_address: to_hex_str(&felts[1]),
+            amount: amount.to_decimal(false),
+            transaction_hash: to_hex_str(&event.transaction_hash),
+            event_id: to_hex_str(&event_id),
+            block_number: event.block_number,
+            block_timestamp,
+        })
+    }
+
+    /// Returns the lock from the event, where the lock address is
+    /// the only key, and data is (token, owner, amount, unlock_time).
+    fn get_lock_from_event(event: &EmittedEvent, block_timestamp: u64) -> Option<LiquidityLock> {
+        if event.keys.len() < 2 || event.data.len() < 5 {
+            return None;
+        }
+
+        let amount = CairoU256 {
+            low: event.data[2].try_into().ok()?,
+            high: event.data[3].try_into().ok()?,
+        };
+
+        Some(LiquidityLock {
+            lock_address: to_hex_str(&event.keys[1]),
+            token_address: to_hex_str(&event.data[0]),
+            owner: to_hex_str(&event.data[1]),
+            amount: amount.to_decimal(false),
+            unlock_time: event.data[4].try_into().ok()?,
+            block_timestamp,
+        })
+    }
+
+    /// Returns the created memecoin from the event data, which is
+    /// (owner, name, symbol, initial_supply, memecoin_address).
+    fn get_memecoin_created_from_felts(felts: &[FieldElement]) -> Option<MemecoinCreatedEvent> {
+        if felts.len() < 6 {
+            return None;
+        }
+
+        let initial_supply = CairoU256 {
+            low: felts[3].try_into().ok()?,
+            high: felts[4].try_into().ok()?,
+        };
+
+        Some(MemecoinCreatedEvent::new(
+            to_hex_str(&felts[0]),
+            parse_cairo_short_string(&felts[1]).ok()?,
+            parse_cairo_short_string(&felts[2]).ok()?,
+            initial_supply,
+            to_hex_str(&felts[5]),
+        ))
+    }
+
+    /// Returns (memecoin_address, quote_token, exchange_name) from the event data.
+    fn get_memecoin_launched_info_from_felts(
+        felts: &[FieldElement],
+    ) -> Option<(FieldElement, FieldElement, String)> {
+        if felts.len() < 3 {
+            return None;
+        }
+
+        Some((
+            felts[0],
+            felts[1],
+            parse_cairo_short_string(&felts[2]).unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::snipers::ETH_ADDRESS;
+    use crate::analysis::SniperConfig;
+    use crate::storage::types::MemecoinInfo;
+    use crate::storage::MockStorage;
+    use ark_starknet::client::MockStarknetClient;
+    use starknet::core::utils::cairo_short_string_to_felt;
+
+    fn launched_event() -> EmittedEvent {
+        EmittedEvent {
+            from_address: FieldElement::from_hex_be("0x1a46").unwrap(),
+            block_hash: FieldElement::from_dec_str("786").unwrap(),
+            transaction_hash: FieldElement::from_dec_str("5432").unwrap(),
+            block_number: 111,
+            keys: vec![MEMECOIN_LAUNCHED_SELECTOR],
+            data: vec![
+                FieldElement::from_hex_be("0x1234").unwrap(),
+                FieldElement::from_hex_be("0x49d3").unwrap(),
+                cairo_short_string_to_felt("Ekubo").unwrap(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_get_memecoin_created_from_felts() {
+        let felts = vec![
+            FieldElement::from_hex_be("0x1").unwrap(),
+            cairo_short_string_to_felt("Doge").unwrap(),
+            cairo_short_string_to_felt("DOGE").unwrap(),
+            FieldElement::from(1000_u64),
+            FieldElement::ZERO,
+            FieldElement::from_hex_be("0x1234").unwrap(),
+        ];
+
+        let created =
+            MemecoinManager::<MockStorage, MockStarknetClient>::get_memecoin_created_from_felts(
+                &felts,
+            )
+            .unwrap();
+
+        assert_eq!(created.name, "Doge");
+        assert_eq!(created.symbol, "DOGE");
+        assert_eq!(created.initial_supply.low, 1000);
+        assert_eq!(
+            created.memecoin_address,
+            to_hex_str(&FieldElement::from_hex_be("0x1234").unwrap())
+        );
+
+        assert!(
+            MemecoinManager::<MockStorage, MockStarknetClient>::get_memecoin_created_from_felts(
+                &felts[..5]
+            )
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_format_memecoin_launched() {
+        let mut storage = MockStorage::default();
+        storage
+            .expect_register_memecoin_launched_event()
+            .returning(|_, _| Box::pin(futures::future::ready(Ok(()))));
+
+        let mut client = MockStarknetClient::default();
+        client
+            .expect_call_contract()
+            .returning(|_, selector, _, _| {
+                if selector == selector!("liquidity_type") {
+                    Ok(vec![
+                        FieldElement::ZERO,
+                        FieldElement::TWO,
+                        FieldElement::from(42_u64),
+                    ])
+                } else {
+                    Ok(vec![FieldElement::from(500_u64), FieldElement::ZERO])
+                }
+            });
+
+        let manager =
+            MemecoinManager::new(Arc::new(storage), Arc::new(client), vec![], vec![], vec![]);
+        let event = launched_event();
+
+        assert!(manager.is_memecoin_event(&event).await);
+
+        let launched = match manager
+            .format_and_register_event(&event, 1000)
+            .await
+            .unwrap()
+        {
+            MemecoinEvent::Launched(l) => l,
+            _ => panic!("Expected a launched event"),
+        };
+
+        assert_eq!(launched.liquidity_type, LiquidityType::Ekubo);
+        assert_eq!(
+            launched.locked_liquidity,
+            Some(to_hex_str(&FieldElement::from(42_u64)))
+        );
+        assert_eq!(launched.team_allocation, Some("500".to_string()));
+        assert_eq!(launched.block_number, 111);
+    }
+
+    #[tokio::test]
+    async fn test_is_memecoin_event_checks_emitter() {
+        let manager = MemecoinManager::new(
+            Arc::new(MockStorage::default()),
+            Arc::new(MockStarknetClient::default()),
+            vec![FieldElement::from_hex_be("0x999").unwrap()],
+            vec![],
+            vec![],
+        );
+
+        assert!(!manager.is_memecoin_event(&launched_event()).await);
+    }
+
+    #[tokio::test]
+    async fn test_memecoins_loaded_again_after_storage_error() {
+        let memecoin = FieldElement::from_hex_be("0x3e3e").unwrap();
+
+        let mut storage = MockStorage::default();
+        let mut seq = mockall::Sequence::new();
+        storage
+            .expect_get_memecoins()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| {
+                Box::pin(futures::future::ready(Err(StorageError::DatabaseError(
+                    "unavailable".to_string(),
+                ))))
+            });
+        storage
+            .expect_get_memecoins()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move || {
+                Box::pin(futures::future::ready(Ok(vec![MemecoinInfo {
+                    memecoin_address: to_hex_str(&memecoin),
+                    owner: "0xaaa".to_string(),
+                    name: "Doge".to_string(),
+                    symbol: "DOGE".to_string(),
+                    initial_supply: "1000000".to_string(),
+                    created_at: 1000,
+                    launch: None,
+                }])))
+            });
+
+        let manager = MemecoinManager::new(
+            Arc::new(storage),
+            Arc::new(MockStarknetClient::default()),
+            vec![],
+            vec![],
+            vec![],
+        );
+
+        assert!(!manager.is_memecoin(&memecoin).await);
+        assert!(manager.is_memecoin(&memecoin).await);
+        // Loaded once, not queried anymore.
+        assert!(manager.is_memecoin(&memecoin).await);
+    }
+
+    #[tokio::test]
+    async fn test_funding_is_registered_once() {
+        let eth = FieldElement::from_hex_be("0xe7").unwrap();
+
+        let mut storage = MockStorage::default();
+        storage
+            .expect_register_wallet_funding()
+            .times(1)
+            .returning(|t| {
+                Box::pin(futures::future::ready(Err(StorageError::AlreadyExists(
+                    t.to_address.clone(),
+                ))))
+            });
+
+        let manager = MemecoinManager::new(
+            Arc::new(storage),
+            Arc::new(MockStarknetClient::default()),
+            vec![],
+            vec![],
+            vec![eth],
+        );
+
+        let mut event = launched_event();
+        event.from_address = eth;
+        event.keys = vec![TRANSFER_SELECTOR];
+        event.data = vec![
+            FieldElement::ONE,
+            FieldElement::TWO,
+            FieldElement::from(10_u64),
+            FieldElement::ZERO,
+        ];
+
+        assert!(manager.is_memecoin_event(&event).await);
+
+        // A wallet already funded is not an error.
+        match manager.format_and_register_event(&event, 10).await.unwrap() {
+            MemecoinEvent::Funding(t) => assert_eq!(t.to_address, to_hex_str(&FieldElement::TWO)),
+            _ => panic!("Expected a funding event"),
+        };
+    }
+
+    #[tokio::test]
+    async fn test_funding_not_registered_by_default() {
+        let mut storage = MockStorage::default();
+        storage
+            .expect_get_memecoins()
+            .returning(|| Box::pin(futures::future::ready(Ok(vec![]))));
+        storage.expect_register_wallet_funding().never();
+
+        let manager = MemecoinManager::new(
+            Arc::new(storage),
+            Arc::new(MockStarknetClient::default()),
+            vec![],
+            vec![],
+            SniperConfig::default().funding_tokens,
+        );
+
+        let mut event = launched_event();
+        event.from_address = ETH_ADDRESS;
+        event.keys = vec![TRANSFER_SELECTOR];
+        event.data = vec![
+            FieldElement::ONE,
+            FieldElement::TWO,
+            FieldElement::from(10_u64),
+            FieldElement::ZERO,
+        ];
+
+        assert!(!manager.is_memecoin_event(&event).await);
+    }
+
+    #[test]
+    fn test_get_transfer_from_keys_and_data() {
+        let mut event = launched_event();
+        event.keys = vec![
+            TRANSFER_SELECTOR,
+            FieldElement::from_hex_be("0x1").unwrap(),
+            FieldElement::from_hex_be("0x2").unwrap(),
+        ];
+        event.data = vec![FieldElement::from(1500_u64), FieldElement::ZERO];
+
+        let transfer =
+            MemecoinManager::<MockStorage, MockStarknetClient>::get_transfer_from_event(&event, 10)
+                .unwrap();
+
+        assert_eq!(transfer.from_address, to_hex_str(&FieldElement::ONE));
+        assert_eq!(transfer.to_address, to_hex_str(&FieldElement::TWO));
+        assert_eq!(transfer.amount, "1500");
+    }
+}
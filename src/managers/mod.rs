@@ -1,11 +1,23 @@
+pub mod contract_cache;
+pub use contract_cache::{ContractCache, ContractCacheConfig, ContractCacheStats};
+
 pub mod contract_manager;
 pub use contract_manager::ContractManager;
 
+pub mod memecoin_manager;
+pub use memecoin_manager::{MemecoinEvent, MemecoinManager};
+
+pub mod swap_manager;
+pub use swap_manager::{EkuboRecipients, SwapManager};
+
 pub mod event_manager;
 pub use event_manager::EventManager;
 
 pub mod token_manager;
 pub use token_manager::TokenManager;
 
+pub mod failure_manager;
+pub use failure_manager::{FailureManager, ReplayReport, DEFAULT_MAX_REPLAY_ATTEMPTS};
+
 pub mod block_manager;
-pub use block_manager::{BlockManager, PendingBlockData};
+pub use block_manager::{BlockIndexing, BlockManager, PendingBlockData};
@@ -0,0 +1,683 @@
+use crate::managers::EventManager;
+use crate::storage::types::{LiquidityType, MemecoinLaunchedEvent, Pool, StorageError, SwapEvent};
+use crate::storage::Storage;
+use anyhow::{anyhow, Result};
+use ark_starknet::{format::to_hex_str, CairoU256};
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::{EmittedEvent, FieldElement};
+use starknet::macros::{felt, selector};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
+
+pub const JEDISWAP_SWAP_SELECTOR: FieldElement = selector!("Swap");
+pub const EKUBO_SWAPPED_SELECTOR: FieldElement = selector!("Swapped");
+const TRANSFER_SELECTOR: FieldElement = selector!("Transfer");
+
+/// Ekubo core contract on mainnet, emitting the swaps of every Ekubo pool.
+pub const EKUBO_CORE_ADDRESS: FieldElement =
+    felt!("0x00000005dd3d2f4429af886cd1a3b08289dbcea99a294197e9eb43b0e0325b4b");
+
+/// Parameters of an Ekubo pool which, with its two sorted tokens,
+/// form the pool key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EkuboPoolParams {
+    pub fee: FieldElement,
+    pub tick_spacing: FieldElement,
+    pub extension: FieldElement,
+}
+
+/// Returns the identifier of the Ekubo pool from its key. Pools of the
+/// same tokens with different fees or tick spacings are distinct.
+pub fn ekubo_pool_address(
+    token0: FieldElement,
+    token1: FieldElement,
+    params: &EkuboPoolParams,
+) -> FieldElement {
+    compute_hash_on_elements(&[
+        token0,
+        token1,
+        params.fee,
+        params.tick_spacing,
+        params.extension,
+    ])
+}
+
+/// Recipients of the tokens withdrawn from the Ekubo core, keyed by
+/// transaction hash and token.
+pub type EkuboRecipients = HashMap<(FieldElement, FieldElement), FieldElement>;
+
+/// Indexes the swaps of the pools in which a memecoin is traded.
+///
+/// Pools are discovered from the memecoin launches: the JediSwap pair is
+/// the locked liquidity, and Ekubo pools are identified by their key
+/// as all the swaps are emitted by the core contract. The key of the
+/// Ekubo pools not being known at launch, they are registered with their
+/// first swap. Quote pools, pricing the quote tokens against stablecoins,
+/// are given at creation and are not registered in the storage, for the
+/// quote tokens not to be analyzed as memecoins: only their swaps are.
+pub struct SwapManager<S: Storage> {
+    storage: Arc<S>,
+    ekubo_core: FieldElement,
+    /// Pools of the quote tokens against stablecoins, known
+    /// with the other pools but kept out of the storage.
+    quote_pools: Vec<Pool>,
+    /// Known pools, keyed by pool address.
+    pools: RwLock<HashMap<FieldElement, Pool>>,
+    /// Ekubo pools of the launched memecoins, without their address,
+    /// keyed by their sorted tokens.
+    ekubo_pairs: RwLock<HashMap<(FieldElement, FieldElement), Pool>>,
+    /// True once the known pools were loaded from the storage.
+    pools_loaded: AtomicBool,
+}
+
+impl<S: Storage> SwapManager<S> {
+    /// Initializes a new instance.
+    pub fn new(storage: Arc<S>, ekubo_core: FieldElement, quote_pools: Vec<Pool>) -> Self {
+        Self {
+            storage,
+            ekubo_core,
+            quote_pools,
+            pools: RwLock::new(HashMap::new()),
+            ekubo_pairs: RwLock::new(HashMap::new()),
+            pools_loaded: AtomicBool::new(false),
+        }
+    }
+
+    /// Registers the pool in which the launched memecoin is traded.
+    /// Ekubo pools are registered with their first swap, `None` being
+    /// returned for them.
+    pub async fn register_pool_from_launch(
+        &self,
+        launch: &MemecoinLaunchedEvent,
+        block_timestamp: u64,
+    ) -> Result<Option<Pool>> {
+        let memecoin = FieldElement::from_hex_be(&launch.memecoin_address)?;
+        let quote = FieldElement::from_hex_be(&launch.quote_token)?;
+
+        // Both exchanges sort the pool tokens by address.
+        let (token0, token1) = if memecoin < quote {
+            (memecoin, quote)
+        } else {
+            (quote, memecoin)
+        };
+
+        let mut pool = Pool {
+            pool_address: String::new(),
+            exchange: launch.liquidity_type.clone(),
+            memecoin_address: launch.memecoin_address.clone(),
+            quote_token: launch.quote_token.clone(),
+            token0: to_hex_str(&token0),
+            token1: to_hex_str(&token1),
+            block_timestamp,
+        };
+
+        let pool_address = match launch.liquidity_type {
+            LiquidityType::JediSwap => match &launch.locked_liquidity {
+                Some(pair) => FieldElement::from_hex_be(pair)?,
+                None => {
+                    warn!(
+                        "JediSwap pair of memecoin {} is unknown",
+                        launch.memecoin_address
+                    );
+                    return Ok(None);
+                }
+            },
+            LiquidityType::Ekubo => {
+                debug!("Waiting for the first swap of Ekubo pool: {:?}", pool);
+                self.ekubo_pairs
+                    .write()
+                    .unwrap()
+                    .insert((token0, token1), pool);
+                return Ok(None);
+            }
+            // Swaps are only decoded for JediSwap and Ekubo.
+            LiquidityType::StarkDeFi | LiquidityType::Unknown => return Ok(None),
+        };
+        pool.pool_address = to_hex_str(&pool_address);
+
+        self.register_pool(pool_address, &pool).await?;
+        Ok(Some(pool))
+    }
+
+    async fn register_pool(&self, pool_address: FieldElement, pool: &Pool) -> Result<()> {
+        debug!("Registering pool: {:?}", pool);
+
+        match self.storage.register_pool(pool, pool.block_timestamp).await {
+            Ok(()) | Err(StorageError::AlreadyExists(_)) => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        self.pools
+            .write()
+            .unwrap()
+            .insert(pool_address, pool.clone());
+        Ok(())
+    }
+
+    /// Returns true if the pool is a quote pool, whose swaps are
+    /// only registered to price the quote token.
+    pub fn is_quote_pool(&self, pool: &Pool) -> bool {
+        self.quote_pools
+            .iter()
+            .any(|p| p.pool_address == pool.pool_address)
+    }
+
+    /// Returns true if the event is a swap on a known pool.
+    pub async fn is_swap_event(&self, event: &EmittedEvent) -> bool {
+        match event.keys.first() {
+            Some(s) if *s == JEDISWAP_SWAP_SELECTOR => {
+                self.get_pool(&event.from_address).await.is_some()
+            }
+            Some(s) if *s == EKUBO_SWAPPED_SELECTOR && event.from_address == self.ekubo_core => {
+                match Self::ekubo_pool_key(&event.data) {
+                    Some((t0, t1, params)) => {
+                        self.get_pool(&ekubo_pool_address(t0, t1, &params))
+                            .await
+                            .is_some()
+                            || self.ekubo_pairs.read().unwrap().contains_key(&(t0, t1))
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the recipients of the tokens withdrawn from the Ekubo core
+    /// in the events of a block. The Swapped event being emitted with the
+    /// router as locker, the trader of an Ekubo swap is the recipient of
+    /// the token bought, in the same transaction.
+    pub fn ekubo_recipients(&self, events: &[EmittedEvent]) -> EkuboRecipients {
+        let mut recipients = HashMap::new();
+
+        for e in events {
+            if e.keys.first() != Some(&TRANSFER_SELECTOR) {
+                continue;
+            }
+
+            // Depending on the ERC20 implementation, `from` and `to`
+            // are in the keys or in the data.
+            let mut addresses = e.keys[1..].iter().chain(e.data.iter());
+            if let (Some(from), Some(to)) = (addresses.next(), addresses.next()) {
+                if *from == self.ekubo_core {
+                    recipients.insert((e.transaction_hash, e.from_address), *to);
+                }
+            }
+        }
+
+        recipients
+    }
+
+    /// Decodes & registers a swap event, which must be on a known pool.
+    /// The trader of the Ekubo swaps is resolved from the recipients of
+    /// the block. Returns the swap with its pool.
+    pub async fn format_and_register_event(
+        &self,
+        event: &EmittedEvent,
+        ekubo_recipients: &EkuboRecipients,
+        block_timestamp: u64,
+    ) -> Result<(SwapEvent, Pool)> {
+        let (swap, pool) = if event.keys[0] == JEDISWAP_SWAP_SELECTOR {
+            let pool = self
+                .get_pool(&event.from_address)
+                .await
+                .ok_or_else(|| anyhow!("Unknown JediSwap pair"))?;
+
+            let swap = Self::get_jediswap_swap(event, &pool, block_timestamp)
+                .ok_or_else(|| anyhow!("Invalid data for JediSwap Swap event"))?;
+            (swap, pool)
+        } else {
+            let (t0, t1, params) = Self::ekubo_pool_key(&event.data)
+                .ok_or_else(|| anyhow!("Invalid data for Ekubo Swapped event"))?;
+            let pool_address = ekubo_pool_address(t0, t1, &params);
+
+            let pool = match self.get_pool(&pool_address).await {
+                Some(p) => p,
+                None => {
+                    // First swap of the pool of a launched memecoin.
+                    let mut pool = self
+                        .ekubo_pairs
+                        .read()
+                        .unwrap()
+                        .get(&(t0, t1))
+                        .cloned()
+                        .ok_or_else(|| anyhow!("Unknown Ekubo pool"))?;
+                    pool.pool_address = to_hex_str(&pool_address);
+                    self.register_pool(pool_address, &pool).await?;
+                    pool
+                }
+            };
+
+            let swap = Self::get_ekubo_swap(event, &pool, ekubo_recipients, block_timestamp)
+                .ok_or_else(|| anyhow!("Invalid data for Ekubo Swapped event"))?;
+            (swap, pool)
+        };
+
+        debug!("Processing swap: {:?}", swap);
+
+        self.storage.register_swap(&swap, block_timestamp).await?;
+        Ok((swap, pool))
+    }
+
+    async fn get_pool(&self, address: &FieldElement) -> Option<Pool> {
+        if !self.pools_loaded.load(Ordering::SeqCst) {
+            // Loaded again on the next call if the storage fails.
+            let mut loaded = true;
+
+            {
+                let mut known = self.pools.write().unwrap();
+                for p in &self.quote_pools {
+                    match FieldElement::from_hex_be(&p.pool_address) {
+                        Ok(a) => {
+                            known.insert(a, p.clone());
+                        }
+                        Err(e) => warn!("Invalid quote pool {}: {:?}", p.pool_address, e),
+                    }
+                }
+            }
+
+            match self.storage.get_pools().await {
+                Ok(pools) => {
+                    let mut known = self.pools.write().unwrap();
+                    for p in pools {
+                        if let Ok(a) = FieldElement::from_hex_be(&p.pool_address) {
+                            known.insert(a, p);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Can't load known pools: {:?}", e);
+                    loaded = false;
+                }
+            }
+
+            match self.storage.get_memecoins().await {
+                Ok(memecoins) => {
+                    for launch in memecoins.iter().filter_map(|m| m.launch.as_ref()) {
+                        if launch.liquidity_type == LiquidityType::Ekubo {
+                            if let Err(e) = self
+                                .register_pool_from_launch(launch, launch.block_timestamp)
+                                .await
+                            {
+                                warn!(
+                                    "Can't load Ekubo pool of {}: {:?}",
+                                    launch.memecoin_address, e
+                                );
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Can't load launched memecoins: {:?}", e);
+                    loaded = false;
+                }
+            }
+
+            self.pools_loaded.store(loaded, Ordering::SeqCst);
+        }
+
+        self.pools.read().unwrap().get(address).cloned()
+    }
+
+    /// Returns the pool key, after the locker in the data.
+    fn ekubo_pool_key(
+        data: &[FieldElement],
+    ) -> Option<(FieldElement, FieldElement, EkuboPoolParams)> {
+        let params = EkuboPoolParams {
+            fee: *data.get(3)?,
+            tick_spacing: *data.get(4)?,
+            extension: *data.get(5)?,
+        };
+        Some((*data.get(1)?, *data.get(2)?, params))
+    }
+
+    /// JediSwap data is (sender, amount0_in, amount1_in, amount0_out, amount1_out, to),
+    /// with the amounts as u256.
+    fn get_jediswap_swap(
+        event: &EmittedEvent,
+        pool: &Pool,
+        block_timestamp: u64,
+    ) -> Option<SwapEvent> {
+        if event.data.len() < 10 {
+            return None;
+        }
+
+        let u256 = |i: usize| -> Option<CairoU256> {
+            Some(CairoU256 {
+                low: event.data[i].try_into().ok()?,
+                high: event.data[i + 1].try_into().ok()?,
+            })
+        };
+
+        let (amount0_in, amount1_in) = (u256(1)?, u256(3)?);
+        let (amount0_out, amount1_out) = (u256(5)?, u256(7)?);
+        let to = event.data[9];
+
+        let zero = |a: &CairoU256| a.low == 0 && a.high == 0;
+
+        let (token_in, token_out, amount_in, amount_out) = if !zero(&amount0_in) {
+            (&pool.token0, &pool.token1, amount0_in, amount1_out)
+        } else if !zero(&amount1_in) {
+            (&pool.token1, &pool.token0, amount1_in, amount0_out)
+        } else {
+            return None;
+        };
+
+        let event_id = EventManager::<S>::get_event_id(
+            &amount_in,
+            &to,
+            &event.from_address,
+            block_timestamp,
+            event,
+        );
+
+        Some(SwapEvent {
+            event_id: to_hex_str(&event_id),
+            pool_address: pool.pool_address.clone(),
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount_in: amount_in.to_decimal(false),
+            amount_out: amount_out.to_decimal(false),
+            trader: to_hex_str(&to),
+            transaction_hash: to_hex_str(&event.transaction_hash),
+            block_number: event.block_number,
+            block_timestamp,
+        })
+    }
+
+    /// Ekubo data is (locker, pool_key[5], params[6], delta[4], ...), where
+    /// the delta is two i129 (magnitude, is_negative) from the pool
+    /// point of view: the positive amount is paid in by the trader.
+    /// The locker being the router, the trader is the recipient of the token
+    /// bought, left empty when the swap is a hop of a multi-hop route.
+    fn get_ekubo_swap(
+        event: &EmittedEvent,
+        pool: &Pool,
+        recipients: &EkuboRecipients,
+        block_timestamp: u64,
+    ) -> Option<SwapEvent> {
+        if event.data.len() < 16 {
+            return None;
+        }
+
+        let locker = event.data[0];
+        let amount0: u128 = event.data[12].try_into().ok()?;
+        let amount0_negative = event.data[13] != FieldElement::ZERO;
+        let amount1: u128 = event.data[14].try_into().ok()?;
+
+        if amount0 == 0 && amount1 == 0 {
+            return None;
+        }
+
+        let (token_in, token_out, amount_in, amount_out) = if !amount0_negative && amount0 > 0 {
+            (&pool.token0, &pool.token1, amount0, amount1)
+        } else {
+            (&pool.token1, &pool.token0, amount1, amount0)
+        };
+
+        let amount_in = CairoU256 {
+            low: amount_in,
+            high: 0,
+        };
+        let amount_out = CairoU256 {
+            low: amount_out,
+            high: 0,
+        };
+
+        let trader = recipients
+            .get(&(
+                event.transaction_hash,
+                FieldElement::from_hex_be(token_out).ok()?,
+            ))
+            .map(to_hex_str)
+            .unwrap_or_default();
+
+        let pool_address = FieldElement::from_hex_be(&pool.pool_address).ok()?;
+        let event_id = EventManager::<S>::get_event_id(
+            &amount_in,
+            &locker,
+            &pool_address,
+            block_timestamp,
+            event,
+        );
+
+        Some(SwapEvent {
+            event_id: to_hex_str(&event_id),
+            pool_address: pool.pool_address.clone(),
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount_in: amount_in.to_decimal(false),
+            amount_out: amount_out.to_decimal(false),
+            trader,
+            transaction_hash: to_hex_str(&event.transaction_hash),
+            block_number: event.block_number,
+            block_timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MockStorage;
+    use crate::testing::MemoryStorage;
+
+    fn felt(v: u64) -> FieldElement {
+        FieldElement::from(v)
+    }
+
+    fn pool() -> Pool {
+        Pool {
+            pool_address: to_hex_str(&felt(0x9a1)),
+            exchange: LiquidityType::JediSwap,
+            memecoin_address: to_hex_str(&felt(0x10)),
+            quote_token: to_hex_str(&felt(0x20)),
+            token0: to_hex_str(&felt(0x10)),
+            token1: to_hex_str(&felt(0x20)),
+            block_timestamp: 0,
+        }
+    }
+
+    fn event(keys: Vec<FieldElement>, data: Vec<FieldElement>) -> EmittedEvent {
+        EmittedEvent {
+            from_address: felt(0x9a1),
+            keys,
+            data,
+            block_hash: felt(1),
+            block_number: 1,
+            transaction_hash: felt(0x7),
+        }
+    }
+
+    #[test]
+    fn test_get_jediswap_swap() {
+        // Buys the memecoin (token0) with 50 of the quote token (token1).
+        let e = event(
+            vec![JEDISWAP_SWAP_SELECTOR],
+            vec![
+                felt(0xa),
+                felt(0),
+                felt(0),
+                felt(50),
+                felt(0),
+                felt(1000),
+                felt(0),
+                felt(0),
+                felt(0),
+                felt(0xb),
+            ],
+        );
+
+        let swap = SwapManager::<MockStorage>::get_jediswap_swap(&e, &pool(), 100).unwrap();
+
+        assert_eq!(swap.token_in, to_hex_str(&felt(0x20)));
+        assert_eq!(swap.token_out, to_hex_str(&felt(0x10)));
+        assert_eq!(swap.amount_in, "50");
+        assert_eq!(swap.amount_out, "1000");
+        assert_eq!(swap.trader, to_hex_str(&felt(0xb)));
+    }
+
+    /// Swapped event of the pool (0x10, 0x20) with the fee, selling
+    /// 1000 of token0 for 50 of token1.
+    fn ekubo_event(fee: u64) -> EmittedEvent {
+        let mut data = vec![
+            felt(0xc),
+            felt(0x10),
+            felt(0x20),
+            felt(fee),
+            felt(1),
+            felt(0),
+        ];
+        data.extend(vec![FieldElement::ZERO; 6]);
+        data.extend(vec![felt(1000), felt(0), felt(50), felt(1)]);
+
+        let mut e = event(vec![EKUBO_SWAPPED_SELECTOR], data);
+        e.from_address = EKUBO_CORE_ADDRESS;
+        e
+    }
+
+    #[test]
+    fn test_get_ekubo_swap() {
+        let (t0, t1, params) =
+            SwapManager::<MockStorage>::ekubo_pool_key(&ekubo_event(5).data).unwrap();
+        assert_eq!((t0, t1, params.fee), (felt(0x10), felt(0x20), felt(5)));
+
+        let mut pool = pool();
+        pool.exchange = LiquidityType::Ekubo;
+        pool.pool_address = to_hex_str(&ekubo_pool_address(t0, t1, &params));
+
+        let swap = SwapManager::<MockStorage>::get_ekubo_swap(
+            &ekubo_event(5),
+            &pool,
+            &EkuboRecipients::new(),
+            100,
+        )
+        .unwrap();
+
+        assert_eq!(swap.token_in, to_hex_str(&felt(0x10)));
+        assert_eq!(swap.amount_in, "1000");
+        assert_eq!(swap.amount_out, "50");
+        // The locker is a router, not the trader.
+        assert_eq!(swap.trader, "");
+    }
+
+    #[test]
+    fn test_ekubo_trader_is_the_recipient() {
+        let manager =
+            SwapManager::new(Arc::new(MockStorage::default()), EKUBO_CORE_ADDRESS, vec![]);
+
+        // Withdrawal of the token bought, with `from` and `to` in the
+        // data, and a transfer to the core in the keys.
+        let mut withdrawal = event(
+            vec![TRANSFER_SELECTOR],
+            vec![EKUBO_CORE_ADDRESS, felt(0xb), felt(50), felt(0)],
+        );
+        withdrawal.from_address = felt(0x20);
+        let mut deposit = event(
+            vec![TRANSFER_SELECTOR, felt(0xb), EKUBO_CORE_ADDRESS],
+            vec![felt(1000), felt(0)],
+        );
+        deposit.from_address = felt(0x10);
+
+        let recipients = manager.ekubo_recipients(&[withdrawal, ekubo_event(5), deposit]);
+        assert_eq!(recipients.len(), 1);
+
+        let (t0, t1, params) =
+            SwapManager::<MockStorage>::ekubo_pool_key(&ekubo_event(5).data).unwrap();
+        let mut pool = pool();
+        pool.exchange = LiquidityType::Ekubo;
+        pool.pool_address = to_hex_str(&ekubo_pool_address(t0, t1, &params));
+
+        let swap =
+            SwapManager::<MockStorage>::get_ekubo_swap(&ekubo_event(5), &pool, &recipients, 100)
+                .unwrap();
+        assert_eq!(swap.trader, to_hex_str(&felt(0xb)));
+
+        // Recipients of other transactions are ignored.
+        let mut other = ekubo_event(5);
+        other.transaction_hash = felt(0x8);
+        let swap =
+            SwapManager::<MockStorage>::get_ekubo_swap(&other, &pool, &recipients, 100).unwrap();
+        assert_eq!(swap.trader, "");
+    }
+
+    #[tokio::test]
+    async fn test_pools_loaded_again_after_storage_error() {
+        let mut storage = MockStorage::default();
+        let mut seq = mockall::Sequence::new();
+        storage
+            .expect_get_pools()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| {
+                Box::pin(futures::future::ready(Err(StorageError::DatabaseError(
+                    "unavailable".to_string(),
+                ))))
+            });
+        storage
+            .expect_get_pools()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Box::pin(futures::future::ready(Ok(vec![pool()]))));
+        storage
+            .expect_get_memecoins()
+            .times(2)
+            .returning(|| Box::pin(futures::future::ready(Ok(vec![]))));
+
+        let manager = SwapManager::new(Arc::new(storage), EKUBO_CORE_ADDRESS, vec![]);
+        let swap = event(vec![JEDISWAP_SWAP_SELECTOR], vec![]);
+
+        assert!(!manager.is_swap_event(&swap).await);
+        assert!(manager.is_swap_event(&swap).await);
+        // Loaded once, not queried anymore.
+        assert!(manager.is_swap_event(&swap).await);
+    }
+
+    #[tokio::test]
+    async fn test_ekubo_pools_registered_by_key() {
+        let storage = Arc::new(MemoryStorage::new());
+        let manager = SwapManager::new(Arc::clone(&storage), EKUBO_CORE_ADDRESS, vec![]);
+
+        let launch = MemecoinLaunchedEvent {
+            memecoin_address: to_hex_str(&felt(0x10)),
+            quote_token: to_hex_str(&felt(0x20)),
+            liquidity_type: LiquidityType::Ekubo,
+            locked_liquidity: Some(to_hex_str(&felt(42))),
+            team_allocation: None,
+            transaction_hash: to_hex_str(&felt(0x7)),
+            block_number: 1,
+            block_timestamp: 100,
+        };
+        assert_eq!(
+            manager
+                .register_pool_from_launch(&launch, 100)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Pools of the same tokens with different fees are distinct.
+        for fee in [5, 30] {
+            let e = ekubo_event(fee);
+            assert!(manager.is_swap_event(&e).await);
+            manager
+                .format_and_register_event(&e, &EkuboRecipients::new(), 110)
+                .await
+                .unwrap();
+        }
+
+        let pools = storage.get_pools().await.unwrap();
+        assert_eq!(pools.len(), 2);
+        assert_ne!(pools[0].pool_address, pools[1].pool_address);
+        assert!(pools
+            .iter()
+            .all(|p| p.memecoin_address == launch.memecoin_address));
+
+        // Other tokens are ignored.
+        let mut other = ekubo_event(5);
+        other.data[2] = felt(0x30);
+        assert!(!manager.is_swap_event(&other).await);
+    }
+}
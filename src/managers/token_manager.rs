@@ -30,7 +30,7 @@ impl<S: Storage, C: StarknetClient> TokenManager<S, C> {
         event: &TokenEvent,
         block_timestamp: u64,
         block_number: u64,
-    ) -> Result<()> {
+    ) -> Result<TokenInfo> {
         let mut token = TokenInfo {
             contract_address: event.contract_address.clone(),
             token_id: event.token_id.clone(),
@@ -67,7 +67,7 @@ impl<S: Storage, C: StarknetClient> TokenManager<S, C> {
                 .await?;
         }
 
-        Ok(())
+        Ok(token)
     }
 
     /// Retrieves the token owner for the last block.
@@ -0,0 +1,275 @@
+//! Prometheus metrics of the indexer, recorded by Pontos and by the
+//! `client::MeteredClient` for the RPC requests.
+//!
+//! The metrics are rendered in the Prometheus text format, and served on
+//! `/metrics`, if the `metrics` feature is enabled. Otherwise nothing
+//! is recorded.
+#[cfg(not(feature = "metrics"))]
+mod noop;
+#[cfg(not(feature = "metrics"))]
+pub use noop::Metrics;
+
+#[cfg(feature = "metrics")]
+use crate::managers::ContractCacheStats;
+#[cfg(feature = "metrics")]
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+#[cfg(feature = "metrics")]
+use std::time::Duration;
+#[cfg(feature = "metrics")]
+use tracing::error;
+
+#[cfg(feature = "metrics")]
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+#[cfg(feature = "metrics")]
+use std::{net::SocketAddr, sync::Arc};
+#[cfg(feature = "metrics")]
+use tracing::info;
+
+/// Metrics of one indexer, registered in their own registry.
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    blocks_indexed: IntCounter,
+    skipped_blocks: IntCounter,
+    last_indexed_block: IntGauge,
+    chain_head: IntGauge,
+    lag: IntGauge,
+    events: IntCounterVec,
+    rpc_requests: IntCounterVec,
+    rpc_duration: HistogramVec,
+    storage_write_duration: HistogramVec,
+    contract_cache: IntGaugeVec,
+}
+
+#[cfg(feature = "metrics")]
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let blocks_indexed =
+            IntCounter::new("pontos_blocks_indexed_total", "Blocks indexed").expect("Valid metric");
+        let skipped_blocks = IntCounter::new(
+            "pontos_skipped_blocks_total",
+            "Blocks skipped as their timestamp is not available",
+        )
+        .expect("Valid metric");
+        let last_indexed_block =
+            IntGauge::new("pontos_last_indexed_block", "Last indexed block").expect("Valid metric");
+        let chain_head =
+            IntGauge::new("pontos_chain_head", "Latest block of the chain").expect("Valid metric");
+        let lag = IntGauge::new(
+            "pontos_lag_blocks",
+            "Blocks between the chain head and the last indexed block",
+        )
+        .expect("Valid metric");
+        let events = IntCounterVec::new(
+            Opts::new("pontos_events_total", "Events registered, by type"),
+            &["type"],
+        )
+        .expect("Valid metric");
+        let rpc_requests = IntCounterVec::new(
+            Opts::new(
+                "pontos_rpc_requests_total",
+                "RPC requests, by method and status",
+            ),
+            &["method", "status"],
+        )
+        .expect("Valid metric");
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("pontos_rpc_duration_seconds", "RPC requests latency"),
+            &["method"],
+        )
+        .expect("Valid metric");
+        let storage_write_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "pontos_storage_write_duration_seconds",
+                "Storage writes latency, by operation",
+            ),
+            &["operation"],
+        )
+        .expect("Valid metric");
+        let contract_cache = IntGaugeVec::new(
+            Opts::new(
+                "pontos_contract_cache",
+                "Hits, misses, evictions and size of the contract cache",
+            ),
+            &["stat"],
+        )
+        .expect("Valid metric");
+
+        registry
+            .register(Box::new(blocks_indexed.clone()))
+            .and_then(|_| registry.register(Box::new(skipped_blocks.clone())))
+            .and_then(|_| registry.register(Box::new(last_indexed_block.clone())))
+            .and_then(|_| registry.register(Box::new(chain_head.clone())))
+            .and_then(|_| registry.register(Box::new(lag.clone())))
+            .and_then(|_| registry.register(Box::new(events.clone())))
+            .and_then(|_| registry.register(Box::new(rpc_requests.clone())))
+            .and_then(|_| registry.register(Box::new(rpc_duration.clone())))
+            .and_then(|_| registry.register(Box::new(storage_write_duration.clone())))
+            .and_then(|_| registry.register(Box::new(contract_cache.clone())))
+            .expect("Metrics registered once");
+
+        Metrics {
+            registry,
+            blocks_indexed,
+            skipped_blocks,
+            last_indexed_block,
+            chain_head,
+            lag,
+            events,
+            rpc_requests,
+            rpc_duration,
+            storage_write_duration,
+            contract_cache,
+        }
+    }
+
+    /// Registry of the metrics, to register other collectors.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn block_indexed(&self, block_number: u64) {
+        self.blocks_indexed.inc();
+        self.last_indexed_block.set(block_number as i64);
+        self.update_lag();
+    }
+
+    pub fn block_skipped(&self) {
+        self.skipped_blocks.inc();
+    }
+
+    pub fn set_chain_head(&self, block_number: u64) {
+        self.chain_head.set(block_number as i64);
+        self.update_lag();
+    }
+
+    pub fn event(&self, event_type: &str) {
+        self.events.with_label_values(&[event_type]).inc();
+    }
+
+    pub fn rpc_request(&self, method: &str, duration: Duration, success: bool) {
+        let status = if success { "ok" } else { "error" };
+        self.rpc_requests.with_label_values(&[method, status]).inc();
+        self.rpc_duration
+            .with_label_values(&[method])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn storage_write(&self, operation: &str, duration: Duration) {
+        self.storage_write_duration
+            .with_label_values(&[operation])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn contract_cache(&self, stats: &ContractCacheStats) {
+        for (stat, value) in [
+            ("hits", stats.hits as i64),
+            ("misses", stats.misses as i64),
+            ("evictions", stats.evictions as i64),
+            ("size", stats.size as i64),
+        ] {
+            self.contract_cache.with_label_values(&[stat]).set(value);
+        }
+    }
+
+    /// Returns the last indexed block, `None` if no block was indexed yet.
+    pub fn last_indexed_block(&self) -> Option<u64> {
+        Some(self.last_indexed_block.get() as u64).filter(|_| self.blocks_indexed.get() > 0)
+    }
+
+    /// Returns the number of blocks between the chain head and
+    /// the last indexed block, as of the last known chain head.
+    pub fn lag(&self) -> u64 {
+        self.lag.get() as u64
+    }
+
+    /// Renders the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buffer = vec![];
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            error!("Can't encode metrics: {:?}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    fn update_lag(&self) {
+        let lag = (self.chain_head.get() - self.last_indexed_block.get()).max(0);
+        self.lag.set(lag);
+    }
+}
+
+/// Returns the router serving the metrics on `/metrics`.
+#[cfg(feature = "metrics")]
+pub fn router(metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/metrics", get(render))
+        .with_state(metrics)
+}
+
+#[cfg(feature = "metrics")]
+async fn render(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics.render(),
+    )
+}
+
+/// Serves the metrics on the given address, until the server fails.
+#[cfg(feature = "metrics")]
+pub async fn serve(metrics: Arc<Metrics>, addr: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Metrics listening on {}", addr);
+    axum::serve(listener, router(metrics)).await
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lag() {
+        let metrics = Metrics::new();
+        assert_eq!(metrics.last_indexed_block(), None);
+
+        metrics.set_chain_head(10);
+        assert_eq!(metrics.lag(), 10);
+
+        metrics.block_indexed(7);
+        assert_eq!(metrics.last_indexed_block(), Some(7));
+        assert_eq!(metrics.lag(), 3);
+
+        // The chain head may be known after a block indexed beyond it.
+        metrics.block_indexed(12);
+        assert_eq!(metrics.lag(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.event("MINT");
+        metrics.event("MINT");
+        metrics.rpc_request("block_time", Duration::from_millis(10), false);
+        metrics.block_skipped();
+
+        let text = metrics.render();
+        assert!(text.contains("pontos_events_total{type=\"MINT\"} 2"));
+        assert!(
+            text.contains("pontos_rpc_requests_total{method=\"block_time\",status=\"error\"} 1")
+        );
+        assert!(text.contains("pontos_rpc_duration_seconds_count{method=\"block_time\"} 1"));
+        assert!(text.contains("pontos_skipped_blocks_total 1"));
+    }
+}
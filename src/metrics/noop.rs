@@ -0,0 +1,40 @@
+//! Metrics recording nothing, when the `metrics` feature is disabled.
+use crate::managers::ContractCacheStats;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct Metrics;
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics
+    }
+
+    pub fn block_indexed(&self, _block_number: u64) {}
+
+    pub fn block_skipped(&self) {}
+
+    pub fn set_chain_head(&self, _block_number: u64) {}
+
+    pub fn event(&self, _event_type: &str) {}
+
+    pub fn rpc_request(&self, _method: &str, _duration: Duration, _success: bool) {}
+
+    pub fn storage_write(&self, _operation: &str, _duration: Duration) {}
+
+    pub fn contract_cache(&self, _stats: &ContractCacheStats) {}
+
+    /// Always `None`, as no block is recorded.
+    pub fn last_indexed_block(&self) -> Option<u64> {
+        None
+    }
+
+    pub fn lag(&self) -> u64 {
+        0
+    }
+
+    /// Always empty.
+    pub fn render(&self) -> String {
+        String::new()
+    }
+}
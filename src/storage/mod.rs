@@ -7,13 +7,22 @@ pub mod sqlx;
 pub use sqlx::DefaultSqlxStorage;
 
 use crate::storage::types::{
-    BlockInfo, ContractInfo, ContractType, StorageError, TokenEvent, TokenInfo, TokenMintInfo, MemecoinCreatedEvent
+    BlockInfo, Candle, CandleInterval, ClassInfo, ContractInfo, ContractType, DeveloperActivity,
+    DeveloperWallet, EventFilter, FailedEvent, FailedEventStatus, LiquidityLock, MarketMetrics,
+    MemecoinCreatedEvent, MemecoinFilter, MemecoinInfo, MemecoinLaunchedEvent, Pagination, Pool,
+    PortfolioSnapshot, SafetyReport, SniperReport, StorageError, SwapEvent, TokenEvent,
+    TokenFilter, TokenInfo, TokenMintInfo, TokenTransfer, TrendingScore,
 };
 use async_trait::async_trait;
 
 #[cfg(test)]
 use mockall::automock;
 
+/// Storage of the indexed data.
+///
+/// Only the tokens, events, contracts and blocks must be stored. The
+/// other methods default to storing nothing and to empty or `NotFound`
+/// results, for storages not supporting the memecoin analytics.
 #[async_trait]
 #[cfg_attr(test, automock)]
 pub trait Storage {
@@ -36,6 +45,32 @@ pub trait Storage {
         block_timestamp: u64,
     ) -> Result<(), StorageError>;
 
+    async fn get_token(
+        &self,
+        contract_address: &str,
+        _token_id_hex: &str,
+    ) -> Result<TokenInfo, StorageError> {
+        Err(StorageError::NotFound(contract_address.to_string()))
+    }
+
+    /// Returns the matching tokens, ordered by contract and token id.
+    async fn get_tokens(
+        &self,
+        _filter: &TokenFilter,
+        _pagination: &Pagination,
+    ) -> Result<Vec<TokenInfo>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the matching events, the most recent first.
+    async fn get_events(
+        &self,
+        _filter: &EventFilter,
+        _pagination: &Pagination,
+    ) -> Result<Vec<TokenEvent>, StorageError> {
+        Ok(vec![])
+    }
+
     async fn get_contract_type(&self, contract_address: &str)
         -> Result<ContractType, StorageError>;
 
@@ -45,11 +80,338 @@ pub trait Storage {
         block_timestamp: u64,
     ) -> Result<(), StorageError>;
 
+    async fn get_contract_info(
+        &self,
+        contract_address: &str,
+    ) -> Result<ContractInfo, StorageError> {
+        Err(StorageError::NotFound(contract_address.to_string()))
+    }
+
+    /// Returns the contracts, the most recently registered first.
+    async fn get_contract_infos(
+        &self,
+        _pagination: &Pagination,
+    ) -> Result<Vec<ContractInfo>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn get_class_info(&self, class_hash: &str) -> Result<ClassInfo, StorageError> {
+        Err(StorageError::NotFound(class_hash.to_string()))
+    }
+
+    async fn register_class_info(
+        &self,
+        _info: &ClassInfo,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
     async fn register_memecoin_created_event(
         &self,
         event: &MemecoinCreatedEvent,
-        block_timestamp: u64) -> Result<(), StorageError>;
+        block_timestamp: u64,
+    ) -> Result<(), StorageError>;
+
+    async fn register_memecoin_launched_event(
+        &self,
+        _event: &MemecoinLaunchedEvent,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_memecoin(&self, memecoin_address: &str) -> Result<MemecoinInfo, StorageError> {
+        Err(StorageError::NotFound(memecoin_address.to_string()))
+    }
+
+    /// Returns the memecoins, launched or not, from the most recent.
+    async fn get_memecoins(&self) -> Result<Vec<MemecoinInfo>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the matching memecoins, from the most recent.
+    async fn get_filtered_memecoins(
+        &self,
+        _filter: &MemecoinFilter,
+        _pagination: &Pagination,
+    ) -> Result<Vec<MemecoinInfo>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn register_token_transfer(
+        &self,
+        _transfer: &TokenTransfer,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the transfers of the token, ordered by block, up to
+    /// `to_block` included if given.
+    async fn get_token_transfers(
+        &self,
+        _token_address: &str,
+        _to_block: Option<u64>,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn register_liquidity_lock(
+        &self,
+        _lock: &LiquidityLock,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_liquidity_locks(
+        &self,
+        _token_address: &str,
+    ) -> Result<Vec<LiquidityLock>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Replaces the safety report of the memecoin, if any.
+    async fn set_safety_report(&self, _report: &SafetyReport) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_safety_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SafetyReport, StorageError> {
+        Err(StorageError::NotFound(memecoin_address.to_string()))
+    }
+
+    async fn register_pool(&self, _pool: &Pool, _block_timestamp: u64) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_pools(&self) -> Result<Vec<Pool>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn register_swap(
+        &self,
+        _swap: &SwapEvent,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the swaps of the pool between the two timestamps included,
+    /// ordered by block.
+    async fn get_swaps(
+        &self,
+        _pool_address: &str,
+        _from_timestamp: u64,
+        _to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the most recent swap of the pool up to the block included.
+    async fn get_last_swap(
+        &self,
+        pool_address: &str,
+        _to_block_number: u64,
+    ) -> Result<SwapEvent, StorageError> {
+        Err(StorageError::NotFound(pool_address.to_string()))
+    }
+
+    /// Returns the swaps registered for the block.
+    async fn get_block_swaps(&self, _block_timestamp: u64) -> Result<Vec<SwapEvent>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn get_candle(
+        &self,
+        memecoin_address: &str,
+        _interval: CandleInterval,
+        _open_time: u64,
+    ) -> Result<Candle, StorageError> {
+        Err(StorageError::NotFound(memecoin_address.to_string()))
+    }
+
+    /// Replaces the candle, if any.
+    async fn set_candle(&self, _candle: &Candle) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn delete_candle(
+        &self,
+        _memecoin_address: &str,
+        _interval: CandleInterval,
+        _open_time: u64,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the candles opened between the two times included,
+    /// ordered by open time.
+    async fn get_candles(
+        &self,
+        _memecoin_address: &str,
+        _interval: CandleInterval,
+        _from_time: u64,
+        _to_time: u64,
+    ) -> Result<Vec<Candle>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Replaces the score of the memecoin for the window, if any.
+    async fn set_trending_score(&self, _score: &TrendingScore) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the scores of the window, the highest first.
+    async fn get_trending_scores(
+        &self,
+        _window: u64,
+        _limit: u64,
+    ) -> Result<Vec<TrendingScore>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the transfers sent or received by the wallet, up to the
+    /// timestamp included, ordered by block.
+    async fn get_wallet_transfers(
+        &self,
+        _wallet_address: &str,
+        _to_timestamp: u64,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the swaps of the wallet, up to the timestamp included,
+    /// ordered by block.
+    async fn get_wallet_swaps(
+        &self,
+        _wallet_address: &str,
+        _to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Returns the wallets having sent or received a memecoin, or swapped
+    /// on a memecoin pool, between the two timestamps included.
+    async fn get_active_wallets(
+        &self,
+        _from_timestamp: u64,
+        _to_timestamp: u64,
+    ) -> Result<Vec<String>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Replaces the snapshot of the wallet for the day, if any.
+    async fn set_portfolio_snapshot(
+        &self,
+        _snapshot: &PortfolioSnapshot,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the snapshots of the wallet between the two days included,
+    /// ordered by day.
+    async fn get_portfolio_snapshots(
+        &self,
+        _wallet_address: &str,
+        _from_day: u64,
+        _to_day: u64,
+    ) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Registers the transfer as the first funding of its recipient.
+    /// Fails with `AlreadyExists` if the recipient was already funded.
+    async fn register_wallet_funding(&self, _transfer: &TokenTransfer) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_wallet_funding(
+        &self,
+        wallet_address: &str,
+    ) -> Result<TokenTransfer, StorageError> {
+        Err(StorageError::NotFound(wallet_address.to_string()))
+    }
 
+    /// Replaces the sniper report of the memecoin, if any.
+    async fn set_sniper_report(&self, _report: &SniperReport) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_sniper_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SniperReport, StorageError> {
+        Err(StorageError::NotFound(memecoin_address.to_string()))
+    }
+
+    /// Fails with `AlreadyExists` if the wallet already has this role.
+    async fn register_developer_wallet(
+        &self,
+        _wallet: &DeveloperWallet,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_developer_wallets(
+        &self,
+        _memecoin_address: &str,
+    ) -> Result<Vec<DeveloperWallet>, StorageError> {
+        Ok(vec![])
+    }
+
+    async fn register_developer_activity(
+        &self,
+        _activity: &DeveloperActivity,
+    ) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Returns the activity of the developer wallets of the memecoin,
+    /// ordered by block.
+    async fn get_developer_activities(
+        &self,
+        _memecoin_address: &str,
+    ) -> Result<Vec<DeveloperActivity>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Replaces the market metrics of the memecoin, if any.
+    async fn set_market_metrics(&self, _metrics: &MarketMetrics) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn get_market_metrics(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<MarketMetrics, StorageError> {
+        Err(StorageError::NotFound(memecoin_address.to_string()))
+    }
+
+    /// Returns the market metrics of the memecoins, the highest
+    /// market cap first.
+    async fn get_top_market_metrics(
+        &self,
+        _limit: u64,
+    ) -> Result<Vec<MarketMetrics>, StorageError> {
+        Ok(vec![])
+    }
+
+    /// Replaces the failed event, if any. Required, as the failed
+    /// events would otherwise be lost instead of being replayed.
+    async fn set_failed_event(&self, event: &FailedEvent) -> Result<(), StorageError>;
+
+    async fn get_failed_event(&self, failure_id: &str) -> Result<FailedEvent, StorageError>;
+
+    /// Returns the failed events with the status, or all of them if `None`,
+    /// ordered by block.
+    async fn get_failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> Result<Vec<FailedEvent>, StorageError>;
 
     /// A block info is only set if the block has a number and a timestamp.
     async fn set_block_info(
@@ -61,8 +423,22 @@ pub trait Storage {
 
     async fn get_block_info(&self, block_number: u64) -> Result<BlockInfo, StorageError>;
 
+    /// Returns the block with its timestamp.
+    async fn get_block(&self, block_number: u64) -> Result<(u64, BlockInfo), StorageError> {
+        Err(StorageError::NotFound(block_number.to_string()))
+    }
+
+    /// Returns the blocks with their timestamp, the most recent first.
+    async fn get_blocks(
+        &self,
+        _pagination: &Pagination,
+    ) -> Result<Vec<(u64, BlockInfo)>, StorageError> {
+        Ok(vec![])
+    }
+
     /// The block timestamps is always present. But the number can be missing
     /// for the pending block support.
+    /// The failed events of the block are removed, unless permanently failed.
     async fn clean_block(
         &self,
         block_timestamp: u64,
@@ -6,7 +6,7 @@
 use async_trait::async_trait;
 
 use log::trace;
-use sqlx::{any::AnyPoolOptions, AnyPool, Error as SqlxError, FromRow};
+use sqlx::{any::AnyPoolOptions, AnyPool, Error as SqlxError, FromRow, Row};
 use std::str::FromStr;
 
 use super::types::*;
@@ -109,6 +109,176 @@ impl DefaultSqlxStorage {
         }
     }
 
+    async fn get_memecoin_launch(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Option<MemecoinLaunchedEvent>, StorageError> {
+        let q = "SELECT * FROM memecoin_launch WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let d = MemecoinLaunchData::from_row(&rows[0])?;
+        Ok(Some(MemecoinLaunchedEvent {
+            memecoin_address: d.memecoin_address,
+            quote_token: d.quote_token,
+            liquidity_type: LiquidityType::from_str(&d.liquidity_type).unwrap(),
+            locked_liquidity: Some(d.locked_liquidity).filter(|l| !l.is_empty()),
+            team_allocation: Some(d.team_allocation).filter(|a| !a.is_empty()),
+            transaction_hash: d.transaction_hash,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        }))
+    }
+
+    async fn to_memecoin_info(&self, d: MemecoinData) -> Result<MemecoinInfo, StorageError> {
+        let launch = self.get_memecoin_launch(&d.memecoin_address).await?;
+
+        Ok(MemecoinInfo {
+            memecoin_address: d.memecoin_address,
+            owner: d.owner,
+            name: d.name,
+            symbol: d.symbol,
+            initial_supply: d.initial_supply,
+            created_at: d.block_timestamp as u64,
+            launch,
+        })
+    }
+
+    fn to_candle(d: CandleData) -> Result<Candle, StorageError> {
+        let price = |p: &str| {
+            p.parse::<f64>()
+                .map_err(|_| StorageError::DatabaseError(format!("Invalid candle price: {p}")))
+        };
+
+        Ok(Candle {
+            memecoin_address: d.memecoin_address,
+            interval: CandleInterval::from_str(&d.candle_interval).map_err(|_| {
+                StorageError::DatabaseError(format!(
+                    "Invalid candle interval: {}",
+                    d.candle_interval
+                ))
+            })?,
+            open_time: d.open_time as u64,
+            open: price(&d.open)?,
+            high: price(&d.high)?,
+            low: price(&d.low)?,
+            close: price(&d.close)?,
+            volume_token: d.volume_token,
+            volume_quote: d.volume_quote,
+            trades: d.trades as u64,
+            close_usd: d.close_usd.parse::<f64>().unwrap_or_default(),
+            volume_usd: d.volume_usd.parse::<f64>().unwrap_or_default(),
+            first_trade_at: d.first_trade_at as u64,
+            last_trade_at: d.last_trade_at as u64,
+        })
+    }
+
+    fn to_token(d: TokenData) -> TokenInfo {
+        TokenInfo {
+            contract_address: d.contract_address,
+            token_id: d.token_id,
+            token_id_hex: d.token_id_hex,
+            owner: d.owner,
+        }
+    }
+
+    /// Returns the block timestamp with the block info.
+    fn to_block(d: BlockData) -> (u64, BlockInfo) {
+        (
+            d.timestamp as u64,
+            BlockInfo {
+                indexer_version: d.indexer_version,
+                indexer_identifier: d.indexer_identifier,
+                status: BlockIndexingStatus::from_str(&d.status)
+                    .unwrap_or(BlockIndexingStatus::None),
+                block_number: d.number as u64,
+            },
+        )
+    }
+
+    /// Returns the `WHERE` clause of the conditions, empty if there is none.
+    /// Adds the condition of the items after the cursor, if any, in a list
+    /// ordered by decreasing timestamp then by id: the condition binds the
+    /// timestamp twice and then the id.
+    fn push_cursor(
+        conditions: &mut Vec<&'static str>,
+        values: &mut Vec<String>,
+        pagination: &Pagination,
+        condition: &'static str,
+    ) {
+        if let Some(c) = &pagination.after {
+            conditions.push(condition);
+            values.push(c.timestamp.to_string());
+            values.push(c.timestamp.to_string());
+            values.push(c.id.clone());
+        }
+    }
+
+    fn where_clause(conditions: &[&str]) -> String {
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        }
+    }
+
+    fn to_market_metrics(d: MarketMetricsData) -> MarketMetrics {
+        let usd = |v: &str| v.parse::<f64>().unwrap_or_default();
+
+        MarketMetrics {
+            price_usd: usd(&d.price_usd),
+            market_cap_usd: usd(&d.market_cap_usd),
+            fdv_usd: usd(&d.fdv_usd),
+            liquidity_usd: usd(&d.liquidity_usd),
+            memecoin_address: d.memecoin_address,
+            total_supply: d.total_supply,
+            circulating_supply: d.circulating_supply,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        }
+    }
+
+    fn to_failed_event(d: FailedEventData) -> Result<FailedEvent, StorageError> {
+        Ok(FailedEvent {
+            kind: EventFailureKind::from_str(&d.kind).map_err(|_| {
+                StorageError::DatabaseError(format!("Invalid failure kind {}", d.kind))
+            })?,
+            status: FailedEventStatus::from_str(&d.status).map_err(|_| {
+                StorageError::DatabaseError(format!("Invalid failure status {}", d.status))
+            })?,
+            failure_id: d.failure_id,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+            transaction_hash: d.transaction_hash,
+            contract_address: d.contract_address,
+            error: serde_json::from_str(&d.error)
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?,
+            attempts: d.attempts as u32,
+        })
+    }
+
+    fn to_swap(d: SwapData) -> SwapEvent {
+        SwapEvent {
+            event_id: d.event_id,
+            pool_address: d.pool_address,
+            token_in: d.token_in,
+            token_out: d.token_out,
+            amount_in: d.amount_in,
+            amount_out: d.amount_out,
+            trader: d.trader,
+            transaction_hash: d.transaction_hash,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        }
+    }
+
     async fn get_block_by_timestamp(&self, ts: u64) -> Result<Option<BlockData>, StorageError> {
         let q = "SELECT * FROM block WHERE block_timestamp = ?";
 
@@ -203,7 +373,7 @@ impl Storage for DefaultSqlxStorage {
             )));
         }
 
-        let q = "INSERT INTO event (block_timestamp, contract_address, from_address, to_address, transaction_hash, token_id, token_id_hex, contract_type, event_type, event_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+        let q = "INSERT INTO event (block_timestamp, from_address, to_address, contract_address, transaction_hash, token_id, token_id_hex, contract_type, event_type, event_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
 
         let _r = sqlx::query(q)
             .bind(event.timestamp.to_string())
@@ -222,6 +392,151 @@ impl Storage for DefaultSqlxStorage {
         Ok(())
     }
 
+    async fn get_token(
+        &self,
+        contract_address: &str,
+        token_id_hex: &str,
+    ) -> Result<TokenInfo, StorageError> {
+        trace!("Getting token {} of {}", token_id_hex, contract_address);
+
+        match self.get_token_by_id(contract_address, token_id_hex).await? {
+            Some(t) => Ok(Self::to_token(t)),
+            None => Err(StorageError::NotFound(format!("token id = {token_id_hex}"))),
+        }
+    }
+
+    async fn get_tokens(
+        &self,
+        filter: &TokenFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<TokenInfo>, StorageError> {
+        trace!("Getting tokens {:?} {:?}", filter, pagination);
+
+        let mut conditions = vec![];
+        let mut values = vec![];
+        if let Some(a) = &filter.contract_address {
+            conditions.push("contract_address = ?");
+            values.push(a.clone());
+        }
+        if let Some(o) = &filter.owner {
+            conditions.push("owner = ?");
+            values.push(o.clone());
+        }
+        if let Some((contract_address, token_id_hex)) =
+            pagination.after.as_ref().and_then(|c| c.token_key())
+        {
+            conditions
+                .push("(contract_address > ? OR (contract_address = ? AND token_id_hex > ?))");
+            values.push(contract_address.to_string());
+            values.push(contract_address.to_string());
+            values.push(token_id_hex.to_string());
+        }
+
+        let q = format!(
+            "SELECT * FROM token {} ORDER BY contract_address, token_id_hex LIMIT ? OFFSET ?",
+            Self::where_clause(&conditions)
+        );
+
+        let mut query = sqlx::query(&q);
+        for v in values {
+            query = query.bind(v);
+        }
+
+        let rows = query
+            .bind(pagination.limit.to_string())
+            .bind(pagination.offset.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut tokens = vec![];
+        for r in rows {
+            tokens.push(Self::to_token(TokenData::from_row(&r)?));
+        }
+
+        Ok(tokens)
+    }
+
+    async fn get_events(
+        &self,
+        filter: &EventFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<TokenEvent>, StorageError> {
+        trace!("Getting events {:?} {:?}", filter, pagination);
+
+        let mut conditions = vec![];
+        let mut values = vec![];
+        if let Some(a) = &filter.contract_address {
+            conditions.push("contract_address = ?");
+            values.push(a.clone());
+        }
+        if let Some(t) = &filter.token_id_hex {
+            conditions.push("token_id_hex = ?");
+            values.push(t.clone());
+        }
+        if let Some(a) = &filter.address {
+            conditions.push("(from_address = ? OR to_address = ?)");
+            values.push(a.clone());
+            values.push(a.clone());
+        }
+        if let Some(t) = &filter.event_type {
+            conditions.push("event_type = ?");
+            values.push(t.to_string());
+        }
+        if let Some(t) = filter.from_timestamp {
+            conditions.push("block_timestamp >= ?");
+            values.push(t.to_string());
+        }
+        if let Some(t) = filter.to_timestamp {
+            conditions.push("block_timestamp <= ?");
+            values.push(t.to_string());
+        }
+        Self::push_cursor(
+            &mut conditions,
+            &mut values,
+            pagination,
+            "(block_timestamp < ? OR (block_timestamp = ? AND event_id > ?))",
+        );
+
+        let q = format!(
+            "SELECT * FROM event {} ORDER BY block_timestamp DESC, event_id LIMIT ? OFFSET ?",
+            Self::where_clause(&conditions)
+        );
+
+        let mut query = sqlx::query(&q);
+        for v in values {
+            query = query.bind(v);
+        }
+
+        let rows = query
+            .bind(pagination.limit.to_string())
+            .bind(pagination.offset.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut events = vec![];
+        for r in rows {
+            let d = EventData::from_row(&r)?;
+            events.push(TokenEvent {
+                timestamp: d.block_timestamp as u64,
+                from_address: d.from_address,
+                to_address: d.to_address,
+                contract_address: d.contract_address,
+                transaction_hash: d.transaction_hash,
+                token_id: d.token_id,
+                token_id_hex: d.token_id_hex,
+                contract_type: d.contract_type,
+                event_type: EventType::from_str(&d.event_type).map_err(|_| {
+                    StorageError::DatabaseError(format!("Invalid event type {}", d.event_type))
+                })?,
+                event_id: d.event_id,
+                block_number: None,
+                updated_at: None,
+            });
+        }
+
+        Ok(events)
+    }
+
     async fn get_contract_type(
         &self,
         contract_address: &str,
@@ -255,11 +570,12 @@ impl Storage for DefaultSqlxStorage {
             )));
         }
 
-        let q = "INSERT INTO contract (contract_address, contract_type, block_timestamp) VALUES (?, ?, ?)";
+        let q = "INSERT INTO contract (contract_address, contract_type, identification_method, block_timestamp) VALUES (?, ?, ?, ?)";
 
         let _r = sqlx::query(q)
             .bind(info.contract_address.clone())
             .bind(info.contract_type.to_string())
+            .bind(info.identification_method.clone().unwrap_or_default())
             .bind(block_timestamp.to_string())
             .execute(&self.pool)
             .await?;
@@ -267,102 +583,1580 @@ impl Storage for DefaultSqlxStorage {
         Ok(())
     }
 
-    async fn set_block_info(
+    async fn get_contract_info(
         &self,
-        block_number: u64,
-        block_timestamp: u64,
-        info: BlockInfo,
-    ) -> Result<(), StorageError> {
-        trace!("Setting block info {:?} for block #{}", info, block_number);
+        contract_address: &str,
+    ) -> Result<ContractInfo, StorageError> {
+        trace!("Getting contract info of {}", contract_address);
 
-        let _r = if (self.get_block_by_timestamp(block_timestamp).await?).is_some() {
-            let q = "UPDATE block SET block_timestamp = ?, block_number = ?, status = ?, indexer_version = ?, indexer_identifier = ? WHERE block_timestamp = ?";
-            sqlx::query(q)
-                .bind(block_timestamp.to_string())
-                .bind(block_number.to_string())
-                .bind(info.status.to_string())
-                .bind(info.indexer_version.clone())
-                .bind(info.indexer_identifier.clone())
-                .bind(block_timestamp.to_string())
-                .execute(&self.pool)
-                .await?
-        } else {
-            let q = "INSERT INTO block (block_timestamp, block_number, status, indexer_version, indexer_identifier) VALUES (?, ?, ?, ?, ?)";
+        match self.get_contract_by_address(contract_address).await? {
+            Some(c) => Ok(ContractInfo {
+                contract_address: c.contract_address,
+                contract_type: c.contract_type,
+                identification_method: Some(c.identification_method).filter(|m| !m.is_empty()),
+                block_timestamp: c.block_timestamp as u64,
+                ..Default::default()
+            }),
+            None => Err(StorageError::NotFound(format!(
+                "contract_address: {contract_address}"
+            ))),
+        }
+    }
 
-            sqlx::query(q)
-                .bind(block_timestamp.to_string())
-                .bind(block_number.to_string())
-                .bind(info.status.to_string())
-                .bind(info.indexer_version.clone())
-                .bind(info.indexer_identifier.clone())
-                .execute(&self.pool)
-                .await?
-        };
+    async fn get_contract_infos(
+        &self,
+        pagination: &Pagination,
+    ) -> Result<Vec<ContractInfo>, StorageError> {
+        trace!("Getting contracts {:?}", pagination);
 
-        Ok(())
+        let mut conditions = vec![];
+        let mut values = vec![];
+        Self::push_cursor(
+            &mut conditions,
+            &mut values,
+            pagination,
+            "(block_timestamp < ? OR (block_timestamp = ? AND contract_address > ?))",
+        );
+
+        let q = format!(
+            "SELECT * FROM contract {} ORDER BY block_timestamp DESC, contract_address LIMIT ? OFFSET ?",
+            Self::where_clause(&conditions)
+        );
+
+        let mut query = sqlx::query(&q);
+        for v in values {
+            query = query.bind(v);
+        }
+
+        let rows = query
+            .bind(pagination.limit.to_string())
+            .bind(pagination.offset.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut infos = vec![];
+        for r in rows {
+            let c = ContractData::from_row(&r)?;
+            infos.push(ContractInfo {
+                contract_address: c.contract_address,
+                contract_type: c.contract_type,
+                identification_method: Some(c.identification_method).filter(|m| !m.is_empty()),
+                block_timestamp: c.block_timestamp as u64,
+                ..Default::default()
+            });
+        }
+
+        Ok(infos)
     }
 
-    async fn get_block_info(&self, block_number: u64) -> Result<BlockInfo, StorageError> {
-        trace!("Getting block info for block #{}", block_number);
+    async fn get_class_info(&self, class_hash: &str) -> Result<ClassInfo, StorageError> {
+        trace!("Getting class info for class {}", class_hash);
 
-        let q = "SELECT * FROM block WHERE block_number = ?";
+        let q = "SELECT * FROM class WHERE class_hash = ?";
 
-        match sqlx::query(q)
-            .bind(block_number.to_string())
+        let rows = sqlx::query(q)
+            .bind(class_hash.to_string())
             .fetch_all(&self.pool)
-            .await
-        {
-            Ok(rows) => {
-                if rows.is_empty() {
-                    Err(StorageError::NotFound(format!(
-                        "block number {block_number}"
-                    )))
-                } else {
-                    let d = BlockData::from_row(&rows[0])?;
-                    Ok(BlockInfo {
-                        indexer_version: d.indexer_version.clone(),
-                        indexer_identifier: d.indexer_identifier.clone(),
-                        status: BlockIndexingStatus::from_str(&d.status).unwrap(),
-                        block_number,
-                    })
-                }
-            }
-            Err(e) => Err(StorageError::DatabaseError(e.to_string())),
+            .await?;
+
+        if rows.is_empty() {
+            Err(StorageError::NotFound(format!("class_hash: {class_hash}")))
+        } else {
+            let c = ClassData::from_row(&rows[0])?;
+            Ok(ClassInfo {
+                class_hash: c.class_hash,
+                contract_type: c.contract_type,
+                identification_method: c.identification_method,
+            })
         }
     }
 
-    async fn clean_block(
+    async fn register_class_info(
         &self,
+        info: &ClassInfo,
         block_timestamp: u64,
-        block_number: Option<u64>,
     ) -> Result<(), StorageError> {
         trace!(
-            "Cleaning block #{:?} [ts: {}]",
-            block_number,
-            block_timestamp.to_string()
+            "Registering class info {:?} for class {}",
+            info.contract_type,
+            info.class_hash
         );
 
-        let q = "DELETE FROM block WHERE block_timestamp = ?";
-        sqlx::query(q)
+        match self.get_class_info(&info.class_hash).await {
+            Ok(_) => {
+                return Err(StorageError::AlreadyExists(format!(
+                    "class hash = {}",
+                    info.class_hash
+                )))
+            }
+            Err(StorageError::NotFound(_)) => (),
+            Err(e) => return Err(e),
+        }
+
+        let q = "INSERT INTO class (class_hash, contract_type, identification_method, block_timestamp) VALUES (?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(info.class_hash.clone())
+            .bind(info.contract_type.clone())
+            .bind(info.identification_method.clone())
             .bind(block_timestamp.to_string())
-            .fetch_all(&self.pool)
+            .execute(&self.pool)
             .await?;
 
-        let q = "DELETE FROM contract WHERE block_timestamp = ?";
-        sqlx::query(q)
+        Ok(())
+    }
+
+    async fn register_memecoin_created_event(
+        &self,
+        event: &MemecoinCreatedEvent,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        trace!("Registering memecoin created {:?}", event);
+
+        let q = "INSERT INTO memecoin (memecoin_address, owner, name, symbol, initial_supply, block_timestamp) VALUES (?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(event.memecoin_address.clone())
+            .bind(event.owner.clone())
+            .bind(event.name.clone())
+            .bind(event.symbol.clone())
+            .bind(event.initial_supply.to_decimal(false))
             .bind(block_timestamp.to_string())
-            .fetch_all(&self.pool)
+            .execute(&self.pool)
             .await?;
 
-        let q = "DELETE FROM token WHERE block_timestamp = ?";
-        sqlx::query(q)
+        Ok(())
+    }
+
+    async fn register_memecoin_launched_event(
+        &self,
+        event: &MemecoinLaunchedEvent,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        trace!("Registering memecoin launched {:?}", event);
+
+        if (self.get_memecoin_launch(&event.memecoin_address).await?).is_some() {
+            return Err(StorageError::AlreadyExists(format!(
+                "memecoin launch = {}",
+                event.memecoin_address
+            )));
+        }
+
+        let q = "INSERT INTO memecoin_launch (memecoin_address, quote_token, liquidity_type, locked_liquidity, team_allocation, transaction_hash, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(event.memecoin_address.clone())
+            .bind(event.quote_token.clone())
+            .bind(event.liquidity_type.to_string())
+            .bind(event.locked_liquidity.clone().unwrap_or_default())
+            .bind(event.team_allocation.clone().unwrap_or_default())
+            .bind(event.transaction_hash.clone())
+            .bind(event.block_number.to_string())
             .bind(block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_memecoin(&self, memecoin_address: &str) -> Result<MemecoinInfo, StorageError> {
+        trace!("Getting memecoin {}", memecoin_address);
+
+        let q = "SELECT * FROM memecoin WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
             .fetch_all(&self.pool)
             .await?;
 
-        let q = "DELETE FROM event WHERE block_timestamp = ?";
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "memecoin: {memecoin_address}"
+            )));
+        }
+
+        self.to_memecoin_info(MemecoinData::from_row(&rows[0])?)
+            .await
+    }
+
+    async fn get_memecoins(&self) -> Result<Vec<MemecoinInfo>, StorageError> {
+        trace!("Getting memecoins");
+
+        let q = "SELECT * FROM memecoin ORDER BY block_timestamp DESC";
+        let rows = sqlx::query(q).fetch_all(&self.pool).await?;
+
+        let mut memecoins = vec![];
+        for r in rows {
+            memecoins.push(self.to_memecoin_info(MemecoinData::from_row(&r)?).await?);
+        }
+
+        Ok(memecoins)
+    }
+
+    async fn get_filtered_memecoins(
+        &self,
+        filter: &MemecoinFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<MemecoinInfo>, StorageError> {
+        trace!("Getting memecoins {:?} {:?}", filter, pagination);
+
+        let mut conditions = vec![];
+        let mut values = vec![];
+        if let Some(o) = &filter.owner {
+            conditions.push("owner = ?");
+            values.push(o.clone());
+        }
+        Self::push_cursor(
+            &mut conditions,
+            &mut values,
+            pagination,
+            "(block_timestamp < ? OR (block_timestamp = ? AND memecoin_address > ?))",
+        );
+
+        let q = format!(
+            "SELECT * FROM memecoin {} ORDER BY block_timestamp DESC, memecoin_address LIMIT ? OFFSET ?",
+            Self::where_clause(&conditions)
+        );
+
+        let mut query = sqlx::query(&q);
+        for v in values {
+            query = query.bind(v);
+        }
+
+        let rows = query
+            .bind(pagination.limit.to_string())
+            .bind(pagination.offset.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut memecoins = vec![];
+        for r in rows {
+            memecoins.push(self.to_memecoin_info(MemecoinData::from_row(&r)?).await?);
+        }
+
+        Ok(memecoins)
+    }
+
+    async fn register_token_transfer(
+        &self,
+        transfer: &TokenTransfer,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        trace!("Registering token transfer {:?}", transfer);
+
+        let q = "SELECT * FROM token_transfer WHERE event_id = ?";
+        if !sqlx::query(q)
+            .bind(transfer.event_id.clone())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "transfer event id = {}",
+                transfer.event_id
+            )));
+        }
+
+        let q = "INSERT INTO token_transfer (event_id, token_address, from_address, to_address, amount, transaction_hash, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(transfer.event_id.clone())
+            .bind(transfer.token_address.clone())
+            .bind(transfer.from_address.clone())
+            .bind(transfer.to_address.clone())
+            .bind(transfer.amount.clone())
+            .bind(transfer.transaction_hash.clone())
+            .bind(transfer.block_number.to_string())
+            .bind(block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_token_transfers(
+        &self,
+        token_address: &str,
+        to_block: Option<u64>,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        trace!(
+            "Getting transfers of token {} up to {:?}",
+            token_address,
+            to_block
+        );
+
+        let q = "SELECT * FROM token_transfer WHERE token_address = ? AND block_number <= ? ORDER BY block_number";
+
+        let rows = sqlx::query(q)
+            .bind(token_address.to_string())
+            .bind(to_block.unwrap_or(i64::MAX as u64).to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut transfers = vec![];
+        for r in rows {
+            let d = TokenTransferData::from_row(&r)?;
+            transfers.push(TokenTransfer {
+                token_address: d.token_address,
+                from_address: d.from_address,
+                to_address: d.to_address,
+                amount: d.amount,
+                transaction_hash: d.transaction_hash,
+                event_id: d.event_id,
+                block_number: d.block_number as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(transfers)
+    }
+
+    async fn register_liquidity_lock(
+        &self,
+        lock: &LiquidityLock,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        trace!("Registering liquidity lock {:?}", lock);
+
+        let q = "INSERT INTO liquidity_lock (lock_address, token_address, owner, amount, unlock_time, block_timestamp) VALUES (?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(lock.lock_address.clone())
+            .bind(lock.token_address.clone())
+            .bind(lock.owner.clone())
+            .bind(lock.amount.clone())
+            .bind(lock.unlock_time.to_string())
+            .bind(block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_liquidity_locks(
+        &self,
+        token_address: &str,
+    ) -> Result<Vec<LiquidityLock>, StorageError> {
+        trace!("Getting liquidity locks of token {}", token_address);
+
+        let q = "SELECT * FROM liquidity_lock WHERE token_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(token_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut locks = vec![];
+        for r in rows {
+            let d = LiquidityLockData::from_row(&r)?;
+            locks.push(LiquidityLock {
+                lock_address: d.lock_address,
+                token_address: d.token_address,
+                owner: d.owner,
+                amount: d.amount,
+                unlock_time: d.unlock_time as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(locks)
+    }
+
+    async fn set_safety_report(&self, report: &SafetyReport) -> Result<(), StorageError> {
+        trace!("Setting safety report {:?}", report);
+
+        let criteria = serde_json::to_string(&report.criteria)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let q = "DELETE FROM safety_report WHERE memecoin_address = ?";
+        sqlx::query(q)
+            .bind(report.memecoin_address.clone())
+            .execute(&self.pool)
+            .await?;
+
+        let q = "INSERT INTO safety_report (memecoin_address, score, criteria, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(report.memecoin_address.clone())
+            .bind(report.score.to_string())
+            .bind(criteria)
+            .bind(report.block_number.to_string())
+            .bind(report.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_safety_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SafetyReport, StorageError> {
+        trace!("Getting safety report of {}", memecoin_address);
+
+        let q = "SELECT * FROM safety_report WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "safety report: {memecoin_address}"
+            )));
+        }
+
+        let d = SafetyReportData::from_row(&rows[0])?;
+        Ok(SafetyReport {
+            memecoin_address: d.memecoin_address,
+            score: d.score as u8,
+            criteria: serde_json::from_str(&d.criteria)
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        })
+    }
+
+    async fn register_pool(&self, pool: &Pool, block_timestamp: u64) -> Result<(), StorageError> {
+        trace!("Registering pool {:?}", pool);
+
+        let q = "SELECT * FROM pool WHERE pool_address = ?";
+        if !sqlx::query(q)
+            .bind(pool.pool_address.clone())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "pool = {}",
+                pool.pool_address
+            )));
+        }
+
+        let q = "INSERT INTO pool (pool_address, exchange, memecoin_address, quote_token, token0, token1, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(pool.pool_address.clone())
+            .bind(pool.exchange.to_string())
+            .bind(pool.memecoin_address.clone())
+            .bind(pool.quote_token.clone())
+            .bind(pool.token0.clone())
+            .bind(pool.token1.clone())
+            .bind(block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_pools(&self) -> Result<Vec<Pool>, StorageError> {
+        trace!("Getting pools");
+
+        let q = "SELECT * FROM pool";
+
+        let rows = sqlx::query(q).fetch_all(&self.pool).await?;
+
+        let mut pools = vec![];
+        for r in rows {
+            let d = PoolData::from_row(&r)?;
+            pools.push(Pool {
+                pool_address: d.pool_address,
+                exchange: LiquidityType::from_str(&d.exchange).unwrap(),
+                memecoin_address: d.memecoin_address,
+                quote_token: d.quote_token,
+                token0: d.token0,
+                token1: d.token1,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(pools)
+    }
+
+    async fn register_swap(
+        &self,
+        swap: &SwapEvent,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        trace!("Registering swap {:?}", swap);
+
+        let q = "SELECT * FROM swap WHERE event_id = ?";
+        if !sqlx::query(q)
+            .bind(swap.event_id.clone())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "swap event id = {}",
+                swap.event_id
+            )));
+        }
+
+        let q = "INSERT INTO swap (event_id, pool_address, token_in, token_out, amount_in, amount_out, trader, transaction_hash, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(swap.event_id.clone())
+            .bind(swap.pool_address.clone())
+            .bind(swap.token_in.clone())
+            .bind(swap.token_out.clone())
+            .bind(swap.amount_in.clone())
+            .bind(swap.amount_out.clone())
+            .bind(swap.trader.clone())
+            .bind(swap.transaction_hash.clone())
+            .bind(swap.block_number.to_string())
+            .bind(block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_swaps(
+        &self,
+        pool_address: &str,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        trace!(
+            "Getting swaps of pool {} from {} to {}",
+            pool_address,
+            from_timestamp,
+            to_timestamp
+        );
+
+        let q = "SELECT * FROM swap WHERE pool_address = ? AND block_timestamp >= ? AND block_timestamp <= ? ORDER BY block_number";
+
+        let rows = sqlx::query(q)
+            .bind(pool_address.to_string())
+            .bind(from_timestamp.to_string())
+            .bind(to_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut swaps = vec![];
+        for r in rows {
+            swaps.push(Self::to_swap(SwapData::from_row(&r)?));
+        }
+
+        Ok(swaps)
+    }
+
+    async fn get_last_swap(
+        &self,
+        pool_address: &str,
+        to_block_number: u64,
+    ) -> Result<SwapEvent, StorageError> {
+        trace!(
+            "Getting last swap of pool {} up to block {}",
+            pool_address,
+            to_block_number
+        );
+
+        let q = "SELECT * FROM swap WHERE pool_address = ? AND block_number <= ? ORDER BY block_number DESC LIMIT 1";
+
+        let rows = sqlx::query(q)
+            .bind(pool_address.to_string())
+            .bind(to_block_number.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!("swap: {pool_address}")));
+        }
+
+        Ok(Self::to_swap(SwapData::from_row(&rows[0])?))
+    }
+
+    async fn get_block_swaps(&self, block_timestamp: u64) -> Result<Vec<SwapEvent>, StorageError> {
+        trace!("Getting swaps of block {}", block_timestamp);
+
+        let q = "SELECT * FROM swap WHERE block_timestamp = ?";
+
+        let rows = sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut swaps = vec![];
+        for r in rows {
+            swaps.push(Self::to_swap(SwapData::from_row(&r)?));
+        }
+
+        Ok(swaps)
+    }
+
+    async fn get_candle(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        open_time: u64,
+    ) -> Result<Candle, StorageError> {
+        let q = "SELECT * FROM candle WHERE memecoin_address = ? AND candle_interval = ? AND open_time = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .bind(interval.to_string())
+            .bind(open_time.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "candle: {memecoin_address} {interval} {open_time}"
+            )));
+        }
+
+        Self::to_candle(CandleData::from_row(&rows[0])?)
+    }
+
+    async fn set_candle(&self, candle: &Candle) -> Result<(), StorageError> {
+        trace!("Setting candle {:?}", candle);
+
+        self.delete_candle(&candle.memecoin_address, candle.interval, candle.open_time)
+            .await?;
+
+        let q = "INSERT INTO candle (memecoin_address, candle_interval, open_time, open, high, low, close, volume_token, volume_quote, trades, close_usd, volume_usd, first_trade_at, last_trade_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(candle.memecoin_address.clone())
+            .bind(candle.interval.to_string())
+            .bind(candle.open_time.to_string())
+            .bind(candle.open.to_string())
+            .bind(candle.high.to_string())
+            .bind(candle.low.to_string())
+            .bind(candle.close.to_string())
+            .bind(candle.volume_token.clone())
+            .bind(candle.volume_quote.clone())
+            .bind(candle.trades.to_string())
+            .bind(candle.close_usd.to_string())
+            .bind(candle.volume_usd.to_string())
+            .bind(candle.first_trade_at.to_string())
+            .bind(candle.last_trade_at.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_candle(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        open_time: u64,
+    ) -> Result<(), StorageError> {
+        let q = "DELETE FROM candle WHERE memecoin_address = ? AND candle_interval = ? AND open_time = ?";
+
+        sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .bind(interval.to_string())
+            .bind(open_time.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_candles(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        from_time: u64,
+        to_time: u64,
+    ) -> Result<Vec<Candle>, StorageError> {
+        trace!(
+            "Getting {} candles of {} from {} to {}",
+            interval,
+            memecoin_address,
+            from_time,
+            to_time
+        );
+
+        let q = "SELECT * FROM candle WHERE memecoin_address = ? AND candle_interval = ? AND open_time >= ? AND open_time <= ? ORDER BY open_time";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .bind(interval.to_string())
+            .bind(from_time.to_string())
+            .bind(to_time.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut candles = vec![];
+        for r in rows {
+            candles.push(Self::to_candle(CandleData::from_row(&r)?)?);
+        }
+
+        Ok(candles)
+    }
+
+    async fn set_trending_score(&self, score: &TrendingScore) -> Result<(), StorageError> {
+        trace!("Setting trending score {:?}", score);
+
+        let q = "DELETE FROM trending_score WHERE memecoin_address = ? AND window_seconds = ?";
+        sqlx::query(q)
+            .bind(score.memecoin_address.clone())
+            .bind(score.window.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "INSERT INTO trending_score (memecoin_address, window_seconds, score, volume_quote, volume_usd, unique_buyers, new_holders, transfers, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(score.memecoin_address.clone())
+            .bind(score.window.to_string())
+            .bind(score.score.to_string())
+            .bind(score.volume_quote.clone())
+            .bind(score.volume_usd.to_string())
+            .bind(score.unique_buyers.to_string())
+            .bind(score.new_holders.to_string())
+            .bind(score.transfers.to_string())
+            .bind(score.block_number.to_string())
+            .bind(score.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_trending_scores(
+        &self,
+        window: u64,
+        limit: u64,
+    ) -> Result<Vec<TrendingScore>, StorageError> {
+        trace!("Getting {} trending scores of window {}", limit, window);
+
+        // Scores being stored as text, they are sorted once parsed.
+        let q = "SELECT * FROM trending_score WHERE window_seconds = ?";
+
+        let rows = sqlx::query(q)
+            .bind(window.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut scores = vec![];
+        for r in rows {
+            let d = TrendingScoreData::from_row(&r)?;
+            scores.push(TrendingScore {
+                memecoin_address: d.memecoin_address,
+                window: d.window_seconds as u64,
+                score: d.score.parse::<f64>().unwrap_or_default(),
+                volume_quote: d.volume_quote,
+                volume_usd: d.volume_usd.parse::<f64>().unwrap_or_default(),
+                unique_buyers: d.unique_buyers as u64,
+                new_holders: d.new_holders as u64,
+                transfers: d.transfers as u64,
+                block_number: d.block_number as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores.truncate(limit as usize);
+
+        Ok(scores)
+    }
+
+    async fn get_wallet_transfers(
+        &self,
+        wallet_address: &str,
+        to_timestamp: u64,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        trace!(
+            "Getting transfers of wallet {} up to {}",
+            wallet_address,
+            to_timestamp
+        );
+
+        let q = "SELECT * FROM token_transfer WHERE (from_address = ? OR to_address = ?) AND block_timestamp <= ? ORDER BY block_number";
+
+        let rows = sqlx::query(q)
+            .bind(wallet_address.to_string())
+            .bind(wallet_address.to_string())
+            .bind(to_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut transfers = vec![];
+        for r in rows {
+            let d = TokenTransferData::from_row(&r)?;
+            transfers.push(TokenTransfer {
+                token_address: d.token_address,
+                from_address: d.from_address,
+                to_address: d.to_address,
+                amount: d.amount,
+                transaction_hash: d.transaction_hash,
+                event_id: d.event_id,
+                block_number: d.block_number as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(transfers)
+    }
+
+    async fn get_wallet_swaps(
+        &self,
+        wallet_address: &str,
+        to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        trace!(
+            "Getting swaps of wallet {} up to {}",
+            wallet_address,
+            to_timestamp
+        );
+
+        let q =
+            "SELECT * FROM swap WHERE trader = ? AND block_timestamp <= ? ORDER BY block_number";
+
+        let rows = sqlx::query(q)
+            .bind(wallet_address.to_string())
+            .bind(to_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut swaps = vec![];
+        for r in rows {
+            swaps.push(Self::to_swap(SwapData::from_row(&r)?));
+        }
+
+        Ok(swaps)
+    }
+
+    async fn get_active_wallets(
+        &self,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<String>, StorageError> {
+        trace!(
+            "Getting active wallets from {} to {}",
+            from_timestamp,
+            to_timestamp
+        );
+
+        let q = "SELECT from_address AS wallet_address FROM token_transfer WHERE block_timestamp >= ? AND block_timestamp <= ? UNION SELECT to_address FROM token_transfer WHERE block_timestamp >= ? AND block_timestamp <= ? UNION SELECT trader FROM swap WHERE block_timestamp >= ? AND block_timestamp <= ? AND trader != '' AND pool_address IN (SELECT pool_address FROM pool)";
+
+        let mut query = sqlx::query(q);
+        for _ in 0..3 {
+            query = query
+                .bind(from_timestamp.to_string())
+                .bind(to_timestamp.to_string());
+        }
+
+        let rows = query.fetch_all(&self.pool).await?;
+
+        let mut wallets = vec![];
+        for r in rows {
+            wallets.push(r.try_get::<String, _>("wallet_address")?);
+        }
+
+        Ok(wallets)
+    }
+
+    async fn set_portfolio_snapshot(
+        &self,
+        snapshot: &PortfolioSnapshot,
+    ) -> Result<(), StorageError> {
+        trace!(
+            "Setting portfolio snapshot of {} for day {}",
+            snapshot.wallet_address,
+            snapshot.day
+        );
+
+        let q = "DELETE FROM portfolio_snapshot WHERE wallet_address = ? AND day = ?";
+        sqlx::query(q)
+            .bind(snapshot.wallet_address.clone())
+            .bind(snapshot.day.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let positions = serde_json::to_string(&snapshot.positions)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let q = "INSERT INTO portfolio_snapshot (wallet_address, day, positions) VALUES (?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(snapshot.wallet_address.clone())
+            .bind(snapshot.day.to_string())
+            .bind(positions)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_portfolio_snapshots(
+        &self,
+        wallet_address: &str,
+        from_day: u64,
+        to_day: u64,
+    ) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        trace!(
+            "Getting portfolio snapshots of {} from {} to {}",
+            wallet_address,
+            from_day,
+            to_day
+        );
+
+        let q = "SELECT * FROM portfolio_snapshot WHERE wallet_address = ? AND day >= ? AND day <= ? ORDER BY day";
+
+        let rows = sqlx::query(q)
+            .bind(wallet_address.to_string())
+            .bind(from_day.to_string())
+            .bind(to_day.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut snapshots = vec![];
+        for r in rows {
+            let d = PortfolioSnapshotData::from_row(&r)?;
+            snapshots.push(PortfolioSnapshot {
+                wallet_address: d.wallet_address,
+                day: d.day as u64,
+                positions: serde_json::from_str(&d.positions)
+                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?,
+            });
+        }
+
+        Ok(snapshots)
+    }
+
+    async fn register_wallet_funding(&self, transfer: &TokenTransfer) -> Result<(), StorageError> {
+        trace!("Registering wallet funding {:?}", transfer);
+
+        let q = "SELECT * FROM wallet_funding WHERE wallet_address = ?";
+        if !sqlx::query(q)
+            .bind(transfer.to_address.clone())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "wallet funding = {}",
+                transfer.to_address
+            )));
+        }
+
+        let q = "INSERT INTO wallet_funding (wallet_address, funder_address, token_address, amount, transaction_hash, event_id, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(transfer.to_address.clone())
+            .bind(transfer.from_address.clone())
+            .bind(transfer.token_address.clone())
+            .bind(transfer.amount.clone())
+            .bind(transfer.transaction_hash.clone())
+            .bind(transfer.event_id.clone())
+            .bind(transfer.block_number.to_string())
+            .bind(transfer.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_wallet_funding(
+        &self,
+        wallet_address: &str,
+    ) -> Result<TokenTransfer, StorageError> {
+        trace!("Getting funding of wallet {}", wallet_address);
+
+        let q = "SELECT * FROM wallet_funding WHERE wallet_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(wallet_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "wallet funding: {wallet_address}"
+            )));
+        }
+
+        let d = WalletFundingData::from_row(&rows[0])?;
+        Ok(TokenTransfer {
+            token_address: d.token_address,
+            from_address: d.funder_address,
+            to_address: d.wallet_address,
+            amount: d.amount,
+            transaction_hash: d.transaction_hash,
+            event_id: d.event_id,
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        })
+    }
+
+    async fn set_sniper_report(&self, report: &SniperReport) -> Result<(), StorageError> {
+        trace!("Setting sniper report {:?}", report);
+
+        let snipers = serde_json::to_string(&report.snipers)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let clusters = serde_json::to_string(&report.clusters)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let q = "DELETE FROM sniper_report WHERE memecoin_address = ?";
+        sqlx::query(q)
+            .bind(report.memecoin_address.clone())
+            .execute(&self.pool)
+            .await?;
+
+        let q = "INSERT INTO sniper_report (memecoin_address, launch_block_number, window_blocks, snipers, clusters, snipers_supply_percent, bundled_supply_percent, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(report.memecoin_address.clone())
+            .bind(report.launch_block_number.to_string())
+            .bind(report.window_blocks.to_string())
+            .bind(snipers)
+            .bind(clusters)
+            .bind(report.snipers_supply_percent.to_string())
+            .bind(report.bundled_supply_percent.to_string())
+            .bind(report.block_number.to_string())
+            .bind(report.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_sniper_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SniperReport, StorageError> {
+        trace!("Getting sniper report of {}", memecoin_address);
+
+        let q = "SELECT * FROM sniper_report WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "sniper report: {memecoin_address}"
+            )));
+        }
+
+        let d = SniperReportData::from_row(&rows[0])?;
+        let json_err = |e: serde_json::Error| StorageError::DatabaseError(e.to_string());
+
+        Ok(SniperReport {
+            memecoin_address: d.memecoin_address,
+            launch_block_number: d.launch_block_number as u64,
+            window_blocks: d.window_blocks as u64,
+            snipers: serde_json::from_str(&d.snipers).map_err(json_err)?,
+            clusters: serde_json::from_str(&d.clusters).map_err(json_err)?,
+            snipers_supply_percent: d.snipers_supply_percent.parse::<f64>().unwrap_or_default(),
+            bundled_supply_percent: d.bundled_supply_percent.parse::<f64>().unwrap_or_default(),
+            block_number: d.block_number as u64,
+            block_timestamp: d.block_timestamp as u64,
+        })
+    }
+
+    async fn register_developer_wallet(
+        &self,
+        wallet: &DeveloperWallet,
+    ) -> Result<(), StorageError> {
+        trace!("Registering developer wallet {:?}", wallet);
+
+        let q = "SELECT * FROM developer_wallet WHERE memecoin_address = ? AND wallet_address = ? AND role = ?";
+        if !sqlx::query(q)
+            .bind(wallet.memecoin_address.clone())
+            .bind(wallet.wallet_address.clone())
+            .bind(wallet.role.to_string())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "developer wallet = {} {} {}",
+                wallet.memecoin_address,
+                wallet.wallet_address,
+                wallet.role.to_string()
+            )));
+        }
+
+        let q = "INSERT INTO developer_wallet (memecoin_address, wallet_address, role, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(wallet.memecoin_address.clone())
+            .bind(wallet.wallet_address.clone())
+            .bind(wallet.role.to_string())
+            .bind(wallet.block_number.to_string())
+            .bind(wallet.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_developer_wallets(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperWallet>, StorageError> {
+        trace!("Getting developer wallets of {}", memecoin_address);
+
+        let q = "SELECT * FROM developer_wallet WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut wallets = vec![];
+        for r in rows {
+            let d = DeveloperWalletData::from_row(&r)?;
+            wallets.push(DeveloperWallet {
+                memecoin_address: d.memecoin_address,
+                wallet_address: d.wallet_address,
+                role: DeveloperRole::from_str(&d.role).map_err(|_| {
+                    StorageError::DatabaseError(format!("Invalid developer role {}", d.role))
+                })?,
+                block_number: d.block_number as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(wallets)
+    }
+
+    async fn register_developer_activity(
+        &self,
+        activity: &DeveloperActivity,
+    ) -> Result<(), StorageError> {
+        trace!("Registering developer activity {:?}", activity);
+
+        let q = "SELECT * FROM developer_activity WHERE event_id = ?";
+        if !sqlx::query(q)
+            .bind(activity.event_id.clone())
+            .fetch_all(&self.pool)
+            .await?
+            .is_empty()
+        {
+            return Err(StorageError::AlreadyExists(format!(
+                "developer activity event id = {}",
+                activity.event_id
+            )));
+        }
+
+        let q = "INSERT INTO developer_activity (event_id, memecoin_address, wallet_address, role, kind, to_address, amount, transaction_hash, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(activity.event_id.clone())
+            .bind(activity.memecoin_address.clone())
+            .bind(activity.wallet_address.clone())
+            .bind(activity.role.to_string())
+            .bind(activity.kind.to_string())
+            .bind(activity.to_address.clone())
+            .bind(activity.amount.clone())
+            .bind(activity.transaction_hash.clone())
+            .bind(activity.block_number.to_string())
+            .bind(activity.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_developer_activities(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperActivity>, StorageError> {
+        trace!("Getting developer activities of {}", memecoin_address);
+
+        let q = "SELECT * FROM developer_activity WHERE memecoin_address = ? ORDER BY block_number";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut activities = vec![];
+        for r in rows {
+            let d = DeveloperActivityData::from_row(&r)?;
+            activities.push(DeveloperActivity {
+                memecoin_address: d.memecoin_address,
+                wallet_address: d.wallet_address,
+                role: DeveloperRole::from_str(&d.role).map_err(|_| {
+                    StorageError::DatabaseError(format!("Invalid developer role {}", d.role))
+                })?,
+                kind: DeveloperActivityKind::from_str(&d.kind).map_err(|_| {
+                    StorageError::DatabaseError(format!("Invalid developer activity {}", d.kind))
+                })?,
+                to_address: d.to_address,
+                amount: d.amount,
+                transaction_hash: d.transaction_hash,
+                event_id: d.event_id,
+                block_number: d.block_number as u64,
+                block_timestamp: d.block_timestamp as u64,
+            });
+        }
+
+        Ok(activities)
+    }
+
+    async fn set_market_metrics(&self, metrics: &MarketMetrics) -> Result<(), StorageError> {
+        trace!("Setting market metrics {:?}", metrics);
+
+        let q = "DELETE FROM market_metrics WHERE memecoin_address = ?";
+        sqlx::query(q)
+            .bind(metrics.memecoin_address.clone())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "INSERT INTO market_metrics (memecoin_address, total_supply, circulating_supply, price_usd, market_cap_usd, fdv_usd, liquidity_usd, block_number, block_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(metrics.memecoin_address.clone())
+            .bind(metrics.total_supply.clone())
+            .bind(metrics.circulating_supply.clone())
+            .bind(metrics.price_usd.to_string())
+            .bind(metrics.market_cap_usd.to_string())
+            .bind(metrics.fdv_usd.to_string())
+            .bind(metrics.liquidity_usd.to_string())
+            .bind(metrics.block_number.to_string())
+            .bind(metrics.block_timestamp.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_market_metrics(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<MarketMetrics, StorageError> {
+        trace!("Getting market metrics of {}", memecoin_address);
+
+        let q = "SELECT * FROM market_metrics WHERE memecoin_address = ?";
+
+        let rows = sqlx::query(q)
+            .bind(memecoin_address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "market metrics: {memecoin_address}"
+            )));
+        }
+
+        Ok(Self::to_market_metrics(MarketMetricsData::from_row(
+            &rows[0],
+        )?))
+    }
+
+    async fn get_top_market_metrics(&self, limit: u64) -> Result<Vec<MarketMetrics>, StorageError> {
+        trace!("Getting top {} market metrics", limit);
+
+        // USD values being stored as text, they are sorted once parsed.
+        let q = "SELECT * FROM market_metrics";
+
+        let rows = sqlx::query(q).fetch_all(&self.pool).await?;
+
+        let mut metrics = vec![];
+        for r in rows {
+            metrics.push(Self::to_market_metrics(MarketMetricsData::from_row(&r)?));
+        }
+
+        metrics.sort_by(|a, b| b.market_cap_usd.total_cmp(&a.market_cap_usd));
+        metrics.truncate(limit as usize);
+
+        Ok(metrics)
+    }
+
+    async fn set_failed_event(&self, event: &FailedEvent) -> Result<(), StorageError> {
+        trace!("Setting failed event {:?}", event);
+
+        let q = "DELETE FROM failed_event WHERE failure_id = ?";
+        sqlx::query(q)
+            .bind(event.failure_id.clone())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let error = serde_json::to_string(&event.error)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let q = "INSERT INTO failed_event (failure_id, block_number, block_timestamp, transaction_hash, contract_address, kind, error, attempts, status) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+        let _r = sqlx::query(q)
+            .bind(event.failure_id.clone())
+            .bind(event.block_number.to_string())
+            .bind(event.block_timestamp.to_string())
+            .bind(event.transaction_hash.clone())
+            .bind(event.contract_address.clone())
+            .bind(event.kind.to_string())
+            .bind(error)
+            .bind(event.attempts.to_string())
+            .bind(event.status.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_failed_event(&self, failure_id: &str) -> Result<FailedEvent, StorageError> {
+        trace!("Getting failed event {}", failure_id);
+
+        let q = "SELECT * FROM failed_event WHERE failure_id = ?";
+
+        let rows = sqlx::query(q)
+            .bind(failure_id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "failed event: {failure_id}"
+            )));
+        }
+
+        Self::to_failed_event(FailedEventData::from_row(&rows[0])?)
+    }
+
+    async fn get_failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> Result<Vec<FailedEvent>, StorageError> {
+        trace!("Getting failed events {:?}", status);
+
+        let rows = match status {
+            Some(s) => {
+                let q = "SELECT * FROM failed_event WHERE status = ? ORDER BY block_number";
+                sqlx::query(q)
+                    .bind(s.to_string())
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => {
+                let q = "SELECT * FROM failed_event ORDER BY block_number";
+                sqlx::query(q).fetch_all(&self.pool).await?
+            }
+        };
+
+        let mut events = vec![];
+        for r in rows {
+            events.push(Self::to_failed_event(FailedEventData::from_row(&r)?)?);
+        }
+
+        Ok(events)
+    }
+
+    async fn set_block_info(
+        &self,
+        block_number: u64,
+        block_timestamp: u64,
+        info: BlockInfo,
+    ) -> Result<(), StorageError> {
+        trace!("Setting block info {:?} for block #{}", info, block_number);
+
+        let _r = if (self.get_block_by_timestamp(block_timestamp).await?).is_some() {
+            let q = "UPDATE block SET block_timestamp = ?, block_number = ?, status = ?, indexer_version = ?, indexer_identifier = ? WHERE block_timestamp = ?";
+            sqlx::query(q)
+                .bind(block_timestamp.to_string())
+                .bind(block_number.to_string())
+                .bind(info.status.to_string())
+                .bind(info.indexer_version.clone())
+                .bind(info.indexer_identifier.clone())
+                .bind(block_timestamp.to_string())
+                .execute(&self.pool)
+                .await?
+        } else {
+            let q = "INSERT INTO block (block_timestamp, block_number, status, indexer_version, indexer_identifier) VALUES (?, ?, ?, ?, ?)";
+
+            sqlx::query(q)
+                .bind(block_timestamp.to_string())
+                .bind(block_number.to_string())
+                .bind(info.status.to_string())
+                .bind(info.indexer_version.clone())
+                .bind(info.indexer_identifier.clone())
+                .execute(&self.pool)
+                .await?
+        };
+
+        Ok(())
+    }
+
+    async fn get_block_info(&self, block_number: u64) -> Result<BlockInfo, StorageError> {
+        trace!("Getting block info for block #{}", block_number);
+
+        let q = "SELECT * FROM block WHERE block_number = ?";
+
+        match sqlx::query(q)
+            .bind(block_number.to_string())
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(rows) => {
+                if rows.is_empty() {
+                    Err(StorageError::NotFound(format!(
+                        "block number {block_number}"
+                    )))
+                } else {
+                    let d = BlockData::from_row(&rows[0])?;
+                    Ok(BlockInfo {
+                        indexer_version: d.indexer_version.clone(),
+                        indexer_identifier: d.indexer_identifier.clone(),
+                        status: BlockIndexingStatus::from_str(&d.status).unwrap(),
+                        block_number,
+                    })
+                }
+            }
+            Err(e) => Err(StorageError::DatabaseError(e.to_string())),
+        }
+    }
+
+    async fn get_block(&self, block_number: u64) -> Result<(u64, BlockInfo), StorageError> {
+        trace!("Getting block #{}", block_number);
+
+        let q = "SELECT * FROM block WHERE block_number = ?";
+
+        let rows = sqlx::query(q)
+            .bind(block_number.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        if rows.is_empty() {
+            return Err(StorageError::NotFound(format!(
+                "block number {block_number}"
+            )));
+        }
+
+        Ok(Self::to_block(BlockData::from_row(&rows[0])?))
+    }
+
+    async fn get_blocks(
+        &self,
+        pagination: &Pagination,
+    ) -> Result<Vec<(u64, BlockInfo)>, StorageError> {
+        trace!("Getting blocks {:?}", pagination);
+
+        let mut conditions = vec![];
+        let mut values = vec![];
+        // Blocks having distinct timestamps, the id is not needed.
+        if let Some(c) = &pagination.after {
+            conditions.push("block_timestamp < ?");
+            values.push(c.timestamp.to_string());
+        }
+
+        let q = format!(
+            "SELECT * FROM block {} ORDER BY block_timestamp DESC LIMIT ? OFFSET ?",
+            Self::where_clause(&conditions)
+        );
+
+        let mut query = sqlx::query(&q);
+        for v in values {
+            query = query.bind(v);
+        }
+
+        let rows = query
+            .bind(pagination.limit.to_string())
+            .bind(pagination.offset.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut blocks = vec![];
+        for r in rows {
+            blocks.push(Self::to_block(BlockData::from_row(&r)?));
+        }
+
+        Ok(blocks)
+    }
+
+    async fn clean_block(
+        &self,
+        block_timestamp: u64,
+        block_number: Option<u64>,
+    ) -> Result<(), StorageError> {
+        trace!(
+            "Cleaning block #{:?} [ts: {}]",
+            block_number,
+            block_timestamp.to_string()
+        );
+
+        let q = "DELETE FROM block WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM contract WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM token WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM event WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM memecoin WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM memecoin_launch WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM token_transfer WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM liquidity_lock WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM pool WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM swap WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM wallet_funding WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM developer_wallet WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM developer_activity WHERE block_timestamp = ?";
+        sqlx::query(q)
+            .bind(block_timestamp.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let q = "DELETE FROM failed_event WHERE block_timestamp = ? AND status <> ?";
         sqlx::query(q)
             .bind(block_timestamp.to_string())
+            .bind(FailedEventStatus::Failed.to_string())
             .fetch_all(&self.pool)
             .await?;
 
@@ -0,0 +1,26 @@
+-- Wallets of the memecoins teams, and the memecoins moved out of them.
+
+CREATE TABLE developer_wallet (
+       memecoin_address TEXT NOT NULL,
+       wallet_address TEXT NOT NULL,
+       role TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address, wallet_address, role)
+);
+
+CREATE TABLE developer_activity (
+       event_id TEXT NOT NULL,
+       memecoin_address TEXT NOT NULL,
+       wallet_address TEXT NOT NULL,
+       role TEXT NOT NULL,
+       kind TEXT NOT NULL,
+       to_address TEXT NOT NULL,
+       amount TEXT NOT NULL,
+       transaction_hash TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (event_id)
+);
@@ -0,0 +1,5 @@
+-- USD valuation of the candles, 0 when the quote token
+-- price is unknown.
+
+ALTER TABLE candle ADD COLUMN close_usd TEXT NOT NULL DEFAULT '0';
+ALTER TABLE candle ADD COLUMN volume_usd TEXT NOT NULL DEFAULT '0';
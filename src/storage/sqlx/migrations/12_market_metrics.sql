@@ -0,0 +1,16 @@
+-- Market metrics of the memecoins, at the last block they changed.
+-- USD values are stored as text, as the candle prices.
+
+CREATE TABLE market_metrics (
+       memecoin_address TEXT NOT NULL,
+       total_supply TEXT NOT NULL,
+       circulating_supply TEXT NOT NULL,
+       price_usd TEXT NOT NULL,
+       market_cap_usd TEXT NOT NULL,
+       fdv_usd TEXT NOT NULL,
+       liquidity_usd TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address)
+);
@@ -0,0 +1,17 @@
+-- Dead-letter store of the events which couldn't be indexed,
+-- kept to be replayed.
+
+CREATE TABLE failed_event (
+       failure_id TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+       transaction_hash TEXT NOT NULL,
+       contract_address TEXT NOT NULL,
+       kind TEXT NOT NULL,
+       -- JSON of the error kind and message.
+       error TEXT NOT NULL,
+       attempts BIGINT NOT NULL,
+       status TEXT NOT NULL,
+
+       PRIMARY KEY (failure_id)
+);
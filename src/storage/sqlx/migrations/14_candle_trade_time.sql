@@ -0,0 +1,5 @@
+-- Timestamps of the first and last trades of the candles, the swaps
+-- being possibly added out of order. 0 for the existing candles.
+
+ALTER TABLE candle ADD COLUMN first_trade_at BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE candle ADD COLUMN last_trade_at BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- USD swap volume of the trending scores, on which they are ranked
+-- whatever the quote token. Stored as text, as the score.
+
+ALTER TABLE trending_score ADD COLUMN volume_usd TEXT NOT NULL DEFAULT '0';
@@ -0,0 +1,3 @@
+-- Keeps track of how the contract type was identified.
+
+ALTER TABLE contract ADD COLUMN identification_method TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,14 @@
+-- Contract type identified per class, shared by all
+-- the contracts deployed with the same class.
+--
+-- Not cleaned with the blocks, as a class classification
+-- doesn't depend on the block where it was first seen.
+
+CREATE TABLE class (
+       class_hash TEXT NOT NULL,
+       contract_type TEXT NOT NULL,
+       identification_method TEXT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (class_hash)
+);
@@ -0,0 +1,25 @@
+-- Memecoins deployed by the Unruggable factory, and their launches.
+
+CREATE TABLE memecoin (
+       memecoin_address TEXT NOT NULL,
+       owner TEXT NOT NULL,
+       name TEXT NOT NULL,
+       symbol TEXT NOT NULL,
+       initial_supply TEXT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address)
+);
+
+CREATE TABLE memecoin_launch (
+       memecoin_address TEXT NOT NULL,
+       quote_token TEXT NOT NULL,
+       liquidity_type TEXT NOT NULL,
+       locked_liquidity TEXT NOT NULL DEFAULT '',
+       team_allocation TEXT NOT NULL DEFAULT '',
+       transaction_hash TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address)
+);
@@ -0,0 +1,36 @@
+-- Fungible transfers of the memecoins, liquidity locks,
+-- and the safety reports computed from them.
+
+CREATE TABLE token_transfer (
+       event_id TEXT NOT NULL,
+       token_address TEXT NOT NULL,
+       from_address TEXT NOT NULL,
+       to_address TEXT NOT NULL,
+       amount TEXT NOT NULL,
+       transaction_hash TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (event_id)
+);
+
+CREATE TABLE liquidity_lock (
+       lock_address TEXT NOT NULL,
+       token_address TEXT NOT NULL,
+       owner TEXT NOT NULL,
+       amount TEXT NOT NULL,
+       unlock_time BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (lock_address)
+);
+
+CREATE TABLE safety_report (
+       memecoin_address TEXT NOT NULL,
+       score BIGINT NOT NULL,
+       criteria TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address)
+);
@@ -0,0 +1,28 @@
+-- Memecoin pools, discovered from the launches, and their swaps.
+
+CREATE TABLE pool (
+       pool_address TEXT NOT NULL,
+       exchange TEXT NOT NULL,
+       memecoin_address TEXT NOT NULL,
+       quote_token TEXT NOT NULL,
+       token0 TEXT NOT NULL,
+       token1 TEXT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (pool_address)
+);
+
+CREATE TABLE swap (
+       event_id TEXT NOT NULL,
+       pool_address TEXT NOT NULL,
+       token_in TEXT NOT NULL,
+       token_out TEXT NOT NULL,
+       amount_in TEXT NOT NULL,
+       amount_out TEXT NOT NULL,
+       trader TEXT NOT NULL,
+       transaction_hash TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (event_id)
+);
@@ -0,0 +1,18 @@
+-- OHLCV candles of the memecoins, computed from the swaps.
+-- Prices are stored as text to keep the float precision
+-- whatever the database.
+
+CREATE TABLE candle (
+       memecoin_address TEXT NOT NULL,
+       candle_interval TEXT NOT NULL,
+       open_time BIGINT NOT NULL,
+       open TEXT NOT NULL,
+       high TEXT NOT NULL,
+       low TEXT NOT NULL,
+       close TEXT NOT NULL,
+       volume_token TEXT NOT NULL,
+       volume_quote TEXT NOT NULL,
+       trades BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address, candle_interval, open_time)
+);
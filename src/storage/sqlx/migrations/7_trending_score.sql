@@ -0,0 +1,16 @@
+-- Trending score of the memecoins, for each rolling window.
+-- The score is stored as text, as the candle prices.
+
+CREATE TABLE trending_score (
+       memecoin_address TEXT NOT NULL,
+       window_seconds BIGINT NOT NULL,
+       score TEXT NOT NULL,
+       volume_quote TEXT NOT NULL,
+       unique_buyers BIGINT NOT NULL,
+       new_holders BIGINT NOT NULL,
+       transfers BIGINT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address, window_seconds)
+);
@@ -0,0 +1,10 @@
+-- Daily snapshots of the wallets positions.
+-- Positions are stored as JSON text.
+
+CREATE TABLE portfolio_snapshot (
+       wallet_address TEXT NOT NULL,
+       day BIGINT NOT NULL,
+       positions TEXT NOT NULL,
+
+       PRIMARY KEY (wallet_address, day)
+);
@@ -0,0 +1,29 @@
+-- First funding of the wallets, and snipers of the memecoin launches.
+-- Percentages are stored as text, snipers and clusters as JSON text.
+
+CREATE TABLE wallet_funding (
+       wallet_address TEXT NOT NULL,
+       funder_address TEXT NOT NULL,
+       token_address TEXT NOT NULL,
+       amount TEXT NOT NULL,
+       transaction_hash TEXT NOT NULL,
+       event_id TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (wallet_address)
+);
+
+CREATE TABLE sniper_report (
+       memecoin_address TEXT NOT NULL,
+       launch_block_number BIGINT NOT NULL,
+       window_blocks BIGINT NOT NULL,
+       snipers TEXT NOT NULL,
+       clusters TEXT NOT NULL,
+       snipers_supply_percent TEXT NOT NULL,
+       bundled_supply_percent TEXT NOT NULL,
+       block_number BIGINT NOT NULL,
+       block_timestamp BIGINT NOT NULL,
+
+       PRIMARY KEY (memecoin_address)
+);
@@ -44,4 +44,207 @@ pub struct ContractData {
     pub block_timestamp: i64,
     pub contract_address: String,
     pub contract_type: String,
+    pub identification_method: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ClassData {
+    pub block_timestamp: i64,
+    pub class_hash: String,
+    pub contract_type: String,
+    pub identification_method: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MemecoinData {
+    pub memecoin_address: String,
+    pub owner: String,
+    pub name: String,
+    pub symbol: String,
+    pub initial_supply: String,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MemecoinLaunchData {
+    pub memecoin_address: String,
+    pub quote_token: String,
+    pub liquidity_type: String,
+    pub locked_liquidity: String,
+    pub team_allocation: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TokenTransferData {
+    pub event_id: String,
+    pub token_address: String,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LiquidityLockData {
+    pub lock_address: String,
+    pub token_address: String,
+    pub owner: String,
+    pub amount: String,
+    pub unlock_time: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SafetyReportData {
+    pub memecoin_address: String,
+    pub score: i64,
+    /// Criteria serialized as JSON.
+    pub criteria: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PoolData {
+    pub pool_address: String,
+    pub exchange: String,
+    pub memecoin_address: String,
+    pub quote_token: String,
+    pub token0: String,
+    pub token1: String,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SwapData {
+    pub event_id: String,
+    pub pool_address: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: String,
+    pub amount_out: String,
+    pub trader: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CandleData {
+    pub memecoin_address: String,
+    pub candle_interval: String,
+    pub open_time: i64,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume_token: String,
+    pub volume_quote: String,
+    pub trades: i64,
+    pub close_usd: String,
+    pub volume_usd: String,
+    pub first_trade_at: i64,
+    pub last_trade_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TrendingScoreData {
+    pub memecoin_address: String,
+    pub window_seconds: i64,
+    pub score: String,
+    pub volume_quote: String,
+    pub volume_usd: String,
+    pub unique_buyers: i64,
+    pub new_holders: i64,
+    pub transfers: i64,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PortfolioSnapshotData {
+    pub wallet_address: String,
+    pub day: i64,
+    pub positions: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WalletFundingData {
+    pub wallet_address: String,
+    pub funder_address: String,
+    pub token_address: String,
+    pub amount: String,
+    pub transaction_hash: String,
+    pub event_id: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SniperReportData {
+    pub memecoin_address: String,
+    pub launch_block_number: i64,
+    pub window_blocks: i64,
+    /// Snipers serialized as JSON.
+    pub snipers: String,
+    /// Clusters serialized as JSON.
+    pub clusters: String,
+    pub snipers_supply_percent: String,
+    pub bundled_supply_percent: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeveloperWalletData {
+    pub memecoin_address: String,
+    pub wallet_address: String,
+    pub role: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MarketMetricsData {
+    pub memecoin_address: String,
+    pub total_supply: String,
+    pub circulating_supply: String,
+    pub price_usd: String,
+    pub market_cap_usd: String,
+    pub fdv_usd: String,
+    pub liquidity_usd: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FailedEventData {
+    pub failure_id: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+    pub transaction_hash: String,
+    pub contract_address: String,
+    pub kind: String,
+    pub error: String,
+    pub attempts: i64,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeveloperActivityData {
+    pub event_id: String,
+    pub memecoin_address: String,
+    pub wallet_address: String,
+    pub role: String,
+    pub kind: String,
+    pub to_address: String,
+    pub amount: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub block_timestamp: i64,
 }
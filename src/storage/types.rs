@@ -1,7 +1,8 @@
+use ark_starknet::client::StarknetClientError;
+use ark_starknet::CairoU256;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
-use ark_starknet::CairoU256;
 
 #[derive(Debug, Clone)]
 pub enum StorageError {
@@ -106,6 +107,97 @@ pub struct TokenInfo {
     pub owner: String,
 }
 
+/// Slice of a list query: `limit` items after the `after` cursor if any,
+/// else after the first `offset` ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pagination {
+    pub offset: u64,
+    pub limit: u64,
+    #[serde(default)]
+    pub after: Option<Cursor>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            offset: 0,
+            limit: 50,
+            after: None,
+        }
+    }
+}
+
+/// Position of an item in a list ordered by decreasing timestamp then by
+/// id, the page after it being stable whatever is registered meanwhile.
+/// The tokens, ordered by contract and token id, have a zero timestamp
+/// and an id formatted as `<contract_address>/<token_id_hex>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub timestamp: u64,
+    pub id: String,
+}
+
+impl Cursor {
+    /// Returns true if the item with the timestamp and id comes after
+    /// the cursor in a list ordered by decreasing timestamp then by id.
+    pub fn precedes(&self, timestamp: u64, id: &str) -> bool {
+        timestamp < self.timestamp || (timestamp == self.timestamp && id > self.id.as_str())
+    }
+
+    /// Returns the contract address and token id hex of a token cursor.
+    pub fn token_key(&self) -> Option<(&str, &str)> {
+        self.id.split_once('/')
+    }
+}
+
+/// Criteria of a tokens query, all the given ones having to match.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TokenFilter {
+    pub contract_address: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// Criteria of a memecoins query, all the given ones having to match.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MemecoinFilter {
+    pub owner: Option<String>,
+}
+
+/// Criteria of an events query, all the given ones having to match.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EventFilter {
+    pub contract_address: Option<String>,
+    pub token_id_hex: Option<String>,
+    /// Sender or recipient of the token.
+    pub address: Option<String>,
+    pub event_type: Option<EventType>,
+    /// Timestamps, included.
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &TokenEvent) -> bool {
+        self.contract_address
+            .as_ref()
+            .map_or(true, |a| *a == event.contract_address)
+            && self
+                .token_id_hex
+                .as_ref()
+                .map_or(true, |t| *t == event.token_id_hex)
+            && self
+                .address
+                .as_ref()
+                .map_or(true, |a| *a == event.from_address || *a == event.to_address)
+            && self
+                .event_type
+                .as_ref()
+                .map_or(true, |t| *t == event.event_type)
+            && self.from_timestamp.map_or(true, |t| event.timestamp >= t)
+            && self.to_timestamp.map_or(true, |t| event.timestamp <= t)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct TokenMintInfo {
     pub address: String,
@@ -114,7 +206,7 @@ pub struct TokenMintInfo {
     pub block_number: Option<u64>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum BlockIndexingStatus {
     None,
@@ -175,7 +267,7 @@ pub struct BlockIndexing {
     pub indexer_version: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockInfo {
     pub indexer_version: String,
     pub indexer_identifier: String,
@@ -187,6 +279,7 @@ pub struct BlockInfo {
 #[serde(rename_all = "snake_case")]
 pub enum ContractType {
     Other,
+    ERC20,
     ERC721,
     ERC1155,
 }
@@ -195,6 +288,7 @@ impl ToString for ContractType {
     fn to_string(&self) -> String {
         match self {
             ContractType::Other => "OTHER".to_string(),
+            ContractType::ERC20 => "ERC20".to_string(),
             ContractType::ERC721 => "ERC721".to_string(),
             ContractType::ERC1155 => "ERC1155".to_string(),
         }
@@ -206,6 +300,7 @@ impl FromStr for ContractType {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "ERC20" => Ok(ContractType::ERC20),
             "ERC721" => Ok(ContractType::ERC721),
             "ERC1155" => Ok(ContractType::ERC1155),
             _ => Ok(ContractType::Other),
@@ -213,6 +308,41 @@ impl FromStr for ContractType {
     }
 }
 
+/// How the type of a contract was determined.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentificationMethod {
+    /// Interfaces declared through SRC5 `supports_interface`.
+    Src5,
+    /// Entrypoints probed with arbitrary calldata.
+    Probing,
+    /// Type already known for the class of the contract.
+    ClassHash,
+}
+
+impl ToString for IdentificationMethod {
+    fn to_string(&self) -> String {
+        match self {
+            IdentificationMethod::Src5 => "SRC5".to_string(),
+            IdentificationMethod::Probing => "PROBING".to_string(),
+            IdentificationMethod::ClassHash => "CLASS_HASH".to_string(),
+        }
+    }
+}
+
+impl FromStr for IdentificationMethod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SRC5" => Ok(IdentificationMethod::Src5),
+            "PROBING" => Ok(IdentificationMethod::Probing),
+            "CLASS_HASH" => Ok(IdentificationMethod::ClassHash),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct ContractInfo {
     pub contract_address: String,
@@ -220,9 +350,23 @@ pub struct ContractInfo {
     pub name: Option<String>,
     pub symbol: Option<String>,
     pub image: Option<String>,
+    /// Method used to identify the contract type, if known.
+    pub identification_method: Option<String>,
+    /// Timestamp of the block the contract was registered at.
+    #[serde(default)]
+    pub block_timestamp: u64,
 }
 
-#[derive(Debug)]
+/// Classification shared by all the contracts of a same class.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ClassInfo {
+    pub class_hash: String,
+    pub contract_type: String,
+    /// Method used to identify the first contract of this class.
+    pub identification_method: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct MemecoinCreatedEvent {
     pub owner: String,
     pub name: String,
@@ -232,7 +376,13 @@ pub struct MemecoinCreatedEvent {
 }
 
 impl MemecoinCreatedEvent {
-    pub fn new(owner: String, name: String, symbol: String, initial_supply: CairoU256, memecoin_address: String) -> Self {
+    pub fn new(
+        owner: String,
+        name: String,
+        symbol: String,
+        initial_supply: CairoU256,
+        memecoin_address: String,
+    ) -> Self {
         MemecoinCreatedEvent {
             owner,
             name,
@@ -241,4 +391,617 @@ impl MemecoinCreatedEvent {
             memecoin_address,
         }
     }
-}
\ No newline at end of file
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidityType {
+    /// ERC20 LP token of a JediSwap pair.
+    JediSwap,
+    /// ERC20 LP token of a StarkDeFi pair.
+    StarkDeFi,
+    /// NFT position on Ekubo.
+    Ekubo,
+    Unknown,
+}
+
+impl ToString for LiquidityType {
+    fn to_string(&self) -> String {
+        match self {
+            LiquidityType::JediSwap => "JEDISWAP".to_string(),
+            LiquidityType::StarkDeFi => "STARKDEFI".to_string(),
+            LiquidityType::Ekubo => "EKUBO".to_string(),
+            LiquidityType::Unknown => "UNKNOWN".to_string(),
+        }
+    }
+}
+
+impl FromStr for LiquidityType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "JEDISWAP" => Ok(LiquidityType::JediSwap),
+            "STARKDEFI" => Ok(LiquidityType::StarkDeFi),
+            "EKUBO" => Ok(LiquidityType::Ekubo),
+            _ => Ok(LiquidityType::Unknown),
+        }
+    }
+}
+
+/// Launch of a memecoin, when its liquidity is added and locked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemecoinLaunchedEvent {
+    pub memecoin_address: String,
+    pub quote_token: String,
+    pub liquidity_type: LiquidityType,
+    /// Locked LP token address for JediSwap, position id for Ekubo.
+    pub locked_liquidity: Option<String>,
+    /// Team allocation, as a decimal string.
+    pub team_allocation: Option<String>,
+    pub transaction_hash: String,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// A memecoin as created by the factory, with its launch if any.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemecoinInfo {
+    pub memecoin_address: String,
+    pub owner: String,
+    pub name: String,
+    pub symbol: String,
+    /// Initial supply, as a decimal string.
+    pub initial_supply: String,
+    pub created_at: u64,
+    pub launch: Option<MemecoinLaunchedEvent>,
+}
+
+impl MemecoinInfo {
+    pub fn from_created(
+        event: &MemecoinCreatedEvent,
+        created_at: u64,
+        launch: Option<MemecoinLaunchedEvent>,
+    ) -> Self {
+        MemecoinInfo {
+            memecoin_address: event.memecoin_address.clone(),
+            owner: event.owner.clone(),
+            name: event.name.clone(),
+            symbol: event.symbol.clone(),
+            initial_supply: event.initial_supply.to_decimal(false),
+            created_at,
+            launch,
+        }
+    }
+}
+
+/// Transfer of a fungible token (memecoin), with the amount as a decimal string.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TokenTransfer {
+    pub token_address: String,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: String,
+    pub transaction_hash: String,
+    pub event_id: String,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// Tokens (usually LP tokens) locked in a lock manager until `unlock_time`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LiquidityLock {
+    pub lock_address: String,
+    pub token_address: String,
+    pub owner: String,
+    /// Locked amount, as a decimal string.
+    pub amount: String,
+    pub unlock_time: u64,
+    pub block_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyCriterionKind {
+    TeamAllocation,
+    LiquidityLock,
+    HolderConcentration,
+    OwnershipRenounced,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct SafetyCriterion {
+    pub kind: SafetyCriterionKind,
+    pub status: SafetyStatus,
+    pub reason: String,
+}
+
+/// Safety evaluation of a memecoin, with the reason of each criterion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct SafetyReport {
+    pub memecoin_address: String,
+    /// Score from 0 (unsafe) to 100.
+    pub score: u8,
+    pub criteria: Vec<SafetyCriterion>,
+    /// Block at which the report was computed.
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// A DEX pool in which a memecoin is traded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pool {
+    /// Pair address for JediSwap. Ekubo pools living in the core contract,
+    /// they are identified by the hash of their tokens.
+    pub pool_address: String,
+    pub exchange: LiquidityType,
+    pub memecoin_address: String,
+    pub quote_token: String,
+    pub token0: String,
+    pub token1: String,
+    pub block_timestamp: u64,
+}
+
+/// A swap on a memecoin pool, normalized across exchanges.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct SwapEvent {
+    pub event_id: String,
+    pub pool_address: String,
+    pub token_in: String,
+    pub token_out: String,
+    /// Amounts as decimal strings.
+    pub amount_in: String,
+    pub amount_out: String,
+    /// Recipient of the swap for JediSwap, usually a router. Empty for
+    /// Ekubo, whose swaps only expose the locker.
+    pub trader: String,
+    pub transaction_hash: String,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+pub enum CandleInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 4] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::OneDay,
+    ];
+
+    pub fn seconds(&self) -> u64 {
+        match self {
+            CandleInterval::OneMinute => 60,
+            CandleInterval::FiveMinutes => 300,
+            CandleInterval::OneHour => 3_600,
+            CandleInterval::OneDay => 86_400,
+        }
+    }
+
+    /// Returns the open time of the candle containing the timestamp.
+    pub fn open_time(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.seconds()
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CandleInterval::OneMinute => write!(f, "1m"),
+            CandleInterval::FiveMinutes => write!(f, "5m"),
+            CandleInterval::OneHour => write!(f, "1h"),
+            CandleInterval::OneDay => write!(f, "1d"),
+        }
+    }
+}
+
+impl FromStr for CandleInterval {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1m" => Ok(CandleInterval::OneMinute),
+            "5m" => Ok(CandleInterval::FiveMinutes),
+            "1h" => Ok(CandleInterval::OneHour),
+            "1d" => Ok(CandleInterval::OneDay),
+            _ => Err(()),
+        }
+    }
+}
+
+/// OHLCV candle of a memecoin, priced in the quote token of its pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct Candle {
+    pub memecoin_address: String,
+    pub interval: CandleInterval,
+    pub open_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded memecoin amount, as a decimal string.
+    pub volume_token: String,
+    /// Traded quote token amount, as a decimal string.
+    pub volume_quote: String,
+    pub trades: u64,
+    /// Close price in USD, 0 if the quote token price is unknown.
+    #[serde(default)]
+    pub close_usd: f64,
+    /// Traded volume in USD, for the trades whose quote token price is known.
+    #[serde(default)]
+    pub volume_usd: f64,
+    /// Timestamps of the first and last trades, the swaps being possibly
+    /// added out of order. 0 for the candles computed before.
+    #[serde(default)]
+    pub first_trade_at: u64,
+    #[serde(default)]
+    pub last_trade_at: u64,
+}
+
+/// Trending score of a memecoin over a rolling window, with the metrics
+/// it is computed from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendingScore {
+    pub memecoin_address: String,
+    /// Window duration, in seconds.
+    pub window: u64,
+    pub score: f64,
+    /// Swap volume in the quote tokens, as a decimal string.
+    pub volume_quote: String,
+    /// Swap volume in USD, from the swaps whose quote token is priced.
+    pub volume_usd: f64,
+    pub unique_buyers: u64,
+    pub new_holders: u64,
+    pub transfers: u64,
+    /// Block at which the score was computed.
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// Position of a wallet on a memecoin, valued in the quote token of its pool.
+/// Amounts are in the smallest units of the tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub wallet_address: String,
+    pub memecoin_address: String,
+    /// Balance, as a decimal string.
+    pub balance: String,
+    /// Average price paid for the memecoins still held, 0 if unknown.
+    pub average_cost: f64,
+    pub last_price: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    /// Value of the balance in USD, 0 if the memecoin price is unknown.
+    #[serde(default)]
+    pub value_usd: f64,
+}
+
+/// Positions of a wallet at the end of a day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub wallet_address: String,
+    /// Timestamp of the start of the day.
+    pub day: u64,
+    pub positions: Vec<Position>,
+}
+
+/// A wallet having received a memecoin right after its launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sniper {
+    pub wallet_address: String,
+    /// Sender of the first funding of the wallet, if known.
+    pub funder_address: Option<String>,
+    /// Amount received during the launch window, as a decimal string.
+    pub received: String,
+    /// Current balance, as a decimal string.
+    pub balance: String,
+}
+
+/// Wallets having received a memecoin in the first blocks after its launch,
+/// and the share of the supply they still hold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SniperReport {
+    pub memecoin_address: String,
+    pub launch_block_number: u64,
+    /// Number of blocks, from the launch block included, of the window.
+    pub window_blocks: u64,
+    pub snipers: Vec<Sniper>,
+    /// Snipers funded from the same source, with at least 2 wallets each.
+    pub clusters: Vec<Vec<String>>,
+    pub snipers_supply_percent: f64,
+    /// Share of the supply held by the clustered snipers.
+    pub bundled_supply_percent: f64,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeveloperRole {
+    /// Owner of the memecoin, as given at its creation.
+    Deployer,
+    /// Recipient of a part of the initial supply.
+    TeamAllocation,
+}
+
+impl ToString for DeveloperRole {
+    fn to_string(&self) -> String {
+        match self {
+            DeveloperRole::Deployer => "DEPLOYER".to_string(),
+            DeveloperRole::TeamAllocation => "TEAM_ALLOCATION".to_string(),
+        }
+    }
+}
+
+impl FromStr for DeveloperRole {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "DEPLOYER" => Ok(DeveloperRole::Deployer),
+            "TEAM_ALLOCATION" => Ok(DeveloperRole::TeamAllocation),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A wallet of the team behind a memecoin. A wallet may have both roles.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeveloperWallet {
+    pub memecoin_address: String,
+    pub wallet_address: String,
+    pub role: DeveloperRole,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeveloperActivityKind {
+    Transfer,
+    /// Transfer to a pool of the memecoin.
+    Sell,
+}
+
+impl ToString for DeveloperActivityKind {
+    fn to_string(&self) -> String {
+        match self {
+            DeveloperActivityKind::Transfer => "TRANSFER".to_string(),
+            DeveloperActivityKind::Sell => "SELL".to_string(),
+        }
+    }
+}
+
+impl FromStr for DeveloperActivityKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TRANSFER" => Ok(DeveloperActivityKind::Transfer),
+            "SELL" => Ok(DeveloperActivityKind::Sell),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Supply and USD valuation of a memecoin. USD values are 0 if the
+/// memecoin price is unknown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct MarketMetrics {
+    pub memecoin_address: String,
+    /// Initial supply minus the burned memecoins, as a decimal string.
+    pub total_supply: String,
+    /// Total supply minus the memecoins held by the memecoin itself, the
+    /// locks and the developer wallets, as a decimal string.
+    pub circulating_supply: String,
+    pub price_usd: f64,
+    pub market_cap_usd: f64,
+    /// Fully diluted valuation, from the total supply.
+    pub fdv_usd: f64,
+    /// Value of both sides of the pools of the memecoin.
+    pub liquidity_usd: f64,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// Memecoins moved out of a developer wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeveloperActivity {
+    pub memecoin_address: String,
+    pub wallet_address: String,
+    pub role: DeveloperRole,
+    pub kind: DeveloperActivityKind,
+    pub to_address: String,
+    /// Amount, as a decimal string.
+    pub amount: String,
+    pub transaction_hash: String,
+    /// Id of the transfer event.
+    pub event_id: String,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+}
+
+/// Step of the indexation at which an event failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventFailureKind {
+    MemecoinEvent,
+    SwapEvent,
+    ContractIdentification,
+    EventRegistration,
+    TokenRegistration,
+}
+
+impl ToString for EventFailureKind {
+    fn to_string(&self) -> String {
+        match self {
+            EventFailureKind::MemecoinEvent => "MEMECOIN_EVENT".to_string(),
+            EventFailureKind::SwapEvent => "SWAP_EVENT".to_string(),
+            EventFailureKind::ContractIdentification => "CONTRACT_IDENTIFICATION".to_string(),
+            EventFailureKind::EventRegistration => "EVENT_REGISTRATION".to_string(),
+            EventFailureKind::TokenRegistration => "TOKEN_REGISTRATION".to_string(),
+        }
+    }
+}
+
+impl FromStr for EventFailureKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "MEMECOIN_EVENT" => Ok(EventFailureKind::MemecoinEvent),
+            "SWAP_EVENT" => Ok(EventFailureKind::SwapEvent),
+            "CONTRACT_IDENTIFICATION" => Ok(EventFailureKind::ContractIdentification),
+            "EVENT_REGISTRATION" => Ok(EventFailureKind::EventRegistration),
+            "TOKEN_REGISTRATION" => Ok(EventFailureKind::TokenRegistration),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailedEventStatus {
+    /// To be replayed.
+    Pending,
+    Resolved,
+    /// Failed too many times, not replayed anymore.
+    Failed,
+}
+
+impl ToString for FailedEventStatus {
+    fn to_string(&self) -> String {
+        match self {
+            FailedEventStatus::Pending => "PENDING".to_string(),
+            FailedEventStatus::Resolved => "RESOLVED".to_string(),
+            FailedEventStatus::Failed => "FAILED".to_string(),
+        }
+    }
+}
+
+impl FromStr for FailedEventStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(FailedEventStatus::Pending),
+            "RESOLVED" => Ok(FailedEventStatus::Resolved),
+            "FAILED" => Ok(FailedEventStatus::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An event which couldn't be indexed, kept in the dead-letter store
+/// to be replayed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedEvent {
+    /// Hash of the event content, identifying it in its transaction.
+    pub failure_id: String,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub transaction_hash: String,
+    /// Emitter of the event.
+    pub contract_address: String,
+    pub kind: EventFailureKind,
+    /// Error of the last attempt.
+    pub error: IndexingError,
+    /// Number of failed attempts, the first indexation included.
+    pub attempts: u32,
+    pub status: FailedEventStatus,
+}
+
+/// A failed request to the Starknet node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    /// Name of the client method, like `block_time`.
+    pub method: String,
+    /// Block of the request, if any.
+    pub block_number: Option<u64>,
+    pub error: IndexingError,
+}
+
+/// Error while indexing, keeping the kind of its source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum IndexingError {
+    /// A contract call failed.
+    Contract(String),
+    EntrypointNotFound(String),
+    InputTooLong,
+    /// Any other error of the Starknet client, like an unavailable node.
+    Starknet(String),
+    Storage(String),
+    /// The event can't be decoded or processed.
+    Other(String),
+}
+
+impl From<&StarknetClientError> for IndexingError {
+    fn from(e: &StarknetClientError) -> Self {
+        match e {
+            StarknetClientError::Contract(s) => IndexingError::Contract(s.clone()),
+            StarknetClientError::EntrypointNotFound(s) => {
+                IndexingError::EntrypointNotFound(s.clone())
+            }
+            StarknetClientError::InputTooLong => IndexingError::InputTooLong,
+            e => IndexingError::Starknet(e.to_string()),
+        }
+    }
+}
+
+impl From<&StorageError> for IndexingError {
+    fn from(e: &StorageError) -> Self {
+        IndexingError::Storage(e.to_string())
+    }
+}
+
+impl From<&anyhow::Error> for IndexingError {
+    fn from(e: &anyhow::Error) -> Self {
+        if let Some(e) = e.downcast_ref::<StarknetClientError>() {
+            e.into()
+        } else if let Some(e) = e.downcast_ref::<StorageError>() {
+            e.into()
+        } else {
+            IndexingError::Other(e.to_string())
+        }
+    }
+}
+
+impl fmt::Display for IndexingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexingError::Contract(s) => write!(f, "Contract error: {s}"),
+            IndexingError::EntrypointNotFound(s) => write!(f, "Entrypoint not found: {s}"),
+            IndexingError::InputTooLong => write!(f, "Input too long"),
+            IndexingError::Starknet(s) => write!(f, "Starknet error: {s}"),
+            IndexingError::Storage(s) => write!(f, "Storage error: {s}"),
+            IndexingError::Other(s) => write!(f, "{s}"),
+        }
+    }
+}
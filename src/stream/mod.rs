@@ -0,0 +1,276 @@
+//! Live streaming of the indexed data to WebSocket and Server-Sent Events
+//! subscribers, enabled with the `stream` feature.
+//!
+//! The `Broadcaster` is built on the receiver of `Pontos::subscribe`, the
+//! event handler of Pontos being left to the application. It fans out the
+//! registered events and tokens, the memecoin launches and the processed
+//! blocks. Each subscriber has its own filter. A subscriber too slow to
+//! keep up with the indexer never blocks it: the oldest events are dropped,
+//! and the subscriber receives a `lagged` message with the number of
+//! skipped ones.
+use crate::event_handler::PontosEvent;
+use crate::storage::types::{EventType, MemecoinLaunchedEvent, TokenEvent, TokenInfo};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+/// Message sent to the subscribers, for the streamed Pontos events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Event(TokenEvent),
+    Token(TokenInfo),
+    MemecoinLaunched(MemecoinLaunchedEvent),
+    Block {
+        block_number: u64,
+    },
+    /// Messages skipped because the subscriber was too slow.
+    Lagged {
+        skipped: u64,
+    },
+}
+
+impl StreamMessage {
+    /// Returns the message of the event, `None` if it's not streamed.
+    pub fn from_event(event: PontosEvent) -> Option<Self> {
+        match event {
+            PontosEvent::EventRegistered(e) => Some(StreamMessage::Event(e)),
+            PontosEvent::TokenRegistered(t) => Some(StreamMessage::Token(t)),
+            PontosEvent::MemecoinLaunched(l) => Some(StreamMessage::MemecoinLaunched(l)),
+            PontosEvent::BlockProcessed { block_number, .. } => {
+                Some(StreamMessage::Block { block_number })
+            }
+            _ => None,
+        }
+    }
+
+    /// Name of the message, used as SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamMessage::Event(_) => "event",
+            StreamMessage::Token(_) => "token",
+            StreamMessage::MemecoinLaunched(_) => "memecoin_launched",
+            StreamMessage::Block { .. } => "block",
+            StreamMessage::Lagged { .. } => "lagged",
+        }
+    }
+}
+
+/// Criteria of a subscription, all the given ones having to match.
+/// Block and lag notifications are always sent.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct StreamFilter {
+    /// Contract of the events and tokens, or launched memecoin.
+    pub contract_address: Option<String>,
+    /// Sender or recipient of the events, or owner of the tokens.
+    pub wallet: Option<String>,
+    /// Type of the events, excluding the other messages.
+    pub event_type: Option<EventType>,
+}
+
+impl StreamFilter {
+    pub fn matches(&self, message: &StreamMessage) -> bool {
+        let contract = |address: &str| {
+            self.contract_address
+                .as_ref()
+                .map_or(true, |a| *a == address)
+        };
+
+        match message {
+            StreamMessage::Event(e) => {
+                contract(&e.contract_address)
+                    && self
+                        .wallet
+                        .as_ref()
+                        .map_or(true, |w| *w == e.from_address || *w == e.to_address)
+                    && self
+                        .event_type
+                        .as_ref()
+                        .map_or(true, |t| *t == e.event_type)
+            }
+            StreamMessage::Token(t) => {
+                contract(&t.contract_address)
+                    && self.wallet.as_ref().map_or(true, |w| *w == t.owner)
+                    && self.event_type.is_none()
+            }
+            StreamMessage::MemecoinLaunched(l) => {
+                contract(&l.memecoin_address) && self.wallet.is_none() && self.event_type.is_none()
+            }
+            StreamMessage::Block { .. } | StreamMessage::Lagged { .. } => true,
+        }
+    }
+}
+
+/// Subscriptions to the events of Pontos. Clones share the same events.
+#[derive(Debug, Clone)]
+pub struct Broadcaster {
+    /// Receiver of `Pontos::subscribe`, only used to subscribe again.
+    events: Arc<broadcast::Receiver<PontosEvent>>,
+}
+
+impl Broadcaster {
+    /// Initializes a new instance over the events of `Pontos::subscribe`.
+    pub fn new(events: broadcast::Receiver<PontosEvent>) -> Self {
+        Self {
+            events: Arc::new(events),
+        }
+    }
+
+    /// Subscribes to the events sent from now on.
+    pub fn subscribe(&self, filter: StreamFilter) -> Subscription {
+        Subscription {
+            receiver: self.events.resubscribe(),
+            filter,
+        }
+    }
+}
+
+pub struct Subscription {
+    receiver: broadcast::Receiver<PontosEvent>,
+    filter: StreamFilter,
+}
+
+impl Subscription {
+    /// Returns the next matching message, or `None` once Pontos
+    /// is dropped.
+    pub async fn recv(&mut self) -> Option<StreamMessage> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => match StreamMessage::from_event(event) {
+                    Some(message) if self.filter.matches(&message) => return Some(message),
+                    _ => continue,
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Slow subscriber skipped {} messages", skipped);
+                    return Some(StreamMessage::Lagged { skipped });
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = StreamMessage> {
+        futures::stream::unfold(self, |mut s| async move { s.recv().await.map(|m| (m, s)) })
+    }
+}
+
+/// Returns the router serving the subscriptions on `/stream/ws` and
+/// `/stream/sse`, filtered by the query parameters.
+pub fn router(broadcaster: Broadcaster) -> Router {
+    Router::new()
+        .route("/stream/ws", get(websocket))
+        .route("/stream/sse", get(sse))
+        .with_state(broadcaster)
+}
+
+async fn websocket(
+    State(broadcaster): State<Broadcaster>,
+    Query(filter): Query<StreamFilter>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let subscription = broadcaster.subscribe(filter);
+    upgrade.on_upgrade(move |socket| forward(socket, subscription))
+}
+
+/// Sends the messages to the socket until the client disconnects.
+async fn forward(mut socket: WebSocket, mut subscription: Subscription) {
+    while let Some(message) = subscription.recv().await {
+        let text = match serde_json::to_string(&message) {
+            Ok(t) => t,
+            Err(e) => {
+                warn!("Can't serialize {:?}: {}", message, e);
+                continue;
+            }
+        };
+
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+    debug!("WebSocket subscriber disconnected");
+}
+
+async fn sse(
+    State(broadcaster): State<Broadcaster>,
+    Query(filter): Query<StreamFilter>,
+) -> impl IntoResponse {
+    let stream = broadcaster
+        .subscribe(filter)
+        .into_stream()
+        .map(|m| Event::default().event(m.name()).json_data(&m));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(contract_address: &str, to_address: &str, event_type: EventType) -> TokenEvent {
+        TokenEvent {
+            contract_address: contract_address.to_string(),
+            to_address: to_address.to_string(),
+            event_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = StreamFilter {
+            contract_address: Some("0x1".to_string()),
+            wallet: Some("0xa".to_string()),
+            event_type: Some(EventType::Mint),
+        };
+
+        let matching = event("0x1", "0xa", EventType::Mint);
+        assert!(filter.matches(&StreamMessage::Event(matching)));
+        let other_wallet = event("0x1", "0xb", EventType::Mint);
+        assert!(!filter.matches(&StreamMessage::Event(other_wallet)));
+        let other_type = event("0x1", "0xa", EventType::Transfer);
+        assert!(!filter.matches(&StreamMessage::Event(other_type)));
+        assert!(!filter.matches(&StreamMessage::Token(TokenInfo::default())));
+        assert!(filter.matches(&StreamMessage::Block { block_number: 1 }));
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber() {
+        let (sender, events) = broadcast::channel(2);
+        let broadcaster = Broadcaster::new(events);
+        let mut subscription = broadcaster.subscribe(StreamFilter::default());
+
+        for block_number in 0..5 {
+            sender
+                .send(PontosEvent::BlockProcessed {
+                    block_number,
+                    indexation_progress: 0.0,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            subscription.recv().await,
+            Some(StreamMessage::Lagged { skipped: 3 })
+        );
+        assert_eq!(
+            subscription.recv().await,
+            Some(StreamMessage::Block { block_number: 3 })
+        );
+        assert_eq!(
+            subscription.recv().await,
+            Some(StreamMessage::Block { block_number: 4 })
+        );
+
+        // Events not streamed are skipped.
+        sender.send(PontosEvent::IndexationRangeCompleted).unwrap();
+        drop(sender);
+        assert_eq!(subscription.recv().await, None);
+    }
+}
@@ -0,0 +1,452 @@
+//! Synthetic chain served by an in-process `StarknetClient`.
+use crate::client::fixture::{parse_block_id, RecordedError};
+use crate::client::ClassHashProvider;
+use ark_starknet::client::{FetchEventsResult, StarknetClient, StarknetClientError};
+use async_trait::async_trait;
+use starknet::core::types::{BlockId, BlockTag, EmittedEvent, FieldElement};
+use starknet::core::utils::{cairo_short_string_to_felt, get_selector_from_name};
+use starknet::macros::selector;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock as AsyncRwLock;
+
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticBlock {
+    pub timestamp: u64,
+    pub events: Vec<EmittedEvent>,
+}
+
+/// Description of a chain: blocks, events and contracts responses.
+///
+/// Events can only be added to a block already declared with `block`.
+/// Any contract call without a registered response fails with
+/// `EntrypointNotFound`, as a contract without this entrypoint would.
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticChain {
+    blocks: BTreeMap<u64, SyntheticBlock>,
+    calls: HashMap<(FieldElement, FieldElement), Result<Vec<FieldElement>, RecordedError>>,
+    class_hashes: HashMap<FieldElement, FieldElement>,
+    pending: Vec<(u64, Vec<FieldElement>)>,
+}
+
+impl SyntheticChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block(mut self, number: u64, timestamp: u64) -> Self {
+        self.blocks.insert(
+            number,
+            SyntheticBlock {
+                timestamp,
+                events: vec![],
+            },
+        );
+        self
+    }
+
+    /// Adds a raw event to the given block. The transaction hash
+    /// is derived from the block number and the event position.
+    pub fn event(
+        mut self,
+        block_number: u64,
+        from_address: FieldElement,
+        keys: Vec<FieldElement>,
+        data: Vec<FieldElement>,
+    ) -> Self {
+        let block = self
+            .blocks
+            .get_mut(&block_number)
+            .expect("Block must be declared before adding events");
+
+        let transaction_hash =
+            FieldElement::from(block_number * 10_000 + block.events.len() as u64 + 1);
+
+        block.events.push(EmittedEvent {
+            from_address,
+            keys,
+            data,
+            block_hash: FieldElement::from(block_number),
+            block_number,
+            transaction_hash,
+        });
+        self
+    }
+
+    /// Adds a `Transfer` event, with the u256 value (token id or amount) in data.
+    pub fn transfer(
+        self,
+        block_number: u64,
+        contract: FieldElement,
+        from: FieldElement,
+        to: FieldElement,
+        value: u128,
+    ) -> Self {
+        self.event(
+            block_number,
+            contract,
+            vec![selector!("Transfer")],
+            vec![from, to, FieldElement::from(value), FieldElement::ZERO],
+        )
+    }
+
+    /// Adds a `MemecoinCreated` event emitted by the given factory.
+    #[allow(clippy::too_many_arguments)]
+    pub fn memecoin_created(
+        self,
+        block_number: u64,
+        factory: FieldElement,
+        owner: FieldElement,
+        name: &str,
+        symbol: &str,
+        initial_supply: u128,
+        memecoin: FieldElement,
+    ) -> Self {
+        self.event(
+            block_number,
+            factory,
+            vec![selector!("MemecoinCreated")],
+            vec![
+                owner,
+                cairo_short_string_to_felt(name).expect("Invalid short string"),
+                cairo_short_string_to_felt(symbol).expect("Invalid short string"),
+                FieldElement::from(initial_supply),
+                FieldElement::ZERO,
+                memecoin,
+            ],
+        )
+    }
+
+    /// Adds a `MemecoinLaunched` event emitted by the given factory.
+    pub fn memecoin_launched(
+        self,
+        block_number: u64,
+        factory: FieldElement,
+        memecoin: FieldElement,
+        quote_token: FieldElement,
+        exchange_name: &str,
+    ) -> Self {
+        self.event(
+            block_number,
+            factory,
+            vec![selector!("MemecoinLaunched")],
+            vec![
+                memecoin,
+                quote_token,
+                cairo_short_string_to_felt(exchange_name).expect("Invalid short string"),
+            ],
+        )
+    }
+
+    /// Adds a JediSwap `Swap` event, where the trader receives the output.
+    pub fn jediswap_swap(
+        self,
+        block_number: u64,
+        pair: FieldElement,
+        amounts_in: (u128, u128),
+        amounts_out: (u128, u128),
+        to: FieldElement,
+    ) -> Self {
+        self.event(
+            block_number,
+            pair,
+            vec![selector!("Swap")],
+            vec![
+                to,
+                FieldElement::from(amounts_in.0),
+                FieldElement::ZERO,
+                FieldElement::from(amounts_in.1),
+                FieldElement::ZERO,
+                FieldElement::from(amounts_out.0),
+                FieldElement::ZERO,
+                FieldElement::from(amounts_out.1),
+                FieldElement::ZERO,
+                to,
+            ],
+        )
+    }
+
+    /// Registers the response of `selector_name` for the contract,
+    /// whatever the calldata and the block.
+    pub fn call_response(
+        mut self,
+        contract: FieldElement,
+        selector_name: &str,
+        response: Result<Vec<FieldElement>, RecordedError>,
+    ) -> Self {
+        let selector = get_selector_from_name(selector_name).expect("Invalid selector");
+        self.calls.insert((contract, selector), response);
+        self
+    }
+
+    /// Makes the contract answer as an ERC721 where all tokens belong to `owner`.
+    pub fn erc721(self, contract: FieldElement, owner: FieldElement) -> Self {
+        self.call_response(contract, "ownerOf", Ok(vec![owner]))
+            .call_response(contract, "owner_of", Ok(vec![owner]))
+    }
+
+    /// Makes the contract answer as an ERC1155.
+    pub fn erc1155(self, contract: FieldElement) -> Self {
+        let balance = vec![FieldElement::ONE, FieldElement::ZERO];
+        self.call_response(contract, "balanceOf", Ok(balance.clone()))
+            .call_response(contract, "balance_of", Ok(balance))
+    }
+
+    /// Declares the class of the contract.
+    pub fn class_hash(mut self, contract: FieldElement, class_hash: FieldElement) -> Self {
+        self.class_hashes.insert(contract, class_hash);
+        self
+    }
+
+    /// Adds a state of the pending block, returned in order by
+    /// successive `block_txs_hashes` calls on the pending tag.
+    pub fn pending(mut self, timestamp: u64, txs_hashes: Vec<FieldElement>) -> Self {
+        self.pending.push((timestamp, txs_hashes));
+        self
+    }
+
+    fn latest(&self) -> u64 {
+        self.blocks.keys().last().copied().unwrap_or(0)
+    }
+}
+
+/// A request that can be made to fail on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailurePoint {
+    BlockTime(u64),
+    BlockEvents(u64),
+    EventsPage(u64),
+    Call(FieldElement),
+}
+
+pub struct SyntheticClient {
+    chain: SyntheticChain,
+    failures: AsyncRwLock<HashMap<FailurePoint, u32>>,
+    pending_cursor: AsyncRwLock<usize>,
+}
+
+impl SyntheticClient {
+    pub fn from_chain(chain: SyntheticChain) -> Self {
+        SyntheticClient {
+            chain,
+            failures: AsyncRwLock::new(HashMap::new()),
+            pending_cursor: AsyncRwLock::new(0),
+        }
+    }
+
+    /// The next `times` requests matching the failure point
+    /// will return an error.
+    pub async fn inject_failure(&self, point: FailurePoint, times: u32) {
+        self.failures.write().await.insert(point, times);
+    }
+
+    async fn check_failure(&self, point: FailurePoint) -> Result<(), StarknetClientError> {
+        let mut failures = self.failures.write().await;
+        match failures.get_mut(&point) {
+            Some(remaining) if *remaining > 0 => {
+                *remaining -= 1;
+                Err(StarknetClientError::Other(format!(
+                    "Injected failure: {:?}",
+                    point
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve(&self, block: &BlockId) -> Result<u64, StarknetClientError> {
+        match block {
+            BlockId::Number(n) => Ok(*n),
+            BlockId::Tag(BlockTag::Latest) => Ok(self.chain.latest()),
+            BlockId::Tag(BlockTag::Pending) => Ok(self.chain.latest() + 1),
+            BlockId::Hash(h) => Err(StarknetClientError::Other(format!(
+                "Block hash not supported: {:#064x}",
+                h
+            ))),
+        }
+    }
+
+    fn get_block(&self, number: u64) -> Result<&SyntheticBlock, StarknetClientError> {
+        self.chain
+            .blocks
+            .get(&number)
+            .ok_or_else(|| StarknetClientError::Other(format!("Block not found: {}", number)))
+    }
+
+    fn matching_events(
+        block: &SyntheticBlock,
+        keys: &Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+    ) -> Vec<EmittedEvent> {
+        block
+            .events
+            .iter()
+            .filter(|e| address.map_or(true, |a| e.from_address == a))
+            .filter(|e| match keys.as_ref().and_then(|k| k.first()) {
+                Some(selectors) if !selectors.is_empty() => selectors.contains(&e.keys[0]),
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl StarknetClient for SyntheticClient {
+    fn new(_rpc_url: &str) -> Result<Self, StarknetClientError>
+    where
+        Self: Sized,
+    {
+        Ok(SyntheticClient::from_chain(SyntheticChain::new()))
+    }
+
+    async fn block_txs_hashes(
+        &self,
+        block: BlockId,
+    ) -> Result<(u64, Vec<FieldElement>), StarknetClientError> {
+        if let BlockId::Tag(BlockTag::Pending) = block {
+            let mut cursor = self.pending_cursor.write().await;
+            let state = self
+                .chain
+                .pending
+                .get(*cursor)
+                .or_else(|| self.chain.pending.last())
+                .cloned()
+                .ok_or_else(|| StarknetClientError::Other("No pending block".to_string()))?;
+
+            *cursor += 1;
+            return Ok(state);
+        }
+
+        let b = self.get_block(self.resolve(&block)?)?;
+        Ok((
+            b.timestamp,
+            b.events.iter().map(|e| e.transaction_hash).collect(),
+        ))
+    }
+
+    async fn block_time(&self, block: BlockId) -> Result<u64, StarknetClientError> {
+        let number = self.resolve(&block)?;
+        self.check_failure(FailurePoint::BlockTime(number)).await?;
+        Ok(self.get_block(number)?.timestamp)
+    }
+
+    async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64, StarknetClientError> {
+        self.resolve(id)
+    }
+
+    fn parse_block_range(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(BlockId, BlockId), StarknetClientError> {
+        Ok((parse_block_id(from)?, parse_block_id(to)?))
+    }
+
+    fn parse_block_id(&self, id: &str) -> Result<BlockId, StarknetClientError> {
+        parse_block_id(id)
+    }
+
+    async fn block_number(&self) -> Result<u64, StarknetClientError> {
+        Ok(self.chain.latest())
+    }
+
+    /// Returns one page per block of the range.
+    async fn fetch_events(
+        &self,
+        from_block: Option<BlockId>,
+        to_block: Option<BlockId>,
+        keys: Option<Vec<Vec<FieldElement>>>,
+        address: Option<FieldElement>,
+        continuation_token: Option<String>,
+    ) -> Result<FetchEventsResult, StarknetClientError> {
+        let from = match continuation_token {
+            Some(t) => t
+                .parse::<u64>()
+                .map_err(|_| StarknetClientError::Other(format!("Invalid token: {}", t)))?,
+            None => match from_block {
+                Some(b) => self.resolve(&b)?,
+                None => 0,
+            },
+        };
+        let to = match to_block {
+            Some(b) => self.resolve(&b)?,
+            None => self.chain.latest(),
+        };
+
+        self.check_failure(FailurePoint::EventsPage(from)).await?;
+
+        let mut events = HashMap::new();
+        if let Some(b) = self.chain.blocks.get(&from) {
+            let matching = Self::matching_events(b, &keys, address);
+            if !matching.is_empty() {
+                events.insert(from, matching);
+            }
+        }
+
+        Ok(FetchEventsResult {
+            events,
+            continuation_token: if from < to {
+                Some((from + 1).to_string())
+            } else {
+                None
+            },
+        })
+    }
+
+    async fn fetch_all_block_events(
+        &self,
+        block: BlockId,
+        keys: Option<Vec<Vec<FieldElement>>>,
+    ) -> Result<HashMap<u64, Vec<EmittedEvent>>, StarknetClientError> {
+        let number = self.resolve(&block)?;
+        self.check_failure(FailurePoint::BlockEvents(number))
+            .await?;
+
+        let mut events = HashMap::new();
+        events.insert(
+            number,
+            Self::matching_events(self.get_block(number)?, &keys, None),
+        );
+        Ok(events)
+    }
+
+    async fn call_contract(
+        &self,
+        contract_address: FieldElement,
+        selector: FieldElement,
+        _calldata: Vec<FieldElement>,
+        _block: BlockId,
+    ) -> Result<Vec<FieldElement>, StarknetClientError> {
+        self.check_failure(FailurePoint::Call(contract_address))
+            .await?;
+
+        match self.chain.calls.get(&(contract_address, selector)) {
+            Some(Ok(r)) => Ok(r.clone()),
+            Some(Err(e)) => Err(e.clone().into()),
+            None => Err(StarknetClientError::EntrypointNotFound(format!(
+                "{:#064x}",
+                selector
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl ClassHashProvider for SyntheticClient {
+    async fn class_hash_at(
+        &self,
+        contract_address: FieldElement,
+        _block: BlockId,
+    ) -> Result<FieldElement, StarknetClientError> {
+        self.chain
+            .class_hashes
+            .get(&contract_address)
+            .copied()
+            .ok_or_else(|| {
+                StarknetClientError::Other(format!(
+                    "Contract not found: {:#064x}",
+                    contract_address
+                ))
+            })
+    }
+}
@@ -0,0 +1,113 @@
+use crate::event_handler::EventHandler;
+use crate::storage::types::{
+    DeveloperActivity, FailedEvent, IndexingError, MemecoinCreatedEvent, MemecoinLaunchedEvent,
+    RpcError, SafetyReport, SwapEvent, TokenEvent, TokenInfo,
+};
+use async_trait::async_trait;
+use tokio::sync::RwLock as AsyncRwLock;
+
+/// A call received by the `RecordingEventHandler`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HandlerCall {
+    BlockProcessed(u64, f64),
+    BlockProcessing(u64, Option<u64>),
+    IndexationRangeCompleted,
+    TokenRegistered(TokenInfo),
+    EventRegistered(TokenEvent),
+    NewLatestBlock(u64),
+    /// Address of the created memecoin.
+    MemecoinCreated(String),
+    MemecoinLaunched(MemecoinLaunchedEvent),
+    SafetyReportUpdated(SafetyReport),
+    SwapRegistered(SwapEvent),
+    DeveloperActivity(DeveloperActivity),
+    EventFailed(FailedEvent),
+    BlockSkipped(u64, IndexingError),
+    RpcError(RpcError),
+}
+
+/// Event handler keeping every call, in order.
+#[derive(Debug, Default)]
+pub struct RecordingEventHandler {
+    calls: AsyncRwLock<Vec<HandlerCall>>,
+}
+
+impl RecordingEventHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn calls(&self) -> Vec<HandlerCall> {
+        self.calls.read().await.clone()
+    }
+
+    async fn push(&self, call: HandlerCall) {
+        self.calls.write().await.push(call);
+    }
+}
+
+#[async_trait]
+impl EventHandler for RecordingEventHandler {
+    async fn on_block_processed(&self, block_number: u64, indexation_progress: f64) {
+        self.push(HandlerCall::BlockProcessed(
+            block_number,
+            indexation_progress,
+        ))
+        .await;
+    }
+
+    async fn on_block_processing(&self, block_timestamp: u64, block_number: Option<u64>) {
+        self.push(HandlerCall::BlockProcessing(block_timestamp, block_number))
+            .await;
+    }
+
+    async fn on_indexation_range_completed(&self) {
+        self.push(HandlerCall::IndexationRangeCompleted).await;
+    }
+
+    async fn on_token_registered(&self, token: TokenInfo) {
+        self.push(HandlerCall::TokenRegistered(token)).await;
+    }
+
+    async fn on_event_registered(&self, event: TokenEvent) {
+        self.push(HandlerCall::EventRegistered(event)).await;
+    }
+
+    async fn on_new_latest_block(&self, block_number: u64) {
+        self.push(HandlerCall::NewLatestBlock(block_number)).await;
+    }
+
+    async fn on_memecoin_created(&self, event: MemecoinCreatedEvent) {
+        self.push(HandlerCall::MemecoinCreated(event.memecoin_address))
+            .await;
+    }
+
+    async fn on_memecoin_launched(&self, event: MemecoinLaunchedEvent) {
+        self.push(HandlerCall::MemecoinLaunched(event)).await;
+    }
+
+    async fn on_safety_report_updated(&self, report: SafetyReport) {
+        self.push(HandlerCall::SafetyReportUpdated(report)).await;
+    }
+
+    async fn on_swap_registered(&self, swap: SwapEvent) {
+        self.push(HandlerCall::SwapRegistered(swap)).await;
+    }
+
+    async fn on_developer_activity(&self, activity: DeveloperActivity) {
+        self.push(HandlerCall::DeveloperActivity(activity)).await;
+    }
+
+    async fn on_event_failed(&self, failure: FailedEvent) {
+        self.push(HandlerCall::EventFailed(failure)).await;
+    }
+
+    async fn on_block_skipped(&self, block_number: u64, error: IndexingError) {
+        self.push(HandlerCall::BlockSkipped(block_number, error))
+            .await;
+    }
+
+    async fn on_rpc_error(&self, error: RpcError) {
+        self.push(HandlerCall::RpcError(error)).await;
+    }
+}
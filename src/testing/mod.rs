@@ -0,0 +1,32 @@
+//! In-process building blocks to test Pontos end-to-end
+//! without any node or database.
+//!
+//! A `SyntheticChain` describes blocks and their events, and is served
+//! by a `SyntheticClient`. The effects of the indexation can then be
+//! asserted on a `MemoryStorage` and a `RecordingEventHandler`.
+pub mod chain;
+pub use chain::{FailurePoint, SyntheticChain, SyntheticClient};
+
+pub mod handler;
+pub use handler::{HandlerCall, RecordingEventHandler};
+
+pub mod recorded;
+
+pub mod storage;
+pub use storage::{MemoryData, MemoryStorage};
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Returns a path in the temporary directory unique to this call,
+/// so that concurrent test runs don't share files.
+pub fn unique_temp_path(name: &str) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!(
+        "tiny_stark_{}_{}_{}",
+        std::process::id(),
+        count,
+        name
+    ))
+}
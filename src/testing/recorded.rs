@@ -0,0 +1,52 @@
+//! Blocks recorded from a node, indexed again offline as a regression
+//! suite of the decoding of real events.
+//!
+//! The fixture is recorded by `examples/record_fixture.rs`, indexing the
+//! blocks with `config()` through a `RecordingClient`. Replaying it with
+//! the same configuration makes the same requests.
+use crate::analysis::{PricingConfig, SniperConfig, TrendingConfig};
+use crate::client::Fixture;
+use crate::health::HealthConfig;
+use crate::managers::swap_manager::EKUBO_CORE_ADDRESS;
+use crate::managers::{ContractCacheConfig, DEFAULT_MAX_REPLAY_ATTEMPTS};
+use crate::PontosConfig;
+
+/// Mainnet blocks with a memecoin created and launched, a swap
+/// and a transfer.
+pub const MAINNET_FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/mainnet_memecoin.json"
+);
+
+/// Configuration of the recording and of the replay.
+pub fn config() -> PontosConfig {
+    PontosConfig {
+        indexer_version: "0.0.1".to_string(),
+        indexer_identifier: "fixture".to_string(),
+        contract_cache: ContractCacheConfig::default(),
+        // The memecoin events of any factory.
+        memecoin_factories: vec![],
+        lock_managers: vec![],
+        ekubo_core: EKUBO_CORE_ADDRESS,
+        trending: TrendingConfig {
+            update_interval: 0,
+            ..Default::default()
+        },
+        sniper: SniperConfig::default(),
+        pricing: PricingConfig::default(),
+        max_replay_attempts: DEFAULT_MAX_REPLAY_ATTEMPTS,
+        health: HealthConfig::default(),
+    }
+}
+
+/// Returns the first and last recorded blocks, `None` if the
+/// fixture has no block.
+pub fn block_range(fixture: &Fixture) -> Option<(u64, u64)> {
+    let blocks: Vec<u64> = fixture
+        .block_times
+        .keys()
+        .filter_map(|k| k.parse().ok())
+        .collect();
+
+    Some((*blocks.iter().min()?, *blocks.iter().max()?))
+}
@@ -0,0 +1,1073 @@
+//! Storage keeping everything in memory, with the same
+//! semantic as the default sqlx storage.
+use crate::storage::types::{
+    BlockInfo, Candle, CandleInterval, ClassInfo, ContractInfo, ContractType, DeveloperActivity,
+    DeveloperWallet, EventFilter, FailedEvent, FailedEventStatus, LiquidityLock, MarketMetrics,
+    MemecoinCreatedEvent, MemecoinFilter, MemecoinInfo, MemecoinLaunchedEvent, Pagination, Pool,
+    PortfolioSnapshot, SafetyReport, SniperReport, StorageError, SwapEvent, TokenEvent,
+    TokenFilter, TokenInfo, TokenMintInfo, TokenTransfer, TrendingScore,
+};
+use crate::storage::Storage;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use tokio::sync::RwLock as AsyncRwLock;
+
+#[derive(Debug, Clone)]
+pub struct StoredToken {
+    pub info: TokenInfo,
+    pub mint: Option<TokenMintInfo>,
+    pub block_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryData {
+    /// Tokens, keyed by (contract address, token id hex).
+    pub tokens: BTreeMap<(String, String), StoredToken>,
+    /// Events with their block timestamp, keyed by event id.
+    pub events: BTreeMap<String, (TokenEvent, u64)>,
+    /// Contracts with their block timestamp, keyed by address.
+    pub contracts: BTreeMap<String, (ContractInfo, u64)>,
+    /// Classes, keyed by class hash.
+    pub classes: BTreeMap<String, ClassInfo>,
+    /// Blocks, keyed by block timestamp.
+    pub blocks: BTreeMap<u64, BlockInfo>,
+    pub memecoins: Vec<(MemecoinCreatedEvent, u64)>,
+    /// Launches, keyed by memecoin address.
+    pub launches: BTreeMap<String, MemecoinLaunchedEvent>,
+    /// Fungible token transfers, keyed by event id.
+    pub transfers: BTreeMap<String, TokenTransfer>,
+    pub locks: Vec<LiquidityLock>,
+    /// Safety reports, keyed by memecoin address.
+    pub safety_reports: BTreeMap<String, SafetyReport>,
+    /// Pools, keyed by pool address.
+    pub pools: BTreeMap<String, Pool>,
+    /// Swaps, keyed by event id.
+    pub swaps: BTreeMap<String, SwapEvent>,
+    /// Candles, keyed by (memecoin address, interval, open time).
+    pub candles: BTreeMap<(String, String, u64), Candle>,
+    /// Trending scores, keyed by (memecoin address, window).
+    pub trending_scores: BTreeMap<(String, u64), TrendingScore>,
+    /// Portfolio snapshots, keyed by (wallet address, day).
+    pub portfolio_snapshots: BTreeMap<(String, u64), PortfolioSnapshot>,
+    /// First funding of the wallets, keyed by wallet address.
+    pub wallet_fundings: BTreeMap<String, TokenTransfer>,
+    pub sniper_reports: BTreeMap<String, SniperReport>,
+    /// Developer wallets, keyed by (memecoin, wallet, role).
+    pub developer_wallets: BTreeMap<(String, String, String), DeveloperWallet>,
+    /// Developer activities, keyed by transfer event id.
+    pub developer_activities: BTreeMap<String, DeveloperActivity>,
+    /// Market metrics, keyed by memecoin address.
+    pub market_metrics: BTreeMap<String, MarketMetrics>,
+    /// Failed events, keyed by failure id.
+    pub failed_events: BTreeMap<String, FailedEvent>,
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    data: AsyncRwLock<MemoryData>,
+    /// Number of token registrations still to fail.
+    token_failures: AsyncRwLock<u32>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of the stored data.
+    pub async fn data(&self) -> MemoryData {
+        self.data.read().await.clone()
+    }
+
+    /// The next `times` token registrations will return an error.
+    pub async fn fail_token_registrations(&self, times: u32) {
+        *self.token_failures.write().await = times;
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn register_mint(
+        &self,
+        contract_address: &str,
+        token_id_hex: &str,
+        info: &TokenMintInfo,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+        if let Some(t) = data
+            .tokens
+            .get_mut(&(contract_address.to_string(), token_id_hex.to_string()))
+        {
+            t.mint = Some(info.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn register_token(
+        &self,
+        token: &TokenInfo,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut failures = self.token_failures.write().await;
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(StorageError::DatabaseError(
+                "Injected token registration failure".to_string(),
+            ));
+        }
+
+        let mut data = self.data.write().await;
+        let key = (token.contract_address.clone(), token.token_id_hex.clone());
+
+        if data.tokens.contains_key(&key) {
+            return Err(StorageError::AlreadyExists(format!(
+                "token id = {}",
+                token.token_id_hex
+            )));
+        }
+
+        data.tokens.insert(
+            key,
+            StoredToken {
+                info: token.clone(),
+                mint: None,
+                block_timestamp,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn register_event(
+        &self,
+        event: &TokenEvent,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.events.contains_key(&event.event_id) {
+            return Err(StorageError::AlreadyExists(format!(
+                "event id = {}",
+                event.event_id
+            )));
+        }
+
+        data.events
+            .insert(event.event_id.clone(), (event.clone(), block_timestamp));
+
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        contract_address: &str,
+        token_id_hex: &str,
+    ) -> Result<TokenInfo, StorageError> {
+        self.data
+            .read()
+            .await
+            .tokens
+            .get(&(contract_address.to_string(), token_id_hex.to_string()))
+            .map(|t| t.info.clone())
+            .ok_or_else(|| StorageError::NotFound(format!("token id = {token_id_hex}")))
+    }
+
+    async fn get_tokens(
+        &self,
+        filter: &TokenFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<TokenInfo>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .tokens
+            .values()
+            .map(|t| &t.info)
+            .filter(|t| {
+                filter
+                    .contract_address
+                    .as_ref()
+                    .map_or(true, |a| *a == t.contract_address)
+                    && filter.owner.as_ref().map_or(true, |o| *o == t.owner)
+            })
+            .filter(|t| {
+                pagination
+                    .after
+                    .as_ref()
+                    .and_then(|c| c.token_key())
+                    .map_or(true, |key| {
+                        (t.contract_address.as_str(), t.token_id_hex.as_str()) > key
+                    })
+            })
+            .skip(pagination.offset as usize)
+            .take(pagination.limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_events(
+        &self,
+        filter: &EventFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<TokenEvent>, StorageError> {
+        let data = self.data.read().await;
+        let mut events: Vec<&TokenEvent> = data
+            .events
+            .values()
+            .map(|(e, _)| e)
+            .filter(|e| filter.matches(e))
+            .filter(|e| {
+                pagination
+                    .after
+                    .as_ref()
+                    .map_or(true, |c| c.precedes(e.timestamp, &e.event_id))
+            })
+            .collect();
+        // Events keyed by id, the order is stable for a same timestamp.
+        events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        Ok(events
+            .into_iter()
+            .skip(pagination.offset as usize)
+            .take(pagination.limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_contract_type(
+        &self,
+        contract_address: &str,
+    ) -> Result<ContractType, StorageError> {
+        match self.data.read().await.contracts.get(contract_address) {
+            Some((info, _)) => Ok(ContractType::from_str(&info.contract_type).unwrap()),
+            None => Err(StorageError::NotFound(format!(
+                "contract_address: {contract_address}"
+            ))),
+        }
+    }
+
+    async fn register_contract_info(
+        &self,
+        info: &ContractInfo,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.contracts.contains_key(&info.contract_address) {
+            return Err(StorageError::AlreadyExists(format!(
+                "contract addr = {}",
+                info.contract_address
+            )));
+        }
+
+        data.contracts.insert(
+            info.contract_address.clone(),
+            (info.clone(), block_timestamp),
+        );
+
+        Ok(())
+    }
+
+    async fn get_contract_info(
+        &self,
+        contract_address: &str,
+    ) -> Result<ContractInfo, StorageError> {
+        self.data
+            .read()
+            .await
+            .contracts
+            .get(contract_address)
+            .map(|(info, ts)| ContractInfo {
+                block_timestamp: *ts,
+                ..info.clone()
+            })
+            .ok_or_else(|| StorageError::NotFound(format!("contract_address: {contract_address}")))
+    }
+
+    async fn get_contract_infos(
+        &self,
+        pagination: &Pagination,
+    ) -> Result<Vec<ContractInfo>, StorageError> {
+        let data = self.data.read().await;
+        let mut contracts: Vec<&(ContractInfo, u64)> = data
+            .contracts
+            .values()
+            .filter(|(info, ts)| {
+                pagination
+                    .after
+                    .as_ref()
+                    .map_or(true, |c| c.precedes(*ts, &info.contract_address))
+            })
+            .collect();
+        // Contracts keyed by address, the order is stable for a same timestamp.
+        contracts.sort_by(|a, b| b.1.cmp(&a.1));
+
+        Ok(contracts
+            .into_iter()
+            .skip(pagination.offset as usize)
+            .take(pagination.limit as usize)
+            .map(|(info, ts)| ContractInfo {
+                block_timestamp: *ts,
+                ..info.clone()
+            })
+            .collect())
+    }
+
+    async fn get_class_info(&self, class_hash: &str) -> Result<ClassInfo, StorageError> {
+        self.data
+            .read()
+            .await
+            .classes
+            .get(class_hash)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("class_hash: {class_hash}")))
+    }
+
+    async fn register_class_info(
+        &self,
+        info: &ClassInfo,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.classes.contains_key(&info.class_hash) {
+            return Err(StorageError::AlreadyExists(format!(
+                "class hash = {}",
+                info.class_hash
+            )));
+        }
+
+        data.classes.insert(info.class_hash.clone(), info.clone());
+        Ok(())
+    }
+
+    async fn register_memecoin_created_event(
+        &self,
+        event: &MemecoinCreatedEvent,
+        block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .memecoins
+            .push((event.clone(), block_timestamp));
+
+        Ok(())
+    }
+
+    async fn register_memecoin_launched_event(
+        &self,
+        event: &MemecoinLaunchedEvent,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .launches
+            .insert(event.memecoin_address.clone(), event.clone());
+
+        Ok(())
+    }
+
+    async fn get_memecoin(&self, memecoin_address: &str) -> Result<MemecoinInfo, StorageError> {
+        let data = self.data.read().await;
+
+        data.memecoins
+            .iter()
+            .find(|(m, _)| m.memecoin_address == memecoin_address)
+            .map(|(m, ts)| {
+                MemecoinInfo::from_created(m, *ts, data.launches.get(memecoin_address).cloned())
+            })
+            .ok_or_else(|| StorageError::NotFound(format!("memecoin: {memecoin_address}")))
+    }
+
+    async fn get_memecoins(&self) -> Result<Vec<MemecoinInfo>, StorageError> {
+        let data = self.data.read().await;
+
+        let mut memecoins: Vec<MemecoinInfo> = data
+            .memecoins
+            .iter()
+            .map(|(m, ts)| {
+                MemecoinInfo::from_created(m, *ts, data.launches.get(&m.memecoin_address).cloned())
+            })
+            .collect();
+        memecoins.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| a.memecoin_address.cmp(&b.memecoin_address))
+        });
+
+        Ok(memecoins)
+    }
+
+    async fn get_filtered_memecoins(
+        &self,
+        filter: &MemecoinFilter,
+        pagination: &Pagination,
+    ) -> Result<Vec<MemecoinInfo>, StorageError> {
+        Ok(self
+            .get_memecoins()
+            .await?
+            .into_iter()
+            .filter(|m| filter.owner.as_ref().map_or(true, |o| *o == m.owner))
+            .filter(|m| {
+                pagination
+                    .after
+                    .as_ref()
+                    .map_or(true, |c| c.precedes(m.created_at, &m.memecoin_address))
+            })
+            .skip(pagination.offset as usize)
+            .take(pagination.limit as usize)
+            .collect())
+    }
+
+    async fn register_token_transfer(
+        &self,
+        transfer: &TokenTransfer,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.transfers.contains_key(&transfer.event_id) {
+            return Err(StorageError::AlreadyExists(format!(
+                "transfer event id = {}",
+                transfer.event_id
+            )));
+        }
+
+        data.transfers
+            .insert(transfer.event_id.clone(), transfer.clone());
+        Ok(())
+    }
+
+    async fn get_token_transfers(
+        &self,
+        token_address: &str,
+        to_block: Option<u64>,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        let mut transfers: Vec<TokenTransfer> = self
+            .data
+            .read()
+            .await
+            .transfers
+            .values()
+            .filter(|t| t.token_address == token_address)
+            .filter(|t| to_block.map_or(true, |b| t.block_number <= b))
+            .cloned()
+            .collect();
+        transfers.sort_by_key(|t| t.block_number);
+
+        Ok(transfers)
+    }
+
+    async fn register_liquidity_lock(
+        &self,
+        lock: &LiquidityLock,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        self.data.write().await.locks.push(lock.clone());
+        Ok(())
+    }
+
+    async fn get_liquidity_locks(
+        &self,
+        token_address: &str,
+    ) -> Result<Vec<LiquidityLock>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .locks
+            .iter()
+            .filter(|l| l.token_address == token_address)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_safety_report(&self, report: &SafetyReport) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .safety_reports
+            .insert(report.memecoin_address.clone(), report.clone());
+        Ok(())
+    }
+
+    async fn get_safety_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SafetyReport, StorageError> {
+        self.data
+            .read()
+            .await
+            .safety_reports
+            .get(memecoin_address)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("safety report: {memecoin_address}")))
+    }
+
+    async fn register_pool(&self, pool: &Pool, _block_timestamp: u64) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.pools.contains_key(&pool.pool_address) {
+            return Err(StorageError::AlreadyExists(format!(
+                "pool = {}",
+                pool.pool_address
+            )));
+        }
+
+        data.pools.insert(pool.pool_address.clone(), pool.clone());
+        Ok(())
+    }
+
+    async fn get_pools(&self) -> Result<Vec<Pool>, StorageError> {
+        Ok(self.data.read().await.pools.values().cloned().collect())
+    }
+
+    async fn register_swap(
+        &self,
+        swap: &SwapEvent,
+        _block_timestamp: u64,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.swaps.contains_key(&swap.event_id) {
+            return Err(StorageError::AlreadyExists(format!(
+                "swap event id = {}",
+                swap.event_id
+            )));
+        }
+
+        data.swaps.insert(swap.event_id.clone(), swap.clone());
+        Ok(())
+    }
+
+    async fn get_swaps(
+        &self,
+        pool_address: &str,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        let mut swaps: Vec<SwapEvent> = self
+            .data
+            .read()
+            .await
+            .swaps
+            .values()
+            .filter(|s| s.pool_address == pool_address)
+            .filter(|s| s.block_timestamp >= from_timestamp && s.block_timestamp <= to_timestamp)
+            .cloned()
+            .collect();
+        swaps.sort_by_key(|s| s.block_number);
+
+        Ok(swaps)
+    }
+
+    async fn get_last_swap(
+        &self,
+        pool_address: &str,
+        to_block_number: u64,
+    ) -> Result<SwapEvent, StorageError> {
+        self.data
+            .read()
+            .await
+            .swaps
+            .values()
+            .filter(|s| s.pool_address == pool_address && s.block_number <= to_block_number)
+            .max_by_key(|s| s.block_number)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("swap: {pool_address}")))
+    }
+
+    async fn get_block_swaps(&self, block_timestamp: u64) -> Result<Vec<SwapEvent>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .swaps
+            .values()
+            .filter(|s| s.block_timestamp == block_timestamp)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_candle(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        open_time: u64,
+    ) -> Result<Candle, StorageError> {
+        self.data
+            .read()
+            .await
+            .candles
+            .get(&(
+                memecoin_address.to_string(),
+                interval.to_string(),
+                open_time,
+            ))
+            .cloned()
+            .ok_or_else(|| {
+                StorageError::NotFound(format!("candle: {memecoin_address} {interval} {open_time}"))
+            })
+    }
+
+    async fn set_candle(&self, candle: &Candle) -> Result<(), StorageError> {
+        self.data.write().await.candles.insert(
+            (
+                candle.memecoin_address.clone(),
+                candle.interval.to_string(),
+                candle.open_time,
+            ),
+            candle.clone(),
+        );
+        Ok(())
+    }
+
+    async fn delete_candle(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        open_time: u64,
+    ) -> Result<(), StorageError> {
+        self.data.write().await.candles.remove(&(
+            memecoin_address.to_string(),
+            interval.to_string(),
+            open_time,
+        ));
+        Ok(())
+    }
+
+    async fn get_candles(
+        &self,
+        memecoin_address: &str,
+        interval: CandleInterval,
+        from_time: u64,
+        to_time: u64,
+    ) -> Result<Vec<Candle>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .candles
+            .values()
+            .filter(|c| c.memecoin_address == memecoin_address && c.interval == interval)
+            .filter(|c| c.open_time >= from_time && c.open_time <= to_time)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_trending_score(&self, score: &TrendingScore) -> Result<(), StorageError> {
+        self.data.write().await.trending_scores.insert(
+            (score.memecoin_address.clone(), score.window),
+            score.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get_trending_scores(
+        &self,
+        window: u64,
+        limit: u64,
+    ) -> Result<Vec<TrendingScore>, StorageError> {
+        let mut scores: Vec<TrendingScore> = self
+            .data
+            .read()
+            .await
+            .trending_scores
+            .values()
+            .filter(|s| s.window == window)
+            .cloned()
+            .collect();
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores.truncate(limit as usize);
+
+        Ok(scores)
+    }
+
+    async fn get_wallet_transfers(
+        &self,
+        wallet_address: &str,
+        to_timestamp: u64,
+    ) -> Result<Vec<TokenTransfer>, StorageError> {
+        let mut transfers: Vec<TokenTransfer> = self
+            .data
+            .read()
+            .await
+            .transfers
+            .values()
+            .filter(|t| t.from_address == wallet_address || t.to_address == wallet_address)
+            .filter(|t| t.block_timestamp <= to_timestamp)
+            .cloned()
+            .collect();
+        transfers.sort_by_key(|t| t.block_number);
+
+        Ok(transfers)
+    }
+
+    async fn get_wallet_swaps(
+        &self,
+        wallet_address: &str,
+        to_timestamp: u64,
+    ) -> Result<Vec<SwapEvent>, StorageError> {
+        let mut swaps: Vec<SwapEvent> = self
+            .data
+            .read()
+            .await
+            .swaps
+            .values()
+            .filter(|s| s.trader == wallet_address && s.block_timestamp <= to_timestamp)
+            .cloned()
+            .collect();
+        swaps.sort_by_key(|s| s.block_number);
+
+        Ok(swaps)
+    }
+
+    async fn get_active_wallets(
+        &self,
+        from_timestamp: u64,
+        to_timestamp: u64,
+    ) -> Result<Vec<String>, StorageError> {
+        let data = self.data.read().await;
+        let in_range = |ts: u64| ts >= from_timestamp && ts <= to_timestamp;
+
+        let mut wallets = BTreeSet::new();
+        for t in data
+            .transfers
+            .values()
+            .filter(|t| in_range(t.block_timestamp))
+        {
+            wallets.insert(t.from_address.clone());
+            wallets.insert(t.to_address.clone());
+        }
+        for s in data.swaps.values().filter(|s| {
+            in_range(s.block_timestamp)
+                && !s.trader.is_empty()
+                && data.pools.contains_key(&s.pool_address)
+        }) {
+            wallets.insert(s.trader.clone());
+        }
+
+        Ok(wallets.into_iter().collect())
+    }
+
+    async fn set_portfolio_snapshot(
+        &self,
+        snapshot: &PortfolioSnapshot,
+    ) -> Result<(), StorageError> {
+        self.data.write().await.portfolio_snapshots.insert(
+            (snapshot.wallet_address.clone(), snapshot.day),
+            snapshot.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get_portfolio_snapshots(
+        &self,
+        wallet_address: &str,
+        from_day: u64,
+        to_day: u64,
+    ) -> Result<Vec<PortfolioSnapshot>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .portfolio_snapshots
+            .values()
+            .filter(|s| s.wallet_address == wallet_address)
+            .filter(|s| s.day >= from_day && s.day <= to_day)
+            .cloned()
+            .collect())
+    }
+
+    async fn register_wallet_funding(&self, transfer: &TokenTransfer) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.wallet_fundings.contains_key(&transfer.to_address) {
+            return Err(StorageError::AlreadyExists(format!(
+                "wallet funding = {}",
+                transfer.to_address
+            )));
+        }
+
+        data.wallet_fundings
+            .insert(transfer.to_address.clone(), transfer.clone());
+        Ok(())
+    }
+
+    async fn get_wallet_funding(
+        &self,
+        wallet_address: &str,
+    ) -> Result<TokenTransfer, StorageError> {
+        self.data
+            .read()
+            .await
+            .wallet_fundings
+            .get(wallet_address)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("wallet funding: {wallet_address}")))
+    }
+
+    async fn set_sniper_report(&self, report: &SniperReport) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .sniper_reports
+            .insert(report.memecoin_address.clone(), report.clone());
+        Ok(())
+    }
+
+    async fn get_sniper_report(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<SniperReport, StorageError> {
+        self.data
+            .read()
+            .await
+            .sniper_reports
+            .get(memecoin_address)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("sniper report: {memecoin_address}")))
+    }
+
+    async fn register_developer_wallet(
+        &self,
+        wallet: &DeveloperWallet,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+        let key = (
+            wallet.memecoin_address.clone(),
+            wallet.wallet_address.clone(),
+            wallet.role.to_string(),
+        );
+
+        if data.developer_wallets.contains_key(&key) {
+            return Err(StorageError::AlreadyExists(format!(
+                "developer wallet = {:?}",
+                key
+            )));
+        }
+
+        data.developer_wallets.insert(key, wallet.clone());
+        Ok(())
+    }
+
+    async fn get_developer_wallets(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperWallet>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .developer_wallets
+            .values()
+            .filter(|w| w.memecoin_address == memecoin_address)
+            .cloned()
+            .collect())
+    }
+
+    async fn register_developer_activity(
+        &self,
+        activity: &DeveloperActivity,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        if data.developer_activities.contains_key(&activity.event_id) {
+            return Err(StorageError::AlreadyExists(format!(
+                "developer activity event id = {}",
+                activity.event_id
+            )));
+        }
+
+        data.developer_activities
+            .insert(activity.event_id.clone(), activity.clone());
+        Ok(())
+    }
+
+    async fn get_developer_activities(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<Vec<DeveloperActivity>, StorageError> {
+        let mut activities: Vec<DeveloperActivity> = self
+            .data
+            .read()
+            .await
+            .developer_activities
+            .values()
+            .filter(|a| a.memecoin_address == memecoin_address)
+            .cloned()
+            .collect();
+        activities.sort_by_key(|a| a.block_number);
+
+        Ok(activities)
+    }
+
+    async fn set_market_metrics(&self, metrics: &MarketMetrics) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .market_metrics
+            .insert(metrics.memecoin_address.clone(), metrics.clone());
+        Ok(())
+    }
+
+    async fn get_market_metrics(
+        &self,
+        memecoin_address: &str,
+    ) -> Result<MarketMetrics, StorageError> {
+        self.data
+            .read()
+            .await
+            .market_metrics
+            .get(memecoin_address)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("market metrics: {memecoin_address}")))
+    }
+
+    async fn get_top_market_metrics(&self, limit: u64) -> Result<Vec<MarketMetrics>, StorageError> {
+        let mut metrics: Vec<MarketMetrics> = self
+            .data
+            .read()
+            .await
+            .market_metrics
+            .values()
+            .cloned()
+            .collect();
+        metrics.sort_by(|a, b| b.market_cap_usd.total_cmp(&a.market_cap_usd));
+        metrics.truncate(limit as usize);
+
+        Ok(metrics)
+    }
+
+    async fn set_failed_event(&self, event: &FailedEvent) -> Result<(), StorageError> {
+        self.data
+            .write()
+            .await
+            .failed_events
+            .insert(event.failure_id.clone(), event.clone());
+        Ok(())
+    }
+
+    async fn get_failed_event(&self, failure_id: &str) -> Result<FailedEvent, StorageError> {
+        self.data
+            .read()
+            .await
+            .failed_events
+            .get(failure_id)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("failed event: {failure_id}")))
+    }
+
+    async fn get_failed_events(
+        &self,
+        status: Option<FailedEventStatus>,
+    ) -> Result<Vec<FailedEvent>, StorageError> {
+        let mut events: Vec<FailedEvent> = self
+            .data
+            .read()
+            .await
+            .failed_events
+            .values()
+            .filter(|e| status.map_or(true, |s| e.status == s))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.block_number);
+
+        Ok(events)
+    }
+
+    async fn set_block_info(
+        &self,
+        _block_number: u64,
+        block_timestamp: u64,
+        info: BlockInfo,
+    ) -> Result<(), StorageError> {
+        self.data.write().await.blocks.insert(block_timestamp, info);
+        Ok(())
+    }
+
+    async fn get_block_info(&self, block_number: u64) -> Result<BlockInfo, StorageError> {
+        self.data
+            .read()
+            .await
+            .blocks
+            .values()
+            .find(|b| b.block_number == block_number)
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(format!("block number {block_number}")))
+    }
+
+    async fn get_block(&self, block_number: u64) -> Result<(u64, BlockInfo), StorageError> {
+        self.data
+            .read()
+            .await
+            .blocks
+            .iter()
+            .find(|(_, b)| b.block_number == block_number)
+            .map(|(ts, info)| (*ts, info.clone()))
+            .ok_or_else(|| StorageError::NotFound(format!("block number {block_number}")))
+    }
+
+    async fn get_blocks(
+        &self,
+        pagination: &Pagination,
+    ) -> Result<Vec<(u64, BlockInfo)>, StorageError> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .blocks
+            .iter()
+            .rev()
+            .filter(|(ts, _)| {
+                pagination
+                    .after
+                    .as_ref()
+                    .map_or(true, |c| **ts < c.timestamp)
+            })
+            .skip(pagination.offset as usize)
+            .take(pagination.limit as usize)
+            .map(|(ts, info)| (*ts, info.clone()))
+            .collect())
+    }
+
+    async fn clean_block(
+        &self,
+        block_timestamp: u64,
+        _block_number: Option<u64>,
+    ) -> Result<(), StorageError> {
+        let mut data = self.data.write().await;
+
+        data.blocks.remove(&block_timestamp);
+        data.contracts.retain(|_, (_, ts)| *ts != block_timestamp);
+        data.tokens
+            .retain(|_, t| t.block_timestamp != block_timestamp);
+        data.events.retain(|_, (_, ts)| *ts != block_timestamp);
+        data.memecoins.retain(|(_, ts)| *ts != block_timestamp);
+        data.launches
+            .retain(|_, l| l.block_timestamp != block_timestamp);
+        data.transfers
+            .retain(|_, t| t.block_timestamp != block_timestamp);
+        data.locks.retain(|l| l.block_timestamp != block_timestamp);
+        data.pools
+            .retain(|_, p| p.block_timestamp != block_timestamp);
+        data.swaps
+            .retain(|_, s| s.block_timestamp != block_timestamp);
+        data.wallet_fundings
+            .retain(|_, t| t.block_timestamp != block_timestamp);
+        data.developer_wallets
+            .retain(|_, w| w.block_timestamp != block_timestamp);
+        data.developer_activities
+            .retain(|_, a| a.block_timestamp != block_timestamp);
+        data.failed_events.retain(|_, e| {
+            e.block_timestamp != block_timestamp || e.status == FailedEventStatus::Failed
+        });
+
+        Ok(())
+    }
+}